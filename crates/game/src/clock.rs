@@ -0,0 +1,341 @@
+//! 对局计时
+//!
+//! 支持包干、Fischer 加秒、日式读秒与加拿大读秒。时钟可以按队伍或按棋手计, 超时后的处理见 `TimeoutRule`。
+
+use std::time::Duration;
+
+use rustgo::Stone;
+use serde::{Deserialize, Serialize};
+
+use crate::{player::PlayerId, team::TeamId};
+
+/// 计时方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TimeControl {
+    /// 不计时
+    #[default]
+    Unlimited,
+    /// 包干: 用完 `main` 即超时
+    Absolute { main: Duration },
+    /// 每下一手加 `increment`
+    Fischer { main: Duration, increment: Duration },
+    /// 日式读秒: 基本时间用完后有 `periods` 次 `period`, 在一次读秒内落子则不消耗次数
+    ByoYomi {
+        main: Duration,
+        period: Duration,
+        periods: u32,
+    },
+    /// 加拿大读秒: 基本时间用完后每 `period` 内要下 `stones` 手
+    Canadian {
+        main: Duration,
+        period: Duration,
+        stones: u32,
+    },
+}
+
+impl TimeControl {
+    /// 读秒的时长与手数不能为 0
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            TimeControl::ByoYomi { period, .. } => !period.is_zero(),
+            TimeControl::Canadian { period, stones, .. } => !period.is_zero() && stones > 0,
+            _ => true,
+        }
+    }
+
+    /// GTP `time_settings` 的参数 (基本时间秒数, 读秒秒数, 读秒手数), 不计时时为 `None`
+    ///
+    /// GTP 只能表示加拿大读秒: 日式读秒按每次读秒 1 手近似, Fischer 按包干近似
+    pub fn gtp_time_settings(&self) -> Option<(u64, u64, u32)> {
+        match *self {
+            TimeControl::Unlimited => None,
+            TimeControl::Absolute { main } | TimeControl::Fischer { main, .. } => {
+                Some((main.as_secs(), 0, 0))
+            }
+            TimeControl::ByoYomi { main, period, .. } => {
+                Some((main.as_secs(), period.as_secs(), 1))
+            }
+            TimeControl::Canadian {
+                main,
+                period,
+                stones,
+            } => Some((main.as_secs(), period.as_secs(), stones)),
+        }
+    }
+}
+
+/// 时钟按队伍还是按棋手计
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClockScope {
+    /// 队伍内的棋手共用一个时钟
+    #[default]
+    Team,
+    Player,
+}
+
+/// 超时之后怎么办
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeoutRule {
+    /// 超时的队伍出局
+    #[default]
+    Forfeit,
+    /// 替超时的棋手 pass, 之后每一手都没有时间
+    AutoPass,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeSettings {
+    pub control: TimeControl,
+    pub scope: ClockScope,
+    pub on_timeout: TimeoutRule,
+}
+
+/// 一个时钟的剩余时间
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Clock {
+    control: TimeControl,
+    main_left: Duration,
+    /// 日式读秒为剩余次数, 加拿大读秒为本次读秒内还要下的手数
+    periods_left: u32,
+    /// 加拿大读秒本次读秒剩余的时间
+    period_left: Duration,
+    /// 已经进入读秒
+    overtime: bool,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl) -> Self {
+        let (main_left, periods_left, period_left) = match control {
+            TimeControl::Unlimited => (Duration::ZERO, 0, Duration::ZERO),
+            TimeControl::Absolute { main } | TimeControl::Fischer { main, .. } => {
+                (main, 0, Duration::ZERO)
+            }
+            TimeControl::ByoYomi {
+                main,
+                period,
+                periods,
+            } => (main, periods, period),
+            TimeControl::Canadian {
+                main,
+                period,
+                stones,
+            } => (main, stones, period),
+        };
+        Clock {
+            control,
+            main_left,
+            periods_left,
+            period_left,
+            overtime: main_left.is_zero(),
+        }
+    }
+
+    /// 这一手最多还能用多久, 不计时时为 `None`
+    pub fn remaining(&self) -> Option<Duration> {
+        match self.control {
+            TimeControl::Unlimited => None,
+            TimeControl::Absolute { .. } | TimeControl::Fischer { .. } => Some(self.main_left),
+            TimeControl::ByoYomi { period, .. } => {
+                Some(self.main_left + period * self.periods_left)
+            }
+            TimeControl::Canadian { .. } => Some(self.main_left + self.period_left),
+        }
+    }
+
+    /// 下完一手, 扣掉这一手用的时间; 超时时返回 `false`, 时钟归零
+    pub fn spend(&mut self, elapsed: Duration) -> bool {
+        if let Some(remaining) = self.remaining()
+            && elapsed > remaining
+        {
+            self.flag();
+            return false;
+        }
+
+        let overtime = elapsed.saturating_sub(self.main_left);
+        self.main_left = self.main_left.saturating_sub(elapsed);
+        if !overtime.is_zero() {
+            self.overtime = true;
+        }
+        match self.control {
+            TimeControl::Unlimited | TimeControl::Absolute { .. } => {}
+            TimeControl::Fischer { increment, .. } => self.main_left += increment,
+            TimeControl::ByoYomi { period, .. } => {
+                // 用满一次读秒才消耗次数
+                let used = overtime.as_nanos() / period.as_nanos().max(1);
+                self.periods_left -= used as u32;
+            }
+            TimeControl::Canadian { period, stones, .. } => {
+                if self.overtime {
+                    self.period_left -= overtime;
+                    self.periods_left -= 1;
+                    if self.periods_left == 0 {
+                        self.period_left = period;
+                        self.periods_left = stones;
+                    }
+                }
+            }
+        }
+        return true;
+    }
+
+    /// 时间用完
+    pub fn flag(&mut self) {
+        self.main_left = Duration::ZERO;
+        self.period_left = Duration::ZERO;
+        self.periods_left = 0;
+        self.overtime = true;
+    }
+
+    /// GTP `time_left` 的参数 (秒数, 手数)
+    ///
+    /// 基本时间内手数为 0; 读秒时为本次读秒的时间与要下的手数
+    pub fn gtp_time_left(&self) -> (u64, u32) {
+        if !self.overtime {
+            return (self.main_left.as_secs(), 0);
+        }
+        match self.control {
+            TimeControl::ByoYomi { period, .. } if self.periods_left > 0 => (period.as_secs(), 1),
+            TimeControl::Canadian { .. } => (self.period_left.as_secs(), self.periods_left),
+            _ => (0, 0),
+        }
+    }
+}
+
+/// 发给棋手的时钟状态
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockState {
+    pub team_id: TeamId,
+    /// 按棋手计时时为时钟所属的棋手, 按队伍计时时为 `None`
+    pub player_id: Option<PlayerId>,
+    pub stone: Stone,
+    /// 这一手最多还能用的毫秒数
+    pub remaining_ms: u64,
+    /// 见 `Clock::gtp_time_left`
+    pub gtp_time_left: (u64, u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(s: u64) -> Duration {
+        Duration::from_secs(s)
+    }
+
+    #[test]
+    fn absolute_and_fischer() {
+        let mut clock = Clock::new(TimeControl::Absolute { main: secs(10) });
+        assert!(clock.spend(secs(4)));
+        assert_eq!(clock.remaining(), Some(secs(6)));
+        assert_eq!(clock.gtp_time_left(), (6, 0));
+        assert!(!clock.spend(secs(7)));
+        assert_eq!(clock.remaining(), Some(Duration::ZERO));
+
+        let mut clock = Clock::new(TimeControl::Fischer {
+            main: secs(10),
+            increment: secs(5),
+        });
+        assert!(clock.spend(secs(8)));
+        assert_eq!(clock.remaining(), Some(secs(7)));
+        assert!(clock.spend(secs(1)));
+        assert_eq!(clock.remaining(), Some(secs(11)));
+
+        let mut clock = Clock::new(TimeControl::Unlimited);
+        assert!(clock.spend(secs(1000)));
+        assert_eq!(clock.remaining(), None);
+    }
+
+    #[test]
+    fn byo_yomi() {
+        let mut clock = Clock::new(TimeControl::ByoYomi {
+            main: secs(10),
+            period: secs(30),
+            periods: 3,
+        });
+        assert_eq!(clock.remaining(), Some(secs(100)));
+        // 用完基本时间, 在第一次读秒内落子
+        assert!(clock.spend(secs(25)));
+        assert_eq!(clock.remaining(), Some(secs(90)));
+        assert_eq!(clock.gtp_time_left(), (30, 1));
+        // 用掉一次读秒
+        assert!(clock.spend(secs(45)));
+        assert_eq!(clock.remaining(), Some(secs(60)));
+        assert!(!clock.spend(secs(61)));
+        assert_eq!(clock.gtp_time_left(), (0, 0));
+    }
+
+    #[test]
+    fn canadian() {
+        let mut clock = Clock::new(TimeControl::Canadian {
+            main: secs(10),
+            period: secs(60),
+            stones: 2,
+        });
+        assert_eq!(clock.gtp_time_left(), (10, 0));
+        assert!(clock.spend(secs(30)));
+        assert_eq!(clock.remaining(), Some(secs(40)));
+        assert_eq!(clock.gtp_time_left(), (40, 1));
+        // 下完一组, 读秒重新开始
+        assert!(clock.spend(secs(20)));
+        assert_eq!(clock.remaining(), Some(secs(60)));
+        assert_eq!(clock.gtp_time_left(), (60, 2));
+        assert!(!clock.spend(secs(61)));
+    }
+
+    #[test]
+    fn invalid_time_controls() {
+        assert!(TimeControl::Unlimited.is_valid());
+        assert!(
+            !TimeControl::Canadian {
+                main: secs(10),
+                period: secs(60),
+                stones: 0,
+            }
+            .is_valid()
+        );
+        assert!(
+            !TimeControl::Canadian {
+                main: secs(10),
+                period: Duration::ZERO,
+                stones: 5,
+            }
+            .is_valid()
+        );
+        assert!(
+            !TimeControl::ByoYomi {
+                main: secs(10),
+                period: Duration::ZERO,
+                periods: 3,
+            }
+            .is_valid()
+        );
+        assert!(
+            TimeControl::ByoYomi {
+                main: secs(10),
+                period: secs(30),
+                periods: 3,
+            }
+            .is_valid()
+        );
+    }
+
+    #[test]
+    fn gtp_time_settings() {
+        assert_eq!(TimeControl::Unlimited.gtp_time_settings(), None);
+        let control = TimeControl::ByoYomi {
+            main: secs(300),
+            period: secs(30),
+            periods: 5,
+        };
+        assert_eq!(control.gtp_time_settings(), Some((300, 30, 1)));
+        let control = TimeControl::Canadian {
+            main: secs(300),
+            period: secs(300),
+            stones: 25,
+        };
+        assert_eq!(control.gtp_time_settings(), Some((300, 300, 25)));
+    }
+}
@@ -1,41 +1,166 @@
+use std::fmt::Display;
+
 use rustgo::{Coord, Stone};
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    clock::{ClockState, TimeSettings},
     player::{PlayerId, PlayerInfo},
+    record::{GameResult, ScoringRule, TeamStatus},
     team::{TeamId, TeamInfo},
 };
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum Action {
     Move { stone: Stone, coord: Coord },
     Pass,
     Resign,
 }
 
+/// 对局拒绝一个动作的原因
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    /// 不是这个棋手的回合
+    NotYourTurn,
+    /// 棋子的颜色不是当前队伍的
+    WrongStone { expected: Stone },
+    /// 棋盘拒绝的着法, 如已有棋子、禁着点、打劫
+    Illegal(String),
+    /// 数子阶段不能落子或 pass
+    Scoring,
+    /// 对局暂停中
+    Paused,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveError::NotYourTurn => write!(f, "not your turn"),
+            MoveError::WrongStone { expected } => write!(f, "wrong stone, expected {expected:?}"),
+            MoveError::Illegal(e) => write!(f, "illegal move: {e}"),
+            MoveError::Scoring => write!(f, "game is in the scoring phase"),
+            MoveError::Paused => write!(f, "game is paused"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
+
+/// 开局信息, 棋手据此准备棋盘与引擎
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameStart {
+    pub size: usize,
+    pub rules: String,
+    pub komi: f64,
+    pub scoring: ScoringRule,
+    /// 第一队的让子, 开局前已经摆在棋盘上
+    pub handicap: Vec<Coord>,
+    /// 按加入对局的顺序, 队内的棋手按轮流的顺序
+    pub teams: Vec<TeamInfo>,
+    /// 从第一手开始的行棋顺序; 有让子时从第二队开始
+    pub turn_order: Vec<TeamId>,
+}
+
+impl GameStart {
+    /// 让子的棋子, 即第一队的棋子
+    pub fn handicap_stone(&self) -> Option<Stone> {
+        if self.handicap.is_empty() {
+            return None;
+        }
+        return self.teams.first().map(|t| t.stone());
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum ServerMessage {
-    GameStart(Vec<TeamInfo>),
+    /// 对局开始, 在其他消息之前发出
+    GameStart(GameStart),
     GameUpdate {
         cur_team: Option<TeamId>,
         cur_player: Option<PlayerId>,
         player_info: Option<Vec<PlayerInfo>>,
+        /// 不计时时为空
+        clocks: Vec<ClockState>,
     },
     PlayerMove {
         player_id: PlayerId,
         stone: Stone,
         coord: Coord,
     },
+    PlayerPass {
+        player_id: PlayerId,
+        stone: Stone,
+    },
+    PlayerResign {
+        player_id: PlayerId,
+        team_id: TeamId,
+    },
+    /// 队伍出局, 之后不再轮到它
+    TeamOut {
+        team_id: TeamId,
+        status: TeamStatus,
+    },
     PlayerChat {
         player_id: PlayerId,
         chat: String,
     },
+    /// 对局计时的方式, 不计时时不发送
+    TimeSettings(TimeSettings),
     GenMove(Stone),
+    /// 对局暂停, 时钟停止
+    GamePaused,
+    /// 对局继续, 之后会重新发出 `GenMove`
+    GameResumed,
+    /// 对局拒绝了棋手的动作; `retries_left` 为还能重试的次数, 不计次数的错误为 `None`
+    MoveRejected {
+        action: Action,
+        error: MoveError,
+        retries_left: Option<usize>,
+    },
+    /// 所有队伍都 pass 之后进入数子阶段, 内容为最初的死子
+    ScoringStart {
+        dead: Vec<Coord>,
+    },
+    /// 死子有变化或有队伍同意; 死子变化后之前的同意都作废
+    DeadStones {
+        dead: Vec<Coord>,
+        accepted: Vec<TeamId>,
+    },
+    /// 有棋手不同意死子, 继续对局
+    PlayResumed {
+        player_id: PlayerId,
+    },
     Error(String),
-    GameOver,
+    GameOver(GameResult),
 }
 
 #[derive(Clone, Debug)]
 pub enum PlayerMessage {
-    PlayerAction { player_id: PlayerId, action: Action },
-    PlayerChat { player_id: PlayerId, chat: String },
+    PlayerAction {
+        player_id: PlayerId,
+        action: Action,
+    },
+    PlayerChat {
+        player_id: PlayerId,
+        chat: String,
+    },
+    /// 数子阶段: 提出一组死子, 替换当前的死子; 每个点代表它所在的整块棋
+    ProposeDead {
+        player_id: PlayerId,
+        dead: Vec<Coord>,
+    },
+    /// 数子阶段: 切换 `coord` 所在的整块棋的死活
+    ToggleDead {
+        player_id: PlayerId,
+        coord: Coord,
+    },
+    /// 数子阶段: 同意当前的死子, 所有队伍都同意后按死子计分
+    AcceptScore {
+        player_id: PlayerId,
+    },
+    /// 数子阶段: 不同意, 继续对局
+    ResumePlay {
+        player_id: PlayerId,
+    },
 }
@@ -1,122 +1,497 @@
-use std::vec;
+use std::{
+    fmt::Display,
+    time::{SystemTime, UNIX_EPOCH},
+    vec,
+};
 
-use rustgo::{Stone, board::Board};
-use tokio::sync::mpsc::{self, Receiver, Sender};
+use rustgo::{
+    Coord, Stone,
+    board::{Board, GoBoard},
+    gtp, score,
+    sgf::MAX_COLORS,
+};
+use tokio::{
+    sync::mpsc::{self, Receiver, Sender, UnboundedSender},
+    task::JoinHandle,
+    time::{Instant, sleep_until},
+};
 
 use crate::{
-    Action, PlayerMessage, ServerMessage,
-    player::{PlayerHandle, PlayerId, PlayerTrait},
+    Action, GameStart, MoveError, PlayerMessage, ServerMessage,
+    clock::{Clock, ClockScope, ClockState, TimeControl, TimeSettings, TimeoutRule},
+    handle::{GameCommand, GameHandle, GameSnapshot, TeamSnapshot},
+    player::{PlayerHandle, PlayerId, PlayerInfo, PlayerTrait},
+    record::{
+        ActionRecord, ChatRecord, EndReason, GameRecord, GameResult, ScoringRule, TeamScore,
+        TeamStatus,
+    },
     team::{TeamHandle, TeamId},
 };
 
-pub struct Game {
-    board: Board,
+/// 默认的规则名, 记录在 `GameRecord` 中
+pub const DEFAULT_RULES: &str = "chinese";
+pub const DEFAULT_KOMI: f64 = 7.5;
+
+/// 对局设置有误, 由 `GameBuilder` 返回
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GameConfigError {
+    /// 棋盘小于 2, 或超过棋盘实现的 `GoBoard::MAX_SIZE`
+    InvalidSize(usize),
+    /// 棋手不能在这么大的棋盘上对弈, 见 `PlayerTrait::max_board_size`
+    SizeUnsupported(PlayerId),
+    /// 队伍少于 2 个
+    TooFewTeams(usize),
+    DuplicateTeam(TeamId),
+    /// 棋子不在 `1..=MAX_COLORS` 内
+    InvalidStone(Stone),
+    DuplicateStone(Stone),
+    UnknownTeam(TeamId),
+    DuplicatePlayer(PlayerId),
+    /// 队伍没有棋手
+    EmptyTeam(TeamId),
+    /// 这个大小的棋盘不能让这么多子
+    InvalidHandicap(usize),
+    /// 读秒的时长或手数为 0, 见 `TimeControl::is_valid`
+    InvalidTimeControl(TimeControl),
+}
+
+impl Display for GameConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GameConfigError::InvalidSize(size) => write!(f, "invalid board size: {size}"),
+            GameConfigError::SizeUnsupported(id) => {
+                write!(f, "player cannot play on this board size: {id:?}")
+            }
+            GameConfigError::TooFewTeams(n) => write!(f, "need at least 2 teams, got {n}"),
+            GameConfigError::DuplicateTeam(id) => write!(f, "team_id already exist: {id:?}"),
+            GameConfigError::InvalidStone(stone) => {
+                write!(f, "stone out of range: {}", stone.as_usize())
+            }
+            GameConfigError::DuplicateStone(stone) => {
+                write!(f, "stone already exist: {}", stone.as_usize())
+            }
+            GameConfigError::UnknownTeam(id) => write!(f, "team_id not exist: {id:?}"),
+            GameConfigError::DuplicatePlayer(id) => write!(f, "player_id already exist: {id:?}"),
+            GameConfigError::EmptyTeam(id) => write!(f, "team has no player: {id:?}"),
+            GameConfigError::InvalidHandicap(n) => write!(f, "invalid handicap: {n}"),
+            GameConfigError::InvalidTimeControl(control) => {
+                write!(f, "invalid time control: {control:?}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GameConfigError {}
+
+/// 非法着法次数用完之后怎么办
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IllegalMoveAction {
+    /// 队伍被判负出局
+    #[default]
+    Forfeit,
+    /// 替棋手 pass
+    Pass,
+}
+
+/// 棋手下出非法着法 (或用错棋子) 时的处理
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IllegalMovePolicy {
+    /// 每一手允许重试的次数
+    pub max_retries: usize,
+    pub then: IllegalMoveAction,
+}
+
+impl Default for IllegalMovePolicy {
+    fn default() -> Self {
+        IllegalMovePolicy {
+            max_retries: 3,
+            then: IllegalMoveAction::Forfeit,
+        }
+    }
+}
+
+/// 数子阶段的状态
+struct Scoring {
+    /// 按 `(y, x)` 排序
+    dead: Vec<Coord>,
+    /// 与 `team_handles` 一一对应
+    accepted: Vec<bool>,
+}
+
+pub struct Game<B: GoBoard = Board> {
+    board: B,
 
     uplink_rx: Receiver<PlayerMessage>,
+    /// 留一个 sender 用于创建 `GameHandle`
+    control_tx: Sender<GameCommand>,
+    control_rx: Receiver<GameCommand>,
+    /// 旁观者, 断开的旁观者在广播时移除
+    observers: Vec<UnboundedSender<ServerMessage>>,
 
     team_handles: Vec<TeamHandle>,
     cur_team_index: usize,
     cur_player_index: Vec<usize>,
+    /// 与 `team_handles` 一一对应
+    team_status: Vec<TeamStatus>,
+    /// 出局队伍的下标, 按出局的先后
+    out_order: Vec<usize>,
+
+    /// 加给第一队以外的每一队
+    komi: f64,
+    /// 连续 pass 的次数, 等于仍在对局中的队伍数时对局结束
+    consecutive_passes: usize,
+    illegal_move_policy: IllegalMovePolicy,
+    /// 当前这一手已经下过的非法着法
+    illegal_moves: usize,
+    scoring_rule: ScoringRule,
+    /// 数子阶段时为 `Some`
+    scoring: Option<Scoring>,
+
+    time: TimeSettings,
+    /// `clocks[team_index][player_index]`, 按队伍计时时每队只有一个时钟
+    clocks: Vec<Vec<Clock>>,
+    /// 当前这一手开始的时刻
+    turn_start: Instant,
+    /// 暂停中时为暂停的时刻
+    paused_at: Option<Instant>,
+
+    record: GameRecord,
+    /// 对局开始的时刻, 用于计算 `GameRecord` 中的时间戳
+    start: Instant,
 }
 
 pub struct GameBuilder {
     size: usize,
+    rules: String,
+    komi: f64,
+    handicap: usize,
+    scoring_rule: ScoringRule,
+    illegal_move_policy: IllegalMovePolicy,
+    time: TimeSettings,
     uplink_tx: Sender<PlayerMessage>,
     uplink_rx: Receiver<PlayerMessage>,
     team_handles: Vec<TeamHandle>,
 }
 
-// TODO actor model???
 impl GameBuilder {
     pub fn new(size: usize) -> Self {
         let (uplink_tx, uplink_rx) = mpsc::channel(1024);
         Self {
             size,
+            rules: DEFAULT_RULES.to_string(),
+            komi: DEFAULT_KOMI,
+            handicap: 0,
+            scoring_rule: ScoringRule::Area,
+            illegal_move_policy: IllegalMovePolicy::default(),
+            time: TimeSettings::default(),
             uplink_tx,
             uplink_rx,
             team_handles: vec![],
         }
     }
 
-    pub fn add_team(&mut self, team_id: TeamId, stone: Stone) {
+    /// 规则名, 只用于记录
+    pub fn set_rules(&mut self, rules: &str) {
+        self.rules = rules.to_string();
+    }
+
+    pub fn set_komi(&mut self, komi: f64) {
+        self.komi = komi;
+    }
+
+    /// 第一队的让子数, 按 GTP `fixed_handicap` 的位置摆放; 有让子时从第二队开始行棋
+    pub fn set_handicap(&mut self, stones: usize) {
+        self.handicap = stones;
+    }
+
+    /// 计分方法, 默认数子法
+    pub fn set_scoring_rule(&mut self, rule: ScoringRule) {
+        self.scoring_rule = rule;
+    }
+
+    pub fn set_illegal_move_policy(&mut self, policy: IllegalMovePolicy) {
+        self.illegal_move_policy = policy;
+    }
+
+    /// 计时方式, 默认不计时
+    pub fn set_time_settings(&mut self, time: TimeSettings) {
+        self.time = time;
+    }
+
+    /// 添加队伍, 队伍按添加的顺序轮流行棋
+    pub fn add_team(&mut self, team_id: TeamId, stone: Stone) -> Result<(), GameConfigError> {
         if self.team_handles.iter().any(|t| t.team_id == team_id) {
-            panic!("team_id already exist: {:?}", team_id);
+            return Err(GameConfigError::DuplicateTeam(team_id));
+        }
+        if stone == Stone::VOID || stone.as_usize() > MAX_COLORS {
+            return Err(GameConfigError::InvalidStone(stone));
         }
         if self.team_handles.iter().any(|t| t.stone == stone) {
-            panic!("stone already exist: {:?}", stone);
+            return Err(GameConfigError::DuplicateStone(stone));
         }
 
         let team_handle = TeamHandle::new(team_id, stone, vec![]);
         self.team_handles.push(team_handle);
+        return Ok(());
     }
 
-    pub fn add_player(&mut self, team_id: TeamId, player: impl PlayerTrait) {
-        // find team
-        let Some(team_idx) = self
-            .team_handles
-            .iter()
-            .enumerate()
-            .find_map(|(team_idx, t)| {
-                if t.team_id == team_id {
-                    Some(team_idx)
-                } else {
-                    None
-                }
-            })
-        else {
-            panic!("team_id not exist: {:?}", team_id);
+    /// 添加棋手并启动它, 队内的棋手按添加的顺序轮流行棋
+    pub fn add_player(
+        &mut self,
+        team_id: TeamId,
+        player: impl PlayerTrait,
+    ) -> Result<(), GameConfigError> {
+        let Some(team_idx) = self.team_handles.iter().position(|t| t.team_id == team_id) else {
+            return Err(GameConfigError::UnknownTeam(team_id));
         };
 
-        // assert player not in any team
+        // player 不能已经在任何一个队伍中
         let player_id = player.player_id();
         for team in &self.team_handles {
             if team.players.iter().any(|p| p.player_id == player_id) {
-                panic!("player_id already exist: {:?}", player_id);
+                return Err(GameConfigError::DuplicatePlayer(player_id));
             }
         }
+        if self.size > player.max_board_size() {
+            return Err(GameConfigError::SizeUnsupported(player_id));
+        }
 
+        let player_name = player.player_name();
         let (downlink_tx, downlink_rx) = mpsc::channel(32);
         player.run(self.uplink_tx.clone(), downlink_rx);
 
-        let player_handle = PlayerHandle::new(player_id, downlink_tx);
+        let player_handle = PlayerHandle::new(player_id, player_name, downlink_tx);
         self.team_handles[team_idx].players.push(player_handle);
+        return Ok(());
+    }
+
+    /// 检查棋盘大小、队伍数、每队的棋手、计时方式以及让子
+    ///
+    /// 棋盘大小受棋盘实现 `B` 的限制; 棋手自己的限制在 `add_player` 时检查
+    fn validate<B: GoBoard>(&self) -> Result<Option<Vec<Coord>>, GameConfigError> {
+        if self.size < 2 || self.size > B::MAX_SIZE {
+            return Err(GameConfigError::InvalidSize(self.size));
+        }
+        if self.team_handles.len() < 2 {
+            return Err(GameConfigError::TooFewTeams(self.team_handles.len()));
+        }
+        if let Some(team) = self.team_handles.iter().find(|t| t.players.is_empty()) {
+            return Err(GameConfigError::EmptyTeam(team.team_id));
+        }
+        if !self.time.control.is_valid() {
+            return Err(GameConfigError::InvalidTimeControl(self.time.control));
+        }
+        if self.handicap == 0 {
+            return Ok(None);
+        }
+        match gtp::handicap_points(self.size, self.handicap) {
+            Some(points) => return Ok(Some(points)),
+            None => return Err(GameConfigError::InvalidHandicap(self.handicap)),
+        }
+    }
+
+    pub fn build(self) -> Result<Game, GameConfigError> {
+        self.build_with_board()
     }
 
-    pub fn build(self) -> Game {
+    /// 使用其他 `GoBoard` 实现作为对局的棋盘
+    pub fn build_with_board<B: GoBoard>(self) -> Result<Game<B>, GameConfigError> {
+        let handicap = self.validate::<B>()?;
         // drop builder's sender so the game loop can exit once all players stop.
         drop(self.uplink_tx);
-        Game::new(self.size, self.uplink_rx, self.team_handles)
+        let mut game = Game::new(self.size, self.uplink_rx, self.team_handles);
+        if let Some(points) = handicap {
+            game.place_handicap(points);
+        }
+        game.record.rules = self.rules;
+        game.komi = self.komi;
+        game.record.komi = self.komi;
+        game.scoring_rule = self.scoring_rule;
+        game.illegal_move_policy = self.illegal_move_policy;
+        game.record.scoring = self.scoring_rule;
+        game.set_time_settings(self.time);
+        Ok(game)
     }
 }
 
-impl Game {
-    pub fn new(
+impl<B: GoBoard> Game<B> {
+    pub(crate) fn new(
         size: usize,
         uplink_rx: Receiver<PlayerMessage>,
         team_handles: Vec<TeamHandle>,
     ) -> Self {
         let len = team_handles.len();
+        let team_infos = team_handles.iter().map(|t| t.team_info()).collect();
+        let mut record = GameRecord::new(DEFAULT_RULES, size, team_infos, 0);
+        record.komi = DEFAULT_KOMI;
+        let (control_tx, control_rx) = mpsc::channel(32);
         Self {
-            board: Board::new(size),
+            board: B::new(size),
             uplink_rx: uplink_rx,
+            control_tx,
+            control_rx,
+            observers: vec![],
             team_handles: team_handles,
             cur_team_index: 0,
             cur_player_index: vec![0; len],
+            team_status: vec![TeamStatus::Active; len],
+            out_order: vec![],
+            komi: DEFAULT_KOMI,
+            consecutive_passes: 0,
+            illegal_move_policy: IllegalMovePolicy::default(),
+            illegal_moves: 0,
+            scoring_rule: ScoringRule::Area,
+            scoring: None,
+            time: TimeSettings::default(),
+            clocks: vec![vec![]; len],
+            turn_start: Instant::now(),
+            paused_at: None,
+            record,
+            start: Instant::now(),
+        }
+    }
+
+    /// 设置计时方式并重置所有时钟
+    pub fn set_time_settings(&mut self, time: TimeSettings) {
+        self.time = time;
+        self.clocks = self
+            .team_handles
+            .iter()
+            .map(|t| {
+                let n = match time.scope {
+                    ClockScope::Team => 1,
+                    ClockScope::Player => t.player_nums(),
+                };
+                vec![Clock::new(time.control); n]
+            })
+            .collect();
+    }
+
+    /// 摆上第一队的让子, 由第二队开始行棋
+    fn place_handicap(&mut self, points: Vec<Coord>) {
+        let stone = self.team_handles[0].stone();
+        for &coord in &points {
+            self.board.place_stone(coord, stone).unwrap();
         }
+        self.record.handicap = points;
+        self.cur_team_index = 1 % self.team_handles.len();
+    }
+
+    /// 控制这个对局的句柄, 在 `run` 运行时生效; `run` 返回之后句柄的操作都返回 `GameEnded`
+    pub fn handle(&self) -> GameHandle {
+        GameHandle::new(self.control_tx.clone())
+    }
+
+    /// 在新的任务中运行对局
+    pub fn spawn(mut self) -> (GameHandle, JoinHandle<GameRecord>)
+    where
+        B: Send + 'static,
+    {
+        let handle = self.handle();
+        let task = tokio::spawn(async move { self.run().await });
+        return (handle, task);
     }
 
     pub fn size(&self) -> usize {
         self.board.size()
     }
 
-    pub fn board(&self) -> &[Stone] {
-        self.board.board_array()
+    pub fn board(&self) -> &B {
+        &self.board
+    }
+
+    /// 到目前为止的对局记录
+    pub fn record(&self) -> &GameRecord {
+        &self.record
+    }
+
+    /// 对局结束后的结果
+    pub fn result(&self) -> Option<&GameResult> {
+        self.record.result.as_ref()
+    }
+
+    pub fn team_status(&self, team_id: TeamId) -> Option<TeamStatus> {
+        let index = self
+            .team_handles
+            .iter()
+            .position(|t| t.team_id == team_id)?;
+        return Some(self.team_status[index]);
+    }
+
+    fn clock_index(&self, player_index: usize) -> usize {
+        match self.time.scope {
+            ClockScope::Team => 0,
+            ClockScope::Player => player_index,
+        }
+    }
+
+    /// 棋手所用的时钟
+    fn clock(&self, team_index: usize, player_index: usize) -> &Clock {
+        &self.clocks[team_index][self.clock_index(player_index)]
+    }
+
+    fn cur_clock_mut(&mut self) -> &mut Clock {
+        let index = self.clock_index(self.cur_player_index[self.cur_team_index]);
+        &mut self.clocks[self.cur_team_index][index]
+    }
+
+    /// 当前棋手超时的时刻, 不计时、暂停中或在数子阶段时为 `None`
+    fn deadline(&self) -> Option<Instant> {
+        if self.scoring.is_some() || self.paused_at.is_some() {
+            return None;
+        }
+        let team_index = self.cur_team_index;
+        let remaining = self
+            .clock(team_index, self.cur_player_index[team_index])
+            .remaining()?;
+        return Some(self.turn_start + remaining);
+    }
+
+    /// 当前棋手已经超时 (但超时还没有处理)
+    fn flagged(&self) -> bool {
+        self.deadline().is_some_and(|d| Instant::now() > d)
+    }
+
+    /// 所有时钟的状态
+    fn clock_states(&self) -> Vec<ClockState> {
+        if self.time.control == TimeControl::Unlimited {
+            return vec![];
+        }
+        let mut states = vec![];
+        for (team_index, team) in self.team_handles.iter().enumerate() {
+            for (i, clock) in self.clocks[team_index].iter().enumerate() {
+                let player_id = match self.time.scope {
+                    ClockScope::Team => None,
+                    ClockScope::Player => Some(team.player_id(i)),
+                };
+                states.push(ClockState {
+                    team_id: team.team_id(),
+                    player_id,
+                    stone: team.stone(),
+                    remaining_ms: clock.remaining().unwrap_or_default().as_millis() as u64,
+                    gtp_time_left: clock.gtp_time_left(),
+                });
+            }
+        }
+        return states;
+    }
+
+    fn active_teams(&self) -> usize {
+        self.team_status
+            .iter()
+            .filter(|&&s| s == TeamStatus::Active)
+            .count()
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64
     }
 
     async fn broadcast(&mut self, msg: ServerMessage) {
         for team in &mut self.team_handles {
             team.broadcast(msg.clone()).await;
         }
+        self.observers.retain(|o| o.send(msg.clone()).is_ok());
     }
 
     fn cur_stone(&self) -> Stone {
@@ -129,6 +504,13 @@ impl Game {
         self.team_handles[self.cur_team_index].team_id()
     }
 
+    /// 棋手所在的队伍
+    fn team_index(&self, player_id: PlayerId) -> Option<usize> {
+        self.team_handles
+            .iter()
+            .position(|t| t.players.iter().any(|p| p.player_id == player_id))
+    }
+
     async fn send(&mut self, msg: ServerMessage) {
         let cur_team_index = self.cur_team_index;
         let cur_team = &mut self.team_handles[cur_team_index];
@@ -137,81 +519,1816 @@ impl Game {
             .await;
     }
 
+    /// 发给任意一个棋手
+    async fn send_to(&mut self, player_id: PlayerId, msg: ServerMessage) {
+        for team in &self.team_handles {
+            if let Some(player) = team.players.iter().find(|p| p.player_id == player_id) {
+                player.send(msg).await;
+                return;
+            }
+        }
+    }
+
+    /// 要求当前棋手落子; 暂停中不发, 继续对局时再发
     async fn genmove(&mut self) {
+        if self.paused_at.is_some() {
+            return;
+        }
         self.send(ServerMessage::GenMove(self.cur_stone())).await;
     }
 
-    pub async fn run(&mut self) {
-        // 广播开局信息
-        // TODO 开局计算出 team_infos
-        // 开局信息也应该包括 board.size, 各个player收到size之后才能准备界面、init engine
-        // self.broadcast(ServerMessage::GameStart(self.team_infos.clone())).await;
+    fn push_action(&mut self, player_id: PlayerId, team_index: usize, action: Action) {
+        let team = &self.team_handles[team_index];
+        let player_index = team
+            .players
+            .iter()
+            .position(|p| p.player_id == player_id)
+            .unwrap_or(0);
+        let time_left_ms = self
+            .clock(team_index, player_index)
+            .remaining()
+            .map(|d| d.as_millis() as u64);
+        self.record.actions.push(ActionRecord {
+            player_id,
+            team_id: team.team_id(),
+            action,
+            timestamp_ms: self.elapsed_ms(),
+            time_left_ms,
+        });
+    }
+
+    /// 轮到下一队; 当前队伍内部也轮到下一个棋手
+    fn advance_turn(&mut self) {
+        let cur_team_index = self.cur_team_index;
+        // 暂停中轮换时从暂停的时刻算起, 继续对局时再加上暂停的时间
+        self.turn_start = self.paused_at.unwrap_or_else(Instant::now);
+        self.illegal_moves = 0;
+
+        // advance player index for cur_team
+        let new_player_index = self.cur_player_index[cur_team_index] + 1;
+        if new_player_index == self.team_handles[cur_team_index].player_nums() {
+            self.cur_player_index[cur_team_index] = 0;
+        } else {
+            self.cur_player_index[cur_team_index] = new_player_index;
+        }
+
+        // advance cur_team_index, 跳过已经出局的队伍
+        loop {
+            self.cur_team_index += 1;
+            if self.cur_team_index == self.team_handles.len() {
+                self.cur_team_index = 0;
+            }
+            if self.team_status[self.cur_team_index] == TeamStatus::Active
+                || self.cur_team_index == cur_team_index
+            {
+                break;
+            }
+        }
+    }
+
+    /// 队伍出局; 返回对局是否因此结束 (只剩一个队伍)
+    async fn drop_team(&mut self, team_index: usize, status: TeamStatus) -> bool {
+        self.team_status[team_index] = status;
+        self.out_order.push(team_index);
+        let team_id = self.team_handles[team_index].team_id();
+        self.broadcast(ServerMessage::TeamOut { team_id, status })
+            .await;
+        return self.active_teams() <= 1;
+    }
+
+    /// 下过棋但棋子已经全部被提走的队伍
+    fn captured_teams(&self) -> Vec<usize> {
+        let size = self.board.size();
+        return (0..self.team_handles.len())
+            .filter(|&i| self.team_status[i] == TeamStatus::Active)
+            .filter(|&i| {
+                let team_id = self.team_handles[i].team_id();
+                self.record
+                    .actions
+                    .iter()
+                    .any(|a| a.team_id == team_id && matches!(a.action, Action::Move { .. }))
+            })
+            .filter(|&i| {
+                let stone = self.team_handles[i].stone();
+                !(0..size * size)
+                    .any(|idx| self.board.stone(Coord::new(idx % size, idx / size)) == stone)
+            })
+            .collect();
+    }
+
+    fn player_info(&self) -> Vec<PlayerInfo> {
+        let mut infos = vec![];
+        for (team_index, team) in self.team_handles.iter().enumerate() {
+            for (i, player) in team.players.iter().enumerate() {
+                let time_left = self.clock(team_index, i).remaining().unwrap_or_default();
+                infos.push(PlayerInfo {
+                    player_id: player.player_id,
+                    team_id: team.team_id(),
+                    player_name: player.player_name.clone(),
+                    eaten_stones: self.board.captures(team.stone()),
+                    time_left: time_left.as_millis() as usize,
+                });
+            }
+        }
+        return infos;
+    }
+
+    async fn broadcast_update(&mut self) {
+        self.broadcast(ServerMessage::GameUpdate {
+            cur_team: Some(self.cur_team_id()),
+            cur_player: Some(self.cur_player_id()),
+            player_info: Some(self.player_info()),
+            clocks: self.clock_states(),
+        })
+        .await;
+    }
+
+    /// 按 `scoring_rule` 计分, 贴目加给第一队以外的每一队; 出局的队伍也计算得分, 但不参与名次比较
+    fn scores(&self) -> Vec<TeamScore> {
+        let max_stone = self
+            .team_handles
+            .iter()
+            .map(|t| t.stone())
+            .max_by_key(|s| s.as_usize())
+            .unwrap_or(Stone::WHITE);
+        let dead = self.dead_stones();
+        let area = match self.scoring_rule {
+            ScoringRule::Area => score::area_score(&self.board, max_stone, &dead),
+            ScoringRule::Territory => score::territory_score(&self.board, max_stone, &dead),
+        };
+        return self
+            .team_handles
+            .iter()
+            .enumerate()
+            .map(|(i, t)| TeamScore {
+                team_id: t.team_id(),
+                score: area[t.stone().as_usize()] as f64 + if i == 0 { 0.0 } else { self.komi },
+                status: self.team_status[i],
+            })
+            .collect();
+    }
+
+    /// 名次与胜者
+    ///
+    /// 仍在对局中的队伍按得分从高到低排在前面, 之后是出局的队伍, 越晚出局越靠前。
+    /// 只剩一个队伍时它获胜, 否则得分最高的队伍获胜, 最高分有多个队伍时没有胜者
+    fn ranking(&self, scores: &[TeamScore]) -> (Vec<TeamId>, Option<TeamId>) {
+        let mut active: Vec<&TeamScore> = scores
+            .iter()
+            .filter(|s| s.status == TeamStatus::Active)
+            .collect();
+        active.sort_by(|a, b| b.score.total_cmp(&a.score));
+        let winner = match active.as_slice() {
+            [] => None,
+            [only] => Some(only.team_id),
+            [first, second, ..] => (first.score > second.score).then_some(first.team_id),
+        };
+
+        let mut ranking: Vec<TeamId> = active.iter().map(|s| s.team_id).collect();
+        ranking.extend(
+            self.out_order
+                .iter()
+                .rev()
+                .map(|&i| self.team_handles[i].team_id()),
+        );
+        return (ranking, winner);
+    }
+
+    /// 数子并结束对局
+    async fn end_game(&mut self, reason: EndReason) {
+        let scores = self.scores();
+        let (ranking, winner) = self.ranking(&scores);
+        let result = GameResult {
+            winner,
+            reason,
+            scores,
+            ranking,
+            dead: self.dead_stones(),
+            note: None,
+        };
+        self.finish(result).await;
+    }
+
+    /// 记录并广播结果, 之后 `run` 返回
+    async fn finish(&mut self, result: GameResult) {
+        self.record.result = Some(result.clone());
+        self.broadcast(ServerMessage::GameOver(result)).await;
+    }
+
+    /// 当前棋手 pass; 返回对局是否结束
+    async fn pass(&mut self, player_id: PlayerId) -> bool {
+        self.push_action(player_id, self.cur_team_index, Action::Pass);
+        self.consecutive_passes += 1;
+        let stone = self.cur_stone();
+        self.broadcast(ServerMessage::PlayerPass { player_id, stone })
+            .await;
+
+        if self.consecutive_passes >= self.active_teams() {
+            self.start_scoring().await;
+            return false;
+        }
+
+        self.advance_turn();
+        self.broadcast_update().await;
+        self.genmove().await;
+        return false;
+    }
+
+    fn dead_stones(&self) -> Vec<Coord> {
+        self.scoring
+            .as_ref()
+            .map_or_else(Vec::new, |s| s.dead.clone())
+    }
+
+    /// `coord` 所在的整块棋, 空点时为空
+    fn group(&self, coord: Coord) -> Vec<Coord> {
+        let size = self.board.size();
+        if coord.x >= size || coord.y >= size {
+            return vec![];
+        }
+        let stone = self.board.stone(coord);
+        if stone == Stone::VOID {
+            return vec![];
+        }
+        let mut group = vec![coord];
+        let mut stack = vec![coord];
+        while let Some(c) = stack.pop() {
+            let neighbors = [
+                (c.x > 0).then(|| Coord::new(c.x - 1, c.y)),
+                (c.x + 1 < size).then(|| Coord::new(c.x + 1, c.y)),
+                (c.y > 0).then(|| Coord::new(c.x, c.y - 1)),
+                (c.y + 1 < size).then(|| Coord::new(c.x, c.y + 1)),
+            ];
+            for n in neighbors.into_iter().flatten() {
+                if self.board.stone(n) == stone && !group.contains(&n) {
+                    group.push(n);
+                    stack.push(n);
+                }
+            }
+        }
+        return group;
+    }
+
+    /// 所有队伍都 pass, 进入数子阶段
+    async fn start_scoring(&mut self) {
+        self.scoring = Some(Scoring {
+            dead: vec![],
+            accepted: vec![false; self.team_handles.len()],
+        });
+        self.broadcast(ServerMessage::ScoringStart { dead: vec![] })
+            .await;
+    }
+
+    /// 死子变化, 之前的同意都作废
+    async fn set_dead(&mut self, mut dead: Vec<Coord>) {
+        dead.sort_by_key(|c| (c.y, c.x));
+        dead.dedup();
+        let Some(scoring) = &mut self.scoring else {
+            return;
+        };
+        if scoring.dead != dead {
+            scoring.dead = dead;
+            scoring.accepted.fill(false);
+        }
+        self.broadcast_dead_stones().await;
+    }
+
+    async fn broadcast_dead_stones(&mut self) {
+        let Some(scoring) = &self.scoring else {
+            return;
+        };
+        let accepted = self
+            .team_handles
+            .iter()
+            .zip(&scoring.accepted)
+            .filter(|(_, accepted)| **accepted)
+            .map(|(t, _)| t.team_id())
+            .collect();
+        let dead = scoring.dead.clone();
+        self.broadcast(ServerMessage::DeadStones { dead, accepted })
+            .await;
+    }
+
+    /// 仍在对局中的队伍是否都同意了死子
+    fn scoring_agreed(&self) -> bool {
+        let Some(scoring) = &self.scoring else {
+            return false;
+        };
+        return (0..self.team_handles.len())
+            .filter(|&i| self.team_status[i] == TeamStatus::Active)
+            .all(|i| scoring.accepted[i]);
+    }
+
+    /// 处理数子阶段的消息; 返回对局是否结束
+    async fn handle_scoring(&mut self, msg: PlayerMessage) -> bool {
+        let player_id = match &msg {
+            PlayerMessage::ProposeDead { player_id, .. }
+            | PlayerMessage::ToggleDead { player_id, .. }
+            | PlayerMessage::AcceptScore { player_id }
+            | PlayerMessage::ResumePlay { player_id } => *player_id,
+            _ => return false,
+        };
+        let Some(team_index) = self.team_index(player_id) else {
+            return false;
+        };
+        if self.scoring.is_none() || self.team_status[team_index] != TeamStatus::Active {
+            return false;
+        }
+
+        match msg {
+            PlayerMessage::ProposeDead { dead, .. } => {
+                let dead = dead.into_iter().flat_map(|c| self.group(c)).collect();
+                self.set_dead(dead).await;
+            }
+            PlayerMessage::ToggleDead { coord, .. } => {
+                let group = self.group(coord);
+                let mut dead = self.dead_stones();
+                if group.iter().all(|c| dead.contains(c)) {
+                    dead.retain(|c| !group.contains(c));
+                } else {
+                    dead.extend(group);
+                }
+                self.set_dead(dead).await;
+            }
+            PlayerMessage::AcceptScore { .. } => {
+                if let Some(scoring) = &mut self.scoring {
+                    scoring.accepted[team_index] = true;
+                }
+                self.broadcast_dead_stones().await;
+                if self.scoring_agreed() {
+                    self.end_game(EndReason::Score).await;
+                    return true;
+                }
+            }
+            PlayerMessage::ResumePlay { .. } => {
+                if self.paused_at.is_some() {
+                    let msg = ServerMessage::Error(MoveError::Paused.to_string());
+                    self.send_to(player_id, msg).await;
+                    return false;
+                }
+                self.scoring = None;
+                self.consecutive_passes = 0;
+                self.broadcast(ServerMessage::PlayResumed { player_id })
+                    .await;
+                self.advance_turn();
+                self.broadcast_update().await;
+                self.genmove().await;
+            }
+            _ => {}
+        }
+        return false;
+    }
+
+    /// 当前队伍出局, 只剩一个队伍时以 `reason` 结束对局; 返回对局是否结束
+    async fn drop_cur_team(&mut self, status: TeamStatus, reason: EndReason) -> bool {
+        if self.drop_team(self.cur_team_index, status).await {
+            self.end_game(reason).await;
+            return true;
+        }
+        self.next_turn_after_drop().await;
+        return false;
+    }
+
+    /// 当前队伍出局之后轮到下一队; 其余队伍都已经连续 pass 时进入数子阶段
+    async fn next_turn_after_drop(&mut self) {
+        if self.consecutive_passes >= self.active_teams() {
+            self.start_scoring().await;
+            return;
+        }
+        self.advance_turn();
+        self.broadcast_update().await;
+        self.genmove().await;
+    }
+
+    /// 检查是否轮到 `player_id` 下棋
+    fn check_turn(&self, player_id: PlayerId) -> Result<(), MoveError> {
+        if self.paused_at.is_some() {
+            return Err(MoveError::Paused);
+        }
+        if self.scoring.is_some() {
+            return Err(MoveError::Scoring);
+        }
+        if player_id != self.cur_player_id() {
+            return Err(MoveError::NotYourTurn);
+        }
+        return Ok(());
+    }
+
+    /// 当前棋手下了非法着法, 按 `IllegalMovePolicy` 处理; 返回对局是否结束
+    async fn illegal_move(
+        &mut self,
+        player_id: PlayerId,
+        action: Action,
+        error: MoveError,
+    ) -> bool {
+        self.illegal_moves += 1;
+        let policy = self.illegal_move_policy;
+        if self.illegal_moves <= policy.max_retries {
+            let retries_left = Some(policy.max_retries - self.illegal_moves);
+            self.send(ServerMessage::MoveRejected {
+                action,
+                error,
+                retries_left,
+            })
+            .await;
+            self.genmove().await;
+            return false;
+        }
+
+        self.send(ServerMessage::MoveRejected {
+            action,
+            error,
+            retries_left: Some(0),
+        })
+        .await;
+        match policy.then {
+            IllegalMoveAction::Forfeit => {
+                return self
+                    .drop_cur_team(TeamStatus::Forfeited, EndReason::Forfeit)
+                    .await;
+            }
+            IllegalMoveAction::Pass => {
+                let elapsed = self.turn_start.elapsed();
+                self.cur_clock_mut().spend(elapsed);
+                return self.pass(player_id).await;
+            }
+        }
+    }
+
+    /// 当前棋手超时, 按 `TimeoutRule` 处理; 返回对局是否结束
+    async fn time_out(&mut self) -> bool {
+        self.cur_clock_mut().flag();
+        match self.time.on_timeout {
+            TimeoutRule::Forfeit => {
+                return self
+                    .drop_cur_team(TeamStatus::TimedOut, EndReason::Timeout)
+                    .await;
+            }
+            TimeoutRule::AutoPass => {
+                let player_id = self.cur_player_id();
+                return self.pass(player_id).await;
+            }
+        }
+    }
+
+    /// 开局信息
+    fn game_start(&self) -> GameStart {
+        let len = self.team_handles.len();
+        let turn_order = (0..len)
+            .map(|i| self.team_handles[(self.cur_team_index + i) % len].team_id())
+            .collect();
+        return GameStart {
+            size: self.record.board.size,
+            rules: self.record.rules.clone(),
+            komi: self.komi,
+            scoring: self.scoring_rule,
+            handicap: self.record.handicap.clone(),
+            teams: self.record.teams.clone(),
+            turn_order,
+        };
+    }
+
+    fn snapshot(&self) -> GameSnapshot {
+        let size = self.board.size();
+        let board = (0..size * size)
+            .map(|idx| self.board.stone(Coord::new(idx % size, idx / size)))
+            .collect();
+        let teams = self
+            .team_handles
+            .iter()
+            .zip(&self.team_status)
+            .map(|(t, &status)| TeamSnapshot {
+                team_id: t.team_id(),
+                stone: t.stone(),
+                status,
+                captures: self.board.captures(t.stone()),
+            })
+            .collect();
+        return GameSnapshot {
+            size,
+            board,
+            cur_team: self.cur_team_id(),
+            cur_player: self.cur_player_id(),
+            moves: self.record.actions.len(),
+            paused: self.paused_at.is_some(),
+            scoring: self.scoring.is_some(),
+            teams,
+            clocks: self.clock_states(),
+        };
+    }
+
+    /// 新的旁观者先收到开局信息、之前的着法与出局的队伍, 然后是当前的状态
+    fn add_observer(&mut self, observer: UnboundedSender<ServerMessage>) {
+        let mut msgs = vec![ServerMessage::GameStart(self.game_start())];
+        if self.time.control != TimeControl::Unlimited {
+            msgs.push(ServerMessage::TimeSettings(self.time));
+        }
+        for record in &self.record.actions {
+            let player_id = record.player_id;
+            msgs.push(match record.action {
+                Action::Move { stone, coord } => ServerMessage::PlayerMove {
+                    player_id,
+                    stone,
+                    coord,
+                },
+                Action::Pass => {
+                    let team = self
+                        .team_handles
+                        .iter()
+                        .find(|t| t.team_id() == record.team_id);
+                    let stone = team.map_or(Stone::VOID, |t| t.stone());
+                    ServerMessage::PlayerPass { player_id, stone }
+                }
+                Action::Resign => ServerMessage::PlayerResign {
+                    player_id,
+                    team_id: record.team_id,
+                },
+            });
+        }
+        for &i in &self.out_order {
+            msgs.push(ServerMessage::TeamOut {
+                team_id: self.team_handles[i].team_id(),
+                status: self.team_status[i],
+            });
+        }
+        msgs.push(ServerMessage::GameUpdate {
+            cur_team: Some(self.cur_team_id()),
+            cur_player: Some(self.cur_player_id()),
+            player_info: Some(self.player_info()),
+            clocks: self.clock_states(),
+        });
+        if let Some(scoring) = &self.scoring {
+            let accepted = self
+                .team_handles
+                .iter()
+                .zip(&scoring.accepted)
+                .filter(|(_, accepted)| **accepted)
+                .map(|(t, _)| t.team_id())
+                .collect();
+            msgs.push(ServerMessage::DeadStones {
+                dead: scoring.dead.clone(),
+                accepted,
+            });
+        }
+        if self.paused_at.is_some() {
+            msgs.push(ServerMessage::GamePaused);
+        }
 
+        for msg in msgs {
+            if observer.send(msg).is_err() {
+                return;
+            }
+        }
+        self.observers.push(observer);
+    }
+
+    /// 处理 `GameHandle` 发来的命令; 返回对局是否结束
+    async fn handle_command(&mut self, cmd: GameCommand) -> bool {
+        match cmd {
+            GameCommand::Snapshot(tx) => {
+                let _ = tx.send(self.snapshot());
+            }
+            GameCommand::Pause => {
+                if self.paused_at.is_none() {
+                    self.paused_at = Some(Instant::now());
+                    self.broadcast(ServerMessage::GamePaused).await;
+                }
+            }
+            GameCommand::Resume => {
+                if let Some(paused_at) = self.paused_at.take() {
+                    // 暂停的时间不计入这一手
+                    self.turn_start += paused_at.elapsed();
+                    self.broadcast(ServerMessage::GameResumed).await;
+                    if self.scoring.is_none() {
+                        self.genmove().await;
+                    }
+                }
+            }
+            GameCommand::Abort(reason) => {
+                let result = GameResult {
+                    winner: None,
+                    reason: EndReason::Aborted,
+                    scores: vec![],
+                    ranking: vec![],
+                    dead: vec![],
+                    note: Some(reason),
+                };
+                self.finish(result).await;
+                return true;
+            }
+            GameCommand::Observe(observer) => self.add_observer(observer),
+        }
+        return false;
+    }
+
+    /// 运行对局直到结束或所有棋手离开, 返回完整的对局记录
+    ///
+    /// 所有仍在对局中的队伍连续 pass 后进入数子阶段, 各方同意死子后按 `ScoringRule` 计分结束。
+    /// 认输、超时、被判负或棋子被提光的队伍出局, 其余队伍继续对局, 只剩一个队伍时它获胜。
+    ///
+    /// 不是当前棋手的动作会被拒绝并忽略; 非法着法按 `IllegalMovePolicy` 处理。
+    /// 运行时可以通过 `handle` 返回的 `GameHandle` 暂停、中止或旁观对局
+    pub async fn run(&mut self) -> GameRecord {
+        self.start = Instant::now();
+        self.record.started_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64);
+
+        // 棋手收到开局信息之后才能准备界面、初始化引擎
+        self.broadcast(ServerMessage::GameStart(self.game_start()))
+            .await;
+        if self.time.control != TimeControl::Unlimited {
+            self.broadcast(ServerMessage::TimeSettings(self.time)).await;
+        }
+
+        self.turn_start = Instant::now();
+        self.broadcast_update().await;
         self.genmove().await;
-        while let Some(msg) = self.uplink_rx.recv().await {
-            dbg!(&msg);
+        loop {
+            let deadline = self.deadline();
+            let msg = tokio::select! {
+                msg = self.uplink_rx.recv() => msg,
+                // `self` 持有一个 sender, 所以不会返回 `None`
+                Some(cmd) = self.control_rx.recv() => {
+                    if self.handle_command(cmd).await {
+                        break;
+                    }
+                    continue;
+                }
+                _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    if self.time_out().await {
+                        break;
+                    }
+                    continue;
+                }
+            };
+            let Some(msg) = msg else {
+                break;
+            };
+
             match msg {
-                PlayerMessage::PlayerAction { player_id, action } => {
-                    match action {
-                        Action::Move { stone, coord } => {
-                            assert!(player_id == self.cur_player_id());
-                            assert!(stone == self.cur_stone());
-
-                            let res = self.board.place_stone(coord, stone);
-                            match res {
-                                Ok(eaten) => {
-                                    println!("{}", self.board.board_string());
-                                    // 广播落子信息
-                                    self.broadcast(ServerMessage::PlayerMove {
-                                        player_id,
-                                        stone,
-                                        coord,
-                                    })
-                                    .await;
-
-                                    let cur_team_index = self.cur_team_index;
-
-                                    // advance player index for cur_team
-                                    let new_player_index =
-                                        self.cur_player_index[cur_team_index] + 1;
-                                    if new_player_index
-                                        == self.team_handles[cur_team_index].player_nums()
-                                    {
-                                        self.cur_player_index[cur_team_index] = 0;
-                                    } else {
-                                        self.cur_player_index[cur_team_index] = new_player_index;
-                                    }
+                PlayerMessage::PlayerAction { player_id, action } => match action {
+                    Action::Move { stone, coord } => {
+                        // 不是当前棋手的动作 (如超时之后才到达的着法) 不计入非法着法
+                        if let Err(error) = self.check_turn(player_id) {
+                            let msg = ServerMessage::MoveRejected {
+                                action,
+                                error,
+                                retries_left: None,
+                            };
+                            self.send_to(player_id, msg).await;
+                            continue;
+                        }
+                        if self.flagged() {
+                            if self.time_out().await {
+                                break;
+                            }
+                            continue;
+                        }
 
-                                    // advance cur_team_index
-                                    self.cur_team_index += 1;
-                                    if self.cur_team_index == self.team_handles.len() {
-                                        self.cur_team_index = 0;
-                                    }
+                        let expected = self.cur_stone();
+                        let res = if stone == expected {
+                            self.board
+                                .place_stone(coord, stone)
+                                .map_err(|e| MoveError::Illegal(e.to_string()))
+                        } else {
+                            Err(MoveError::WrongStone { expected })
+                        };
+                        match res {
+                            Ok(eaten) => {
+                                let elapsed = self.turn_start.elapsed();
+                                self.cur_clock_mut().spend(elapsed);
+                                self.push_action(player_id, self.cur_team_index, action);
+                                self.consecutive_passes = 0;
+
+                                // 广播落子信息
+                                self.broadcast(ServerMessage::PlayerMove {
+                                    player_id,
+                                    stone,
+                                    coord,
+                                })
+                                .await;
 
-                                    self.broadcast(ServerMessage::GameUpdate {
-                                        cur_team: Some(
-                                            self.team_handles[self.cur_team_index].team_id(),
-                                        ),
-                                        cur_player: Some(self.cur_player_id()),
-                                        player_info: None, // TODO 更新吃子、计时信息
-                                    })
-                                    .await;
+                                if !eaten.is_empty() {
+                                    let mut over = false;
+                                    for team_index in self.captured_teams() {
+                                        over = self
+                                            .drop_team(team_index, TeamStatus::Eliminated)
+                                            .await;
+                                    }
+                                    if over {
+                                        self.end_game(EndReason::Elimination).await;
+                                        break;
+                                    }
                                 }
-                                Err(s) => {
-                                    self.send(ServerMessage::Error(s.to_string())).await;
+
+                                self.advance_turn();
+                                self.broadcast_update().await;
+                                self.genmove().await;
+                            }
+                            Err(error) => {
+                                if self.illegal_move(player_id, action, error).await {
+                                    break;
                                 }
                             }
                         }
-                        Action::Pass => todo!(),
-                        Action::Resign => todo!(),
                     }
-                    self.genmove().await;
-                }
+                    Action::Pass => {
+                        if let Err(error) = self.check_turn(player_id) {
+                            let msg = ServerMessage::MoveRejected {
+                                action,
+                                error,
+                                retries_left: None,
+                            };
+                            self.send_to(player_id, msg).await;
+                            continue;
+                        }
+                        let over = if self.flagged() {
+                            self.time_out().await
+                        } else {
+                            let elapsed = self.turn_start.elapsed();
+                            self.cur_clock_mut().spend(elapsed);
+                            self.pass(player_id).await
+                        };
+                        if over {
+                            break;
+                        }
+                    }
+                    Action::Resign => {
+                        // 认输不必等轮到自己
+                        let Some(team_index) = self.team_index(player_id) else {
+                            continue;
+                        };
+                        if self.team_status[team_index] != TeamStatus::Active {
+                            continue;
+                        }
+                        let team_id = self.team_handles[team_index].team_id();
+                        self.push_action(player_id, team_index, action);
+                        self.broadcast(ServerMessage::PlayerResign { player_id, team_id })
+                            .await;
+
+                        if self.drop_team(team_index, TeamStatus::Resigned).await {
+                            self.end_game(EndReason::Resign).await;
+                            break;
+                        }
+                        if self.scoring.is_some() {
+                            // 其余队伍可能都已经同意了
+                            if self.scoring_agreed() {
+                                self.end_game(EndReason::Score).await;
+                                break;
+                            }
+                            continue;
+                        }
+                        if team_index != self.cur_team_index {
+                            // 仍然等待当前棋手
+                            continue;
+                        }
+                        self.next_turn_after_drop().await;
+                    }
+                },
                 PlayerMessage::PlayerChat { player_id, chat } => {
+                    self.record.chats.push(ChatRecord {
+                        player_id,
+                        chat: chat.clone(),
+                        timestamp_ms: self.elapsed_ms(),
+                    });
                     self.broadcast(ServerMessage::PlayerChat { player_id, chat })
                         .await;
                 }
+                msg => {
+                    if self.handle_scoring(msg).await {
+                        break;
+                    }
+                }
             }
         }
+
+        if self.record.result.is_none() {
+            self.record.result = Some(GameResult {
+                winner: None,
+                reason: EndReason::Abandoned,
+                scores: vec![],
+                ranking: vec![],
+                dead: vec![],
+                note: None,
+            });
+        }
+
+        // 之后句柄的操作都返回 `GameEnded`, 排队中的查询随命令一起丢弃
+        self.control_rx.close();
+        while self.control_rx.try_recv().is_ok() {}
+        return self.record.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rustgo::board::bitboard::BitBoard;
+
+    use super::*;
+    use crate::{
+        handle::GameEnded, player::channel_player::ChannelPlayer, record::GAME_RECORD_VERSION,
+    };
+
+    /// 按脚本行棋的棋手, 脚本用完后离开对局 (但继续接收消息); 数子阶段同意任何死子
+    struct ScriptPlayer {
+        player_id: PlayerId,
+        chat: Option<String>,
+        script: Vec<Action>,
+        /// 每一手思考的时间
+        think: Duration,
+    }
+
+    impl PlayerTrait for ScriptPlayer {
+        fn run(self, uplink_tx: Sender<PlayerMessage>, mut downlink_rx: Receiver<ServerMessage>) {
+            tokio::spawn(async move {
+                let player_id = self.player_id;
+                let mut uplink_tx = Some(uplink_tx);
+                if let (Some(chat), Some(tx)) = (self.chat, &uplink_tx) {
+                    tx.send(PlayerMessage::PlayerChat { player_id, chat })
+                        .await
+                        .unwrap();
+                }
+                let mut script = self.script.into_iter();
+                let mut accepted = None;
+                while let Some(msg) = downlink_rx.recv().await {
+                    match msg {
+                        ServerMessage::GenMove(_) if let Some(tx) = &uplink_tx => {
+                            let action = script.next().unwrap();
+                            tokio::time::sleep(self.think).await;
+                            tx.send(PlayerMessage::PlayerAction { player_id, action })
+                                .await
+                                .unwrap();
+                            // 最后一手是 pass 时还要留下来同意死子
+                            if script.len() == 0 && action != Action::Pass {
+                                uplink_tx = None;
+                            }
+                        }
+                        ServerMessage::ScoringStart { dead }
+                        | ServerMessage::DeadStones { dead, .. }
+                            if let Some(tx) = &uplink_tx
+                                && accepted.as_ref() != Some(&dead) =>
+                        {
+                            accepted = Some(dead);
+                            tx.send(PlayerMessage::AcceptScore { player_id })
+                                .await
+                                .unwrap();
+                        }
+                        ServerMessage::GameOver(_) => break,
+                        _ => {}
+                    }
+                }
+            });
+        }
+
+        fn player_id(&self) -> PlayerId {
+            self.player_id
+        }
+    }
+
+    fn mv(stone: Stone, x: usize, y: usize) -> Action {
+        return Action::Move {
+            stone,
+            coord: Coord::new(x, y),
+        };
+    }
+
+    #[tokio::test]
+    async fn record_actions_and_chats() {
+        let mut builder = GameBuilder::new(9);
+        builder.set_rules("japanese");
+        builder.add_team(TeamId::new(0), Stone::BLACK).unwrap();
+        builder.add_team(TeamId::new(1), Stone::WHITE).unwrap();
+        builder
+            .add_player(
+                TeamId::new(0),
+                ScriptPlayer {
+                    player_id: PlayerId::new(0),
+                    chat: Some("hello".to_string()),
+                    script: vec![mv(Stone::BLACK, 2, 2), mv(Stone::BLACK, 6, 6)],
+                    think: Duration::ZERO,
+                },
+            )
+            .unwrap();
+        builder
+            .add_player(
+                TeamId::new(1),
+                ScriptPlayer {
+                    player_id: PlayerId::new(1),
+                    chat: None,
+                    script: vec![mv(Stone::WHITE, 2, 6), mv(Stone::WHITE, 6, 2)],
+                    think: Duration::ZERO,
+                },
+            )
+            .unwrap();
+        let mut game = builder.build().unwrap();
+        let record = game.run().await;
+
+        assert_eq!(record.version, GAME_RECORD_VERSION);
+        assert_eq!(record.rules, "japanese");
+        assert_eq!(record.board.size, 9);
+        assert_eq!(record.teams.len(), 2);
+        assert_eq!(record.teams[1].stone(), Stone::WHITE);
+        assert_eq!(record.teams[0].players()[0].player_name, "player-0");
+
+        let actions: Vec<_> = record
+            .actions
+            .iter()
+            .map(|a| (a.player_id, a.team_id, a.action))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (PlayerId::new(0), TeamId::new(0), mv(Stone::BLACK, 2, 2)),
+                (PlayerId::new(1), TeamId::new(1), mv(Stone::WHITE, 2, 6)),
+                (PlayerId::new(0), TeamId::new(0), mv(Stone::BLACK, 6, 6)),
+                (PlayerId::new(1), TeamId::new(1), mv(Stone::WHITE, 6, 2)),
+            ]
+        );
+        assert!(
+            record
+                .actions
+                .windows(2)
+                .all(|w| w[0].timestamp_ms <= w[1].timestamp_ms)
+        );
+        assert_eq!(record.chats.len(), 1);
+        assert_eq!(record.chats[0].chat, "hello");
+        assert_eq!(
+            record.result,
+            Some(GameResult {
+                winner: None,
+                reason: EndReason::Abandoned,
+                scores: vec![],
+                ranking: vec![],
+                dead: vec![],
+                note: None,
+            })
+        );
+        assert_eq!(game.record(), &record);
+
+        let json = record.to_json();
+        assert_eq!(GameRecord::from_json(&json).unwrap(), record);
+    }
+
+    /// 第 i 队使用第 i 个脚本, 队伍与棋手的 id 都是 i
+    fn script_game(scripts: Vec<(Stone, Vec<Action>)>) -> Game {
+        let scripts = scripts
+            .into_iter()
+            .map(|(stone, script)| (stone, script, Duration::ZERO))
+            .collect();
+        return timed_game(TimeSettings::default(), scripts);
+    }
+
+    /// 同 `script_game`, 另外给出计时方式与每队的思考时间
+    fn timed_game(time: TimeSettings, scripts: Vec<(Stone, Vec<Action>, Duration)>) -> Game {
+        return script_builder(time, scripts).build().unwrap();
+    }
+
+    fn script_builder(
+        time: TimeSettings,
+        scripts: Vec<(Stone, Vec<Action>, Duration)>,
+    ) -> GameBuilder {
+        let mut builder = GameBuilder::new(9);
+        builder.set_komi(6.5);
+        builder.set_time_settings(time);
+        for (i, (stone, script, think)) in scripts.into_iter().enumerate() {
+            builder.add_team(TeamId::new(i), stone).unwrap();
+            builder
+                .add_player(
+                    TeamId::new(i),
+                    ScriptPlayer {
+                        player_id: PlayerId::new(i),
+                        chat: None,
+                        script,
+                        think,
+                    },
+                )
+                .unwrap();
+        }
+        return builder;
+    }
+
+    /// 界面一侧的 (上行, 下行) channel
+    type Ui = (Sender<PlayerMessage>, Receiver<ServerMessage>);
+
+    /// `N` 个队伍各有一个 `ChannelPlayer`, 第 i 队使用第 i+1 种棋子, 队伍与棋手的 id 都是 i;
+    /// 返回每个棋手的 `Ui`
+    fn channel_builder<const N: usize>() -> (GameBuilder, [Ui; N]) {
+        let mut builder = GameBuilder::new(9);
+        let ui = std::array::from_fn(|i| {
+            let (down_tx, down_rx) = mpsc::channel(64);
+            let (up_tx, up_rx) = mpsc::channel(64);
+            builder
+                .add_team(TeamId::new(i), Stone::new(i as u8 + 1))
+                .unwrap();
+            builder
+                .add_player(
+                    TeamId::new(i),
+                    ChannelPlayer::new(PlayerId::new(i), down_tx, up_rx),
+                )
+                .unwrap();
+            (up_tx, down_rx)
+        });
+        return (builder, ui);
+    }
+
+    fn two_player_game(black: Vec<Action>, white: Vec<Action>) -> Game {
+        return script_game(vec![(Stone::BLACK, black), (Stone::WHITE, white)]);
+    }
+
+    fn idle_player(id: usize) -> ScriptPlayer {
+        return ScriptPlayer {
+            player_id: PlayerId::new(id),
+            chat: None,
+            script: vec![],
+            think: Duration::ZERO,
+        };
+    }
+
+    #[tokio::test]
+    async fn builder_validation() {
+        let mut builder = GameBuilder::new(9);
+        builder.add_team(TeamId::new(0), Stone::BLACK).unwrap();
+        assert_eq!(
+            builder.add_team(TeamId::new(0), Stone::WHITE),
+            Err(GameConfigError::DuplicateTeam(TeamId::new(0)))
+        );
+        assert_eq!(
+            builder.add_team(TeamId::new(1), Stone::BLACK),
+            Err(GameConfigError::DuplicateStone(Stone::BLACK))
+        );
+        for stone in [Stone::VOID, Stone::new(MAX_COLORS as u8 + 1)] {
+            assert_eq!(
+                builder.add_team(TeamId::new(1), stone),
+                Err(GameConfigError::InvalidStone(stone))
+            );
+        }
+        assert_eq!(
+            builder.add_player(TeamId::new(1), idle_player(0)),
+            Err(GameConfigError::UnknownTeam(TeamId::new(1)))
+        );
+        builder.add_player(TeamId::new(0), idle_player(0)).unwrap();
+        assert_eq!(
+            builder.add_player(TeamId::new(0), idle_player(0)),
+            Err(GameConfigError::DuplicatePlayer(PlayerId::new(0)))
+        );
+        assert_eq!(builder.build().err(), Some(GameConfigError::TooFewTeams(1)));
+
+        let two_teams = |size, handicap| {
+            let mut builder = GameBuilder::new(size);
+            builder.set_handicap(handicap);
+            builder.add_team(TeamId::new(0), Stone::BLACK).unwrap();
+            builder.add_team(TeamId::new(1), Stone::WHITE).unwrap();
+            builder.add_player(TeamId::new(0), idle_player(0)).unwrap();
+            return builder;
+        };
+        assert_eq!(
+            two_teams(9, 0).build().err(),
+            Some(GameConfigError::EmptyTeam(TeamId::new(1)))
+        );
+        let mut builder = two_teams(9, 1);
+        builder.add_player(TeamId::new(1), idle_player(1)).unwrap();
+        assert_eq!(
+            builder.build().err(),
+            Some(GameConfigError::InvalidHandicap(1))
+        );
+        let mut builder = two_teams(1, 0);
+        builder.add_player(TeamId::new(1), idle_player(1)).unwrap();
+        assert_eq!(builder.build().err(), Some(GameConfigError::InvalidSize(1)));
+        let mut builder = two_teams(20, 0);
+        builder.add_player(TeamId::new(1), idle_player(1)).unwrap();
+        assert_eq!(
+            builder.build_with_board::<BitBoard>().err(),
+            Some(GameConfigError::InvalidSize(20))
+        );
+        // 不经过 GTP 时可以超过 GTP 坐标的大小
+        let mut builder = two_teams(gtp::MAX_SIZE + 1, 0);
+        builder.add_player(TeamId::new(1), idle_player(1)).unwrap();
+        assert!(builder.build().is_ok());
+        let invalid_controls = [
+            TimeControl::Canadian {
+                main: Duration::from_secs(10),
+                period: Duration::from_secs(60),
+                stones: 0,
+            },
+            TimeControl::Canadian {
+                main: Duration::from_secs(10),
+                period: Duration::ZERO,
+                stones: 5,
+            },
+            TimeControl::ByoYomi {
+                main: Duration::from_secs(10),
+                period: Duration::ZERO,
+                periods: 3,
+            },
+        ];
+        for control in invalid_controls {
+            let mut builder = two_teams(9, 0);
+            builder.add_player(TeamId::new(1), idle_player(1)).unwrap();
+            builder.set_time_settings(TimeSettings {
+                control,
+                ..Default::default()
+            });
+            assert_eq!(
+                builder.build().err(),
+                Some(GameConfigError::InvalidTimeControl(control))
+            );
+        }
+        let mut builder = two_teams(9, 4);
+        builder.add_player(TeamId::new(1), idle_player(1)).unwrap();
+        assert!(builder.build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn consecutive_passes_end_by_score() {
+        let mut game = two_player_game(
+            vec![mv(Stone::BLACK, 4, 4), Action::Pass],
+            vec![Action::Pass, Action::Pass],
+        );
+        let record = game.run().await;
+
+        assert_eq!(record.komi, 6.5);
+        assert_eq!(record.actions.len(), 3);
+        assert_eq!(
+            record.result,
+            Some(GameResult {
+                winner: Some(TeamId::new(0)),
+                reason: EndReason::Score,
+                scores: vec![
+                    TeamScore {
+                        team_id: TeamId::new(0),
+                        score: 81.0,
+                        status: TeamStatus::Active,
+                    },
+                    TeamScore {
+                        team_id: TeamId::new(1),
+                        score: 6.5,
+                        status: TeamStatus::Active,
+                    },
+                ],
+                ranking: vec![TeamId::new(0), TeamId::new(1)],
+                dead: vec![],
+                note: None,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn pass_counter_resets_after_move() {
+        let mut game = two_player_game(
+            vec![Action::Pass, Action::Pass],
+            vec![mv(Stone::WHITE, 4, 4), Action::Pass],
+        );
+        let record = game.run().await;
+
+        assert_eq!(record.actions.len(), 4);
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Score);
+        assert_eq!(result.winner, Some(TeamId::new(1)));
+    }
+
+    #[tokio::test]
+    async fn resign_ends_game() {
+        let mut game = two_player_game(
+            vec![mv(Stone::BLACK, 4, 4), mv(Stone::BLACK, 2, 2)],
+            vec![Action::Resign],
+        );
+        let record = game.run().await;
+
+        assert_eq!(record.actions.len(), 2);
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Resign);
+        assert_eq!(result.winner, Some(TeamId::new(0)));
+        assert_eq!(game.result().unwrap().reason, EndReason::Resign);
+    }
+
+    /// 记录中每个动作是否依次由 `ids` 中的棋手做出
+    fn played_by(record: &GameRecord, ids: &[usize]) -> bool {
+        let players: Vec<_> = record.actions.iter().map(|a| a.player_id).collect();
+        let expected: Vec<_> = ids.iter().map(|&i| PlayerId::new(i)).collect();
+        return players == expected;
+    }
+
+    #[tokio::test]
+    async fn resigned_team_is_skipped() {
+        let c3 = Stone::new(3);
+        let mut game = script_game(vec![
+            (Stone::BLACK, vec![mv(Stone::BLACK, 4, 4), Action::Pass]),
+            (Stone::WHITE, vec![Action::Resign]),
+            (c3, vec![mv(c3, 2, 2), Action::Pass]),
+        ]);
+        let record = game.run().await;
+
+        assert!(played_by(&record, &[0, 1, 2, 0, 2]));
+        assert_eq!(game.team_status(TeamId::new(1)), Some(TeamStatus::Resigned));
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Score);
+        assert_eq!(result.winner, Some(TeamId::new(2)));
+        assert_eq!(
+            result.ranking,
+            vec![TeamId::new(2), TeamId::new(0), TeamId::new(1)]
+        );
+        assert_eq!(result.scores[1].status, TeamStatus::Resigned);
+    }
+
+    #[tokio::test]
+    async fn captured_team_is_eliminated() {
+        let c3 = Stone::new(3);
+        let mut game = script_game(vec![
+            (
+                Stone::BLACK,
+                vec![mv(Stone::BLACK, 1, 0), mv(Stone::BLACK, 0, 1), Action::Pass],
+            ),
+            (Stone::WHITE, vec![mv(Stone::WHITE, 0, 0)]),
+            (c3, vec![mv(c3, 8, 8), Action::Pass]),
+        ]);
+        let record = game.run().await;
+
+        assert!(played_by(&record, &[0, 1, 2, 0, 2, 0]));
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Score);
+        // 黑 2 子 + 1 目, C3 1 子 + 贴目
+        assert_eq!(result.scores[0].score, 3.0);
+        assert_eq!(result.scores[2].score, 7.5);
+        assert_eq!(result.scores[1].status, TeamStatus::Eliminated);
+        assert_eq!(
+            result.ranking,
+            vec![TeamId::new(2), TeamId::new(0), TeamId::new(1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn last_team_standing_wins() {
+        let mut game = two_player_game(
+            vec![mv(Stone::BLACK, 1, 0), mv(Stone::BLACK, 0, 1)],
+            vec![mv(Stone::WHITE, 0, 0), Action::Pass],
+        );
+        let record = game.run().await;
+
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Elimination);
+        assert_eq!(result.winner, Some(TeamId::new(0)));
+        assert_eq!(result.ranking, vec![TeamId::new(0), TeamId::new(1)]);
+    }
+
+    fn absolute(secs: u64, on_timeout: TimeoutRule) -> TimeSettings {
+        return TimeSettings {
+            control: TimeControl::Absolute {
+                main: Duration::from_secs(secs),
+            },
+            scope: ClockScope::Team,
+            on_timeout,
+        };
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_forfeits() {
+        let mut game = timed_game(
+            absolute(10, TimeoutRule::Forfeit),
+            vec![
+                (
+                    Stone::BLACK,
+                    vec![mv(Stone::BLACK, 4, 4), mv(Stone::BLACK, 2, 2)],
+                    Duration::from_secs(6),
+                ),
+                (
+                    Stone::WHITE,
+                    vec![mv(Stone::WHITE, 6, 6), mv(Stone::WHITE, 2, 6)],
+                    Duration::from_secs(1),
+                ),
+            ],
+        );
+        let record = game.run().await;
+
+        // 黑第二手在 12 秒时才下, 10 秒时已经超时
+        assert_eq!(record.actions.len(), 2);
+        assert_eq!(record.actions[0].time_left_ms, Some(4000));
+        assert_eq!(record.actions[1].time_left_ms, Some(9000));
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Timeout);
+        assert_eq!(result.winner, Some(TeamId::new(1)));
+        assert_eq!(result.scores[0].status, TeamStatus::TimedOut);
+        assert_eq!(game.team_status(TeamId::new(0)), Some(TeamStatus::TimedOut));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_auto_passes() {
+        let mut game = timed_game(
+            absolute(5, TimeoutRule::AutoPass),
+            vec![
+                (
+                    Stone::BLACK,
+                    vec![mv(Stone::BLACK, 4, 4); 3],
+                    Duration::from_secs(6),
+                ),
+                (Stone::WHITE, vec![Action::Pass], Duration::from_secs(1)),
+            ],
+        );
+        let record = game.run().await;
+
+        // 黑超时被自动 pass, 白也 pass, 对局结束
+        let actions: Vec<_> = record.actions.iter().map(|a| a.action).collect();
+        assert_eq!(actions, vec![Action::Pass, Action::Pass]);
+        assert_eq!(record.actions[0].time_left_ms, Some(0));
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Score);
+        assert_eq!(result.winner, Some(TeamId::new(1)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn player_clocks_and_byo_yomi() {
+        let time = TimeSettings {
+            control: TimeControl::ByoYomi {
+                main: Duration::from_secs(5),
+                period: Duration::from_secs(3),
+                periods: 2,
+            },
+            scope: ClockScope::Player,
+            on_timeout: TimeoutRule::Forfeit,
+        };
+        let mut game = timed_game(
+            time,
+            vec![
+                (
+                    Stone::BLACK,
+                    vec![mv(Stone::BLACK, 4, 4), mv(Stone::BLACK, 2, 2), Action::Pass],
+                    Duration::from_secs(4),
+                ),
+                (
+                    Stone::WHITE,
+                    vec![mv(Stone::WHITE, 6, 6), Action::Pass],
+                    Duration::from_secs(1),
+                ),
+            ],
+        );
+        let record = game.run().await;
+
+        // 黑: 5+3+3 秒, 第一手后 1+6, 第二手用掉基本时间和一次完整的读秒
+        let time_left: Vec<_> = record.actions.iter().map(|a| a.time_left_ms).collect();
+        assert_eq!(
+            time_left,
+            vec![Some(7000), Some(10000), Some(3000), Some(9000)]
+        );
+        // 黑第三手 (pass) 超过最后一次读秒
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Timeout);
+        assert_eq!(result.winner, Some(TeamId::new(1)));
+    }
+
+    fn illegal_move_game(
+        policy: IllegalMovePolicy,
+        black: Vec<Action>,
+        white: Vec<Action>,
+    ) -> Game {
+        let scripts = vec![
+            (Stone::BLACK, black, Duration::ZERO),
+            (Stone::WHITE, white, Duration::ZERO),
+        ];
+        let mut builder = script_builder(TimeSettings::default(), scripts);
+        builder.set_illegal_move_policy(policy);
+        return builder.build().unwrap();
+    }
+
+    #[tokio::test]
+    async fn illegal_moves_forfeit() {
+        let policy = IllegalMovePolicy {
+            max_retries: 1,
+            then: IllegalMoveAction::Forfeit,
+        };
+        let mut game = illegal_move_game(
+            policy,
+            vec![mv(Stone::BLACK, 4, 4), mv(Stone::BLACK, 2, 2)],
+            // 用错棋子, 然后下在已有棋子的位置
+            vec![mv(Stone::BLACK, 2, 2), mv(Stone::WHITE, 4, 4)],
+        );
+        let record = game.run().await;
+
+        assert_eq!(record.actions.len(), 1);
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Forfeit);
+        assert_eq!(result.winner, Some(TeamId::new(0)));
+        assert_eq!(result.scores[1].status, TeamStatus::Forfeited);
+    }
+
+    #[tokio::test]
+    async fn illegal_moves_auto_pass() {
+        let policy = IllegalMovePolicy {
+            max_retries: 1,
+            then: IllegalMoveAction::Pass,
+        };
+        let mut game = illegal_move_game(
+            policy,
+            vec![mv(Stone::BLACK, 4, 4), Action::Pass],
+            vec![mv(Stone::WHITE, 4, 4), mv(Stone::WHITE, 4, 4), Action::Pass],
+        );
+        let record = game.run().await;
+
+        // 白两次非法着法后被自动 pass, 黑也 pass, 对局结束
+        let actions: Vec<_> = record.actions.iter().map(|a| a.action).collect();
+        assert_eq!(
+            actions,
+            vec![mv(Stone::BLACK, 4, 4), Action::Pass, Action::Pass]
+        );
+        assert!(played_by(&record, &[0, 1, 0]));
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Score);
+        assert_eq!(result.winner, Some(TeamId::new(0)));
+    }
+
+    /// 读消息直到 `f` 返回 `Some`
+    async fn expect<T>(
+        rx: &mut Receiver<ServerMessage>,
+        f: impl Fn(ServerMessage) -> Option<T>,
+    ) -> T {
+        loop {
+            let msg = rx.recv().await.unwrap();
+            if let Some(t) = f(msg) {
+                return t;
+            }
+        }
+    }
+
+    async fn expect_dead(rx: &mut Receiver<ServerMessage>) -> (Vec<Coord>, Vec<TeamId>) {
+        expect(rx, |msg| match msg {
+            ServerMessage::DeadStones { dead, accepted } => Some((dead, accepted)),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn expect_genmove(rx: &mut Receiver<ServerMessage>) {
+        expect(rx, |msg| {
+            matches!(msg, ServerMessage::GenMove(_)).then_some(())
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn scoring_phase() {
+        let (mut builder, ui) = channel_builder();
+        builder.set_komi(6.5);
+        builder.set_scoring_rule(ScoringRule::Territory);
+        let mut game = builder.build().unwrap();
+        let game = tokio::spawn(async move { game.run().await });
+        let [(b_tx, mut b_rx), (w_tx, mut w_rx)] = ui;
+        let b = PlayerId::new(0);
+        let w = PlayerId::new(1);
+        let act = |player_id, action| PlayerMessage::PlayerAction { player_id, action };
+
+        expect_genmove(&mut b_rx).await;
+        b_tx.send(act(b, mv(Stone::BLACK, 2, 2))).await.unwrap();
+        expect_genmove(&mut w_rx).await;
+        w_tx.send(act(w, mv(Stone::WHITE, 6, 6))).await.unwrap();
+        expect_genmove(&mut b_rx).await;
+        b_tx.send(act(b, Action::Pass)).await.unwrap();
+        expect_genmove(&mut w_rx).await;
+        w_tx.send(act(w, Action::Pass)).await.unwrap();
+        expect(&mut b_rx, |msg| match msg {
+            ServerMessage::ScoringStart { dead } => Some(dead),
+            _ => None,
+        })
+        .await;
+
+        // 数子阶段中的着法被忽略
+        b_tx.send(act(b, mv(Stone::BLACK, 3, 3))).await.unwrap();
+
+        // 提出死子, 切换两次, 然后继续对局
+        b_tx.send(PlayerMessage::ProposeDead {
+            player_id: b,
+            dead: vec![Coord::new(6, 6)],
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            expect_dead(&mut w_rx).await,
+            (vec![Coord::new(6, 6)], vec![])
+        );
+        let toggle = PlayerMessage::ToggleDead {
+            player_id: w,
+            coord: Coord::new(6, 6),
+        };
+        w_tx.send(toggle.clone()).await.unwrap();
+        assert_eq!(expect_dead(&mut w_rx).await, (vec![], vec![]));
+        w_tx.send(toggle).await.unwrap();
+        assert_eq!(
+            expect_dead(&mut w_rx).await,
+            (vec![Coord::new(6, 6)], vec![])
+        );
+        w_tx.send(PlayerMessage::ResumePlay { player_id: w })
+            .await
+            .unwrap();
+
+        // 继续对局后轮到黑
+        expect_genmove(&mut b_rx).await;
+        b_tx.send(act(b, mv(Stone::BLACK, 2, 3))).await.unwrap();
+        expect_genmove(&mut w_rx).await;
+        w_tx.send(act(w, Action::Pass)).await.unwrap();
+        expect_genmove(&mut b_rx).await;
+        b_tx.send(act(b, Action::Pass)).await.unwrap();
+
+        b_tx.send(PlayerMessage::ProposeDead {
+            player_id: b,
+            dead: vec![Coord::new(6, 6)],
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            expect_dead(&mut w_rx).await,
+            (vec![Coord::new(6, 6)], vec![])
+        );
+        w_tx.send(PlayerMessage::AcceptScore { player_id: w })
+            .await
+            .unwrap();
+        // b_rx 里还有之前的消息
+        let accepted = expect(&mut b_rx, |msg| match msg {
+            ServerMessage::DeadStones { accepted, .. } if !accepted.is_empty() => Some(accepted),
+            _ => None,
+        })
+        .await;
+        assert_eq!(accepted, vec![TeamId::new(1)]);
+        b_tx.send(PlayerMessage::AcceptScore { player_id: b })
+            .await
+            .unwrap();
+
+        let record = game.await.unwrap();
+        assert_eq!(record.scoring, ScoringRule::Territory);
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Score);
+        assert_eq!(result.winner, Some(TeamId::new(0)));
+        assert_eq!(result.dead, vec![Coord::new(6, 6)]);
+        // 黑: 79 目地加 1 个俘虏; 白: 只有贴目
+        assert_eq!(result.scores[0].score, 80.0);
+        assert_eq!(result.scores[1].score, 6.5);
+    }
+
+    #[tokio::test]
+    async fn game_start_with_handicap() {
+        let (mut builder, ui) = channel_builder();
+        builder.set_komi(0.5);
+        builder.set_handicap(2);
+        let mut game = builder.build().unwrap();
+        let handicap = vec![Coord::new(2, 6), Coord::new(6, 2)];
+        assert_eq!(game.board().stone(handicap[0]), Stone::BLACK);
+        let game = tokio::spawn(async move { game.run().await });
+        let [(_b_tx, mut b_rx), (w_tx, mut w_rx)] = ui;
+
+        let Some(ServerMessage::GameStart(start)) = b_rx.recv().await else {
+            panic!("expected GameStart first");
+        };
+        assert_eq!(start.size, 9);
+        assert_eq!(start.rules, DEFAULT_RULES);
+        assert_eq!(start.komi, 0.5);
+        assert_eq!(start.handicap, handicap);
+        assert_eq!(start.handicap_stone(), Some(Stone::BLACK));
+        assert_eq!(start.teams.len(), 2);
+        assert_eq!(start.teams[1].players()[0].player_id, PlayerId::new(1));
+        assert_eq!(start.turn_order, vec![TeamId::new(1), TeamId::new(0)]);
+
+        // 让子之后白先下
+        expect_genmove(&mut w_rx).await;
+        w_tx.send(PlayerMessage::PlayerAction {
+            player_id: PlayerId::new(1),
+            action: Action::Resign,
+        })
+        .await
+        .unwrap();
+        let record = game.await.unwrap();
+        assert_eq!(record.handicap, handicap);
+        assert_eq!(record.result.unwrap().winner, Some(TeamId::new(0)));
+    }
+
+    #[tokio::test]
+    async fn out_of_turn_moves_are_rejected() {
+        let (builder, ui) = channel_builder();
+        let mut game = builder.build().unwrap();
+        let game = tokio::spawn(async move { game.run().await });
+        let [(b_tx, mut b_rx), (w_tx, mut w_rx)] = ui;
+        let b = PlayerId::new(0);
+        let w = PlayerId::new(1);
+        let act = |player_id, action| PlayerMessage::PlayerAction { player_id, action };
+
+        expect_genmove(&mut b_rx).await;
+        let early = mv(Stone::WHITE, 4, 4);
+        w_tx.send(act(w, early)).await.unwrap();
+        let rejected = expect(&mut w_rx, |msg| match msg {
+            ServerMessage::MoveRejected {
+                action,
+                error,
+                retries_left,
+            } => Some((action, error, retries_left)),
+            _ => None,
+        })
+        .await;
+        assert_eq!(rejected, (early, MoveError::NotYourTurn, None));
+
+        // 不计入非法着法, 仍然轮到黑
+        b_tx.send(act(b, Action::Resign)).await.unwrap();
+        let record = game.await.unwrap();
+        assert_eq!(record.actions.len(), 1);
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Resign);
+        assert_eq!(result.winner, Some(TeamId::new(1)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn handle_pause_observe_and_abort() {
+        let (mut builder, ui) = channel_builder();
+        builder.set_time_settings(absolute(10, TimeoutRule::Forfeit));
+        let (handle, game) = builder.build().unwrap().spawn();
+        let [(b_tx, mut b_rx), (w_tx, mut w_rx)] = ui;
+        let b = PlayerId::new(0);
+        let w = PlayerId::new(1);
+        let act = |player_id, action| PlayerMessage::PlayerAction { player_id, action };
+
+        expect_genmove(&mut b_rx).await;
+        b_tx.send(act(b, mv(Stone::BLACK, 4, 4))).await.unwrap();
+        expect_genmove(&mut w_rx).await;
+
+        // 中途加入的旁观者先收到之前的着法
+        let mut observer = handle.observe().await.unwrap();
+        assert!(matches!(
+            observer.recv().await,
+            Some(ServerMessage::GameStart(_))
+        ));
+        assert!(matches!(
+            observer.recv().await,
+            Some(ServerMessage::TimeSettings(_))
+        ));
+        assert!(matches!(
+            observer.recv().await,
+            Some(ServerMessage::PlayerMove { player_id, .. }) if player_id == b
+        ));
+        assert!(matches!(
+            observer.recv().await,
+            Some(ServerMessage::GameUpdate { cur_player: Some(player_id), .. }) if player_id == w
+        ));
+
+        // 暂停时时钟停止, 着法被拒绝
+        handle.pause().await.unwrap();
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        let snapshot = handle.snapshot().await.unwrap();
+        assert!(snapshot.paused);
+        assert_eq!(snapshot.moves, 1);
+        assert_eq!(snapshot.cur_player, w);
+        assert_eq!(snapshot.board[4 * 9 + 4], Stone::BLACK);
+        assert_eq!(snapshot.teams[1].status, TeamStatus::Active);
+        w_tx.send(act(w, mv(Stone::WHITE, 2, 2))).await.unwrap();
+        let error = expect(&mut w_rx, |msg| match msg {
+            ServerMessage::MoveRejected { error, .. } => Some(error),
+            _ => None,
+        })
+        .await;
+        assert_eq!(error, MoveError::Paused);
+
+        handle.resume().await.unwrap();
+        expect_genmove(&mut w_rx).await;
+        w_tx.send(act(w, mv(Stone::WHITE, 2, 2))).await.unwrap();
+        expect_genmove(&mut b_rx).await;
+
+        handle.abort("server shutdown").await.unwrap();
+        let record = game.await.unwrap();
+        // 白的这一手没有用到暂停的时间
+        assert_eq!(record.actions[1].time_left_ms, Some(10000));
+        let result = record.result.unwrap();
+        assert_eq!(result.reason, EndReason::Aborted);
+        assert_eq!(result.winner, None);
+        assert_eq!(result.note.as_deref(), Some("server shutdown"));
+
+        let mut seen = vec![];
+        while let Some(msg) = observer.recv().await {
+            seen.push(msg);
+        }
+        assert!(matches!(seen[0], ServerMessage::GamePaused));
+        assert!(matches!(seen[1], ServerMessage::GameResumed));
+        assert!(matches!(seen.last(), Some(ServerMessage::GameOver(_))));
+        assert_eq!(handle.snapshot().await, Err(GameEnded));
+    }
+
+    #[tokio::test]
+    async fn handle_fails_after_run_returns() {
+        let mut game = two_player_game(vec![Action::Resign], vec![]);
+        let handle = game.handle();
+        game.run().await;
+        assert_eq!(handle.snapshot().await, Err(GameEnded));
+        assert_eq!(handle.pause().await, Err(GameEnded));
+        assert_eq!(handle.abort("too late").await, Err(GameEnded));
+        assert!(handle.observe().await.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn turn_changes_while_paused() {
+        let (mut builder, ui) = channel_builder();
+        builder.set_time_settings(absolute(10, TimeoutRule::Forfeit));
+        let (handle, game) = builder.build().unwrap().spawn();
+        let [(a_tx, mut a_rx), (b_tx, mut b_rx), _c] = ui;
+        let a = PlayerId::new(0);
+        let b = PlayerId::new(1);
+
+        expect_genmove(&mut a_rx).await;
+        handle.pause().await.unwrap();
+        assert!(handle.snapshot().await.unwrap().paused);
+
+        // 暂停中认输, 轮到下一队但不发 GenMove
+        a_tx.send(PlayerMessage::PlayerAction {
+            player_id: a,
+            action: Action::Resign,
+        })
+        .await
+        .unwrap();
+        expect(&mut b_rx, |msg| {
+            matches!(msg, ServerMessage::PlayerResign { .. }).then_some(())
+        })
+        .await;
+        assert_eq!(handle.snapshot().await.unwrap().cur_player, b);
+        while let Ok(msg) = b_rx.try_recv() {
+            assert!(!matches!(msg, ServerMessage::GenMove(_)));
+        }
+
+        // 暂停的时间不计入下一队的这一手
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        handle.resume().await.unwrap();
+        expect_genmove(&mut b_rx).await;
+        b_tx.send(PlayerMessage::PlayerAction {
+            player_id: b,
+            action: mv(Stone::WHITE, 4, 4),
+        })
+        .await
+        .unwrap();
+        expect(&mut b_rx, |msg| {
+            matches!(msg, ServerMessage::PlayerMove { .. }).then_some(())
+        })
+        .await;
+        handle.abort("done").await.unwrap();
+        let record = game.await.unwrap();
+        assert_eq!(record.actions.len(), 2);
+        assert_eq!(record.actions[1].time_left_ms, Some(10000));
+    }
+
+    #[tokio::test]
+    async fn resign_after_others_passed_starts_scoring() {
+        let (builder, ui) = channel_builder();
+        let (handle, game) = builder.build().unwrap().spawn();
+        let [(a_tx, mut a_rx), (b_tx, mut b_rx), (c_tx, mut c_rx)] = ui;
+        let act = |i, action| PlayerMessage::PlayerAction {
+            player_id: PlayerId::new(i),
+            action,
+        };
+
+        expect_genmove(&mut a_rx).await;
+        a_tx.send(act(0, Action::Pass)).await.unwrap();
+        expect_genmove(&mut b_rx).await;
+        b_tx.send(act(1, Action::Pass)).await.unwrap();
+        expect_genmove(&mut c_rx).await;
+        // 剩下的两队都已经 pass, 不再轮回第一队
+        c_tx.send(act(2, Action::Resign)).await.unwrap();
+        let msg = expect(&mut a_rx, |msg| match msg {
+            ServerMessage::GenMove(_) | ServerMessage::ScoringStart { .. } => Some(msg),
+            _ => None,
+        })
+        .await;
+        assert!(matches!(msg, ServerMessage::ScoringStart { .. }));
+        assert!(handle.snapshot().await.unwrap().scoring);
+
+        handle.abort("done").await.unwrap();
+        let record = game.await.unwrap();
+        assert_eq!(record.result.unwrap().reason, EndReason::Aborted);
+    }
+
+    #[tokio::test]
+    async fn resume_play_rejected_while_paused() {
+        let (builder, ui) = channel_builder();
+        let (handle, game) = builder.build().unwrap().spawn();
+        let [(b_tx, mut b_rx), (w_tx, mut w_rx)] = ui;
+        let b = PlayerId::new(0);
+        let w = PlayerId::new(1);
+        let act = |player_id, action| PlayerMessage::PlayerAction { player_id, action };
+
+        expect_genmove(&mut b_rx).await;
+        b_tx.send(act(b, Action::Pass)).await.unwrap();
+        expect_genmove(&mut w_rx).await;
+        w_tx.send(act(w, Action::Pass)).await.unwrap();
+        expect(&mut w_rx, |msg| {
+            matches!(msg, ServerMessage::ScoringStart { .. }).then_some(())
+        })
+        .await;
+
+        handle.pause().await.unwrap();
+        assert!(handle.snapshot().await.unwrap().paused);
+        w_tx.send(PlayerMessage::ResumePlay { player_id: w })
+            .await
+            .unwrap();
+        let error = expect(&mut w_rx, |msg| match msg {
+            ServerMessage::Error(e) => Some(e),
+            _ => None,
+        })
+        .await;
+        assert_eq!(error, MoveError::Paused.to_string());
+        assert!(handle.snapshot().await.unwrap().scoring);
+
+        handle.abort("done").await.unwrap();
+        let result = game.await.unwrap().result.unwrap();
+        assert_eq!(result.reason, EndReason::Aborted);
     }
 }
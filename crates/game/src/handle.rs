@@ -0,0 +1,108 @@
+//! 运行中对局的句柄
+//!
+//! `Game::spawn` 让对局在自己的任务里运行, 外部通过 `GameHandle` 查询局面、暂停、中止对局或旁观。
+//! 对局结束之后句柄的操作都会失败。
+
+use rustgo::Stone;
+use tokio::sync::{
+    mpsc::{self, Sender, UnboundedReceiver, UnboundedSender},
+    oneshot,
+};
+
+use crate::{ServerMessage, clock::ClockState, player::PlayerId, record::TeamStatus, team::TeamId};
+
+/// 发给对局的控制命令
+pub(crate) enum GameCommand {
+    Snapshot(oneshot::Sender<GameSnapshot>),
+    Pause,
+    Resume,
+    Abort(String),
+    Observe(UnboundedSender<ServerMessage>),
+}
+
+/// 队伍的状态
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TeamSnapshot {
+    pub team_id: TeamId,
+    pub stone: Stone,
+    pub status: TeamStatus,
+    /// 提走的棋子数
+    pub captures: usize,
+}
+
+/// 对局当前的局面
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameSnapshot {
+    pub size: usize,
+    /// `board[y * size + x]`
+    pub board: Vec<Stone>,
+    pub cur_team: TeamId,
+    pub cur_player: PlayerId,
+    /// 已经记录的动作数
+    pub moves: usize,
+    pub paused: bool,
+    /// 在数子阶段
+    pub scoring: bool,
+    pub teams: Vec<TeamSnapshot>,
+    /// 不计时时为空
+    pub clocks: Vec<ClockState>,
+}
+
+/// 对局已经结束, 句柄的操作无法完成
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GameEnded;
+
+impl std::fmt::Display for GameEnded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "game has ended")
+    }
+}
+
+impl std::error::Error for GameEnded {}
+
+/// 运行中对局的句柄, 可以 clone 给多个使用者
+#[derive(Clone)]
+pub struct GameHandle {
+    control_tx: Sender<GameCommand>,
+}
+
+impl GameHandle {
+    pub(crate) fn new(control_tx: Sender<GameCommand>) -> Self {
+        GameHandle { control_tx }
+    }
+
+    async fn command(&self, cmd: GameCommand) -> Result<(), GameEnded> {
+        self.control_tx.send(cmd).await.map_err(|_| GameEnded)
+    }
+
+    /// 当前的局面
+    pub async fn snapshot(&self) -> Result<GameSnapshot, GameEnded> {
+        let (tx, rx) = oneshot::channel();
+        self.command(GameCommand::Snapshot(tx)).await?;
+        rx.await.map_err(|_| GameEnded)
+    }
+
+    /// 暂停对局: 时钟停止, 落子与 pass 被拒绝; 已经暂停时不做任何事
+    pub async fn pause(&self) -> Result<(), GameEnded> {
+        self.command(GameCommand::Pause).await
+    }
+
+    /// 继续对局, 并重新要求当前棋手落子
+    pub async fn resume(&self) -> Result<(), GameEnded> {
+        self.command(GameCommand::Resume).await
+    }
+
+    /// 中止对局, 没有胜者; `reason` 记录在结果中
+    pub async fn abort(&self, reason: &str) -> Result<(), GameEnded> {
+        self.command(GameCommand::Abort(reason.to_string())).await
+    }
+
+    /// 旁观对局: 先收到开局信息与之前的着法, 之后收到所有广播的消息
+    ///
+    /// 队列没有上限, 旁观者读得慢也不会拖住对局
+    pub async fn observe(&self) -> Result<UnboundedReceiver<ServerMessage>, GameEnded> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.command(GameCommand::Observe(tx)).await?;
+        Ok(rx)
+    }
+}
@@ -1,6 +1,9 @@
+pub mod clock;
 mod common;
 pub mod game;
+pub mod handle;
 pub mod player;
+pub mod record;
 pub mod team;
 
 pub use common::*;
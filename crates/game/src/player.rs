@@ -1,26 +1,58 @@
+use std::{fmt::Display, io::ErrorKind};
+
 use crate::{PlayerMessage, ServerMessage, team::TeamId};
+use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc::{Receiver, Sender};
 
 #[derive(Debug)]
 pub enum PlayerError {
     IoError(std::io::Error),
+    /// 引擎对命令返回了错误 (GTP 的 `?` 响应)
     EngineError(String),
+    /// 引擎没有在限定时间内响应, 内容为命令
+    Timeout(String),
+    /// 引擎进程退出或连接断开
+    Disconnected,
+    /// 无法解析的响应
+    ProtocolError(String),
+    /// 引擎缺少必须的功能
+    Unsupported(String),
+}
+
+impl Display for PlayerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayerError::IoError(e) => write!(f, "io error: {e}"),
+            PlayerError::EngineError(e) => write!(f, "engine error: {e}"),
+            PlayerError::Timeout(cmd) => write!(f, "engine timed out on '{cmd}'"),
+            PlayerError::Disconnected => write!(f, "engine disconnected"),
+            PlayerError::ProtocolError(e) => write!(f, "protocol error: {e}"),
+            PlayerError::Unsupported(e) => write!(f, "unsupported engine: {e}"),
+        }
+    }
 }
 
+impl std::error::Error for PlayerError {}
+
 impl From<std::io::Error> for PlayerError {
     fn from(value: std::io::Error) -> Self {
-        PlayerError::IoError(value)
+        match value.kind() {
+            ErrorKind::UnexpectedEof | ErrorKind::BrokenPipe | ErrorKind::ConnectionReset => {
+                PlayerError::Disconnected
+            }
+            _ => PlayerError::IoError(value),
+        }
     }
 }
 
-#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug, Serialize, Deserialize)]
 pub struct PlayerId(usize);
 impl PlayerId {
     pub fn new(id: usize) -> Self {
         PlayerId(id)
     }
 }
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PlayerInfo {
     pub player_id: PlayerId,
     pub team_id: TeamId,
@@ -31,20 +63,27 @@ pub struct PlayerInfo {
 
 pub struct PlayerHandle {
     pub player_id: PlayerId,
+    pub player_name: String,
 
     /// server -> player
     pub downlink_tx: Sender<ServerMessage>,
 }
 impl PlayerHandle {
-    pub fn new(player_id: PlayerId, downlink_tx: Sender<ServerMessage>) -> Self {
+    pub fn new(
+        player_id: PlayerId,
+        player_name: String,
+        downlink_tx: Sender<ServerMessage>,
+    ) -> Self {
         Self {
             player_id,
+            player_name,
             downlink_tx,
         }
     }
 
+    /// 发送消息; 棋手已经离开时忽略
     pub async fn send(&self, msg: ServerMessage) {
-        self.downlink_tx.send(msg).await.unwrap();
+        let _ = self.downlink_tx.send(msg).await;
     }
 }
 
@@ -52,8 +91,20 @@ pub trait PlayerTrait {
     fn run(self, uplink_tx: Sender<PlayerMessage>, downlink_rx: Receiver<ServerMessage>);
 
     fn player_id(&self) -> PlayerId;
+
+    /// 显示用的名字, 记录在棋谱里
+    fn player_name(&self) -> String {
+        format!("player-{}", self.player_id().0)
+    }
+
+    /// 能对弈的最大棋盘, 超过时 `GameBuilder::add_player` 拒绝这个棋手
+    fn max_board_size(&self) -> usize {
+        usize::MAX
+    }
 }
 
 pub mod channel_player;
 pub mod dummy_player;
-pub mod local_gnugo_player;
+pub mod gtp_client;
+pub mod gtp_engine_player;
+pub mod tcp_gtp_player;
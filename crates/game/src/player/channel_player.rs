@@ -34,6 +34,8 @@ impl PlayerTrait for ChannelPlayer {
                     Some(msg) = self.uplink_from_ui.recv() => {
                         uplink_tx.send(msg).await.unwrap();
                     }
+                    // 对局和界面都已经关闭
+                    else => break,
                 };
             }
         });
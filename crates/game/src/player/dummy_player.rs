@@ -1,95 +1,143 @@
-use std::time::Duration;
-
 use rand::{RngExt, rngs::StdRng};
-use rustgo::{Coord, Stone, board::Board};
-use tokio::{
-    sync::mpsc::{Receiver, Sender},
-    time::sleep,
+use rustgo::{
+    Coord, Stone,
+    board::{Board, GoBoard},
 };
+use tokio::sync::mpsc::{Receiver, Sender};
 
 use crate::{
-    Action, PlayerMessage, ServerMessage,
+    Action, GameStart, PlayerMessage, ServerMessage,
     player::{PlayerError, PlayerId, PlayerTrait},
 };
 
-pub struct DummyPlayer {
+/// 随机试这么多次还找不到合法的点就 pass
+const MAX_ATTEMPTS: usize = 100;
+
+/// 随机落子的Player, 棋盘可以替换为任意 `GoBoard` 的实现
+///
+/// 收到 `GameStart` 之后才有棋盘, 在此之前总是 pass
+pub struct DummyPlayer<B: GoBoard = Board> {
     player_id: PlayerId,
-    board: Board,
+    board: Option<B>,
     rng: StdRng,
 }
 
 impl DummyPlayer {
-    pub fn new(player_id: PlayerId, size: usize) -> Self {
+    pub fn new(player_id: PlayerId) -> Self {
+        DummyPlayer::with_board(player_id)
+    }
+}
+
+impl<B: GoBoard> DummyPlayer<B> {
+    /// 使用 `B` 作为棋盘, 如 `DummyPlayer::<BitBoard>::with_board(player_id)`
+    pub fn with_board(player_id: PlayerId) -> Self {
         DummyPlayer {
             player_id,
-            board: Board::new(size),
+            board: None,
             rng: rand::make_rng(),
         }
     }
 
-    pub fn random_coord(&mut self) -> Coord {
-        // TODO random of usize???
-        let idx = (self.rng.random::<u32>() % (self.board.size_square() as u32)) as usize;
-        return self.board.coord(idx);
+    /// 按开局信息准备棋盘并摆上让子
+    fn new_game(&mut self, start: &GameStart) {
+        let mut board = B::new(start.size);
+        if let Some(stone) = start.handicap_stone() {
+            for &coord in &start.handicap {
+                board.place_stone(coord, stone).unwrap();
+            }
+        }
+        self.board = Some(board);
+    }
+
+    pub fn random_coord(&mut self) -> Option<Coord> {
+        let size = self.board.as_ref()?.size();
+        Some(Coord::new(
+            self.rng.random_range(0..size),
+            self.rng.random_range(0..size),
+        ))
     }
 
     fn play(&mut self, stone: Stone, coord: Coord) -> Result<(), PlayerError> {
-        self.board.place_stone(coord, stone).unwrap();
+        if let Some(board) = &mut self.board {
+            board.place_stone(coord, stone).unwrap();
+        }
         Ok(())
     }
 
     fn genmove(&mut self, stone: Stone) -> Result<Action, PlayerError> {
-        let coord = self.random_coord();
-        return Ok(Action::Move { stone, coord });
+        for _ in 0..MAX_ATTEMPTS {
+            let Some(coord) = self.random_coord() else {
+                break;
+            };
+            if self.board.as_mut().unwrap().is_legal(coord, stone) {
+                return Ok(Action::Move { stone, coord });
+            }
+        }
+        return Ok(Action::Pass);
     }
 }
 
-impl PlayerTrait for DummyPlayer {
+impl<B: GoBoard + Send + 'static> PlayerTrait for DummyPlayer<B> {
     fn run(mut self, uplink_tx: Sender<PlayerMessage>, mut downlink_rx: Receiver<ServerMessage>) {
         tokio::spawn(async move {
-            loop {
-                if let Some(msg) = downlink_rx.recv().await {
-                    match msg {
-                        ServerMessage::PlayerMove { stone, coord, .. } => {
-                            self.play(stone, coord).unwrap();
-                        }
-                        ServerMessage::PlayerChat { player_id, chat } => {
-                            println!(
-                                "Player[{:?}] hear Player[{:?}] says: {}",
-                                self.player_id, player_id, chat
-                            );
-                        }
-                        ServerMessage::GenMove(stone) => {
-                            // sleep(Duration::from_secs(1)).await;
-                            let action = self.genmove(stone).unwrap();
+            let mut accepted = None;
+            while let Some(msg) = downlink_rx.recv().await {
+                match msg {
+                    ServerMessage::GameStart(start) => self.new_game(&start),
+                    ServerMessage::PlayerMove { stone, coord, .. } => {
+                        self.play(stone, coord).unwrap();
+                    }
+                    ServerMessage::PlayerChat { player_id, chat } => {
+                        println!(
+                            "Player[{:?}] hear Player[{:?}] says: {}",
+                            self.player_id, player_id, chat
+                        );
+                    }
+                    ServerMessage::GenMove(stone) => {
+                        // sleep(Duration::from_secs(1)).await;
+                        let action = self.genmove(stone).unwrap();
 
-                            uplink_tx
-                                .send(PlayerMessage::PlayerAction {
-                                    player_id: self.player_id,
-                                    action,
-                                })
-                                .await
-                                .unwrap();
+                        let msg = PlayerMessage::PlayerAction {
+                            player_id: self.player_id,
+                            action,
+                        };
+                        if uplink_tx.send(msg).await.is_err() {
+                            break;
+                        }
 
-                            uplink_tx
-                                .send(PlayerMessage::PlayerChat {
-                                    player_id: self.player_id,
-                                    chat: format!("i choose {:?}", action),
-                                })
-                                .await
-                                .unwrap();
+                        let msg = PlayerMessage::PlayerChat {
+                            player_id: self.player_id,
+                            chat: format!("i choose {:?}", action),
+                        };
+                        if uplink_tx.send(msg).await.is_err() {
+                            break;
+                        }
+                    }
+                    ServerMessage::Error(_) | ServerMessage::MoveRejected { .. } => {
+                        let msg = PlayerMessage::PlayerChat {
+                            player_id: self.player_id,
+                            chat: "oh shit".to_string(),
+                        };
+                        if uplink_tx.send(msg).await.is_err() {
+                            break;
+                        }
+                    }
+                    ServerMessage::ScoringStart { dead }
+                    | ServerMessage::DeadStones { dead, .. } => {
+                        // 同意任何死子
+                        if accepted.as_ref() == Some(&dead) {
+                            continue;
                         }
-                        ServerMessage::Error(_) => {
-                            uplink_tx
-                                .send(PlayerMessage::PlayerChat {
-                                    player_id: self.player_id,
-                                    chat: "oh shit".to_string(),
-                                })
-                                .await
-                                .unwrap();
+                        accepted = Some(dead);
+                        let msg = PlayerMessage::AcceptScore {
+                            player_id: self.player_id,
+                        };
+                        if uplink_tx.send(msg).await.is_err() {
+                            break;
                         }
-                        _ => {}
                     }
+                    ServerMessage::GameOver(_) => break,
+                    _ => {}
                 }
             }
         });
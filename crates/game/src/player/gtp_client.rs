@@ -0,0 +1,737 @@
+use std::time::Duration;
+
+use rustgo::{Coord, Stone, gtp};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    Action, GameStart, PlayerMessage,
+    clock::{ClockState, TimeControl},
+    player::{PlayerError, PlayerId},
+};
+
+/// 与传输方式无关的 GTP 引擎设置
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GtpOptions {
+    /// 每局开始时设置好棋盘之后依次执行的命令, 如 `time_settings 300 30 5`
+    pub startup_commands: Vec<String>,
+    /// 除 genmove 之外的命令的超时
+    pub command_timeout: Duration,
+    pub genmove_timeout: Duration,
+}
+
+impl Default for GtpOptions {
+    fn default() -> Self {
+        GtpOptions {
+            startup_commands: vec![],
+            command_timeout: Duration::from_secs(10),
+            genmove_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// 引擎通过 `name`/`version`/`list_commands` 报告的信息
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EngineInfo {
+    pub name: String,
+    pub version: String,
+    pub commands: Vec<String>,
+}
+
+impl EngineInfo {
+    pub fn supports(&self, command: &str) -> bool {
+        self.commands.iter().any(|c| c == command)
+    }
+
+    /// `name version`, 引擎没有报告名字时为 `None`
+    pub fn display_name(&self) -> Option<String> {
+        if self.name.is_empty() {
+            return None;
+        }
+        if self.version.is_empty() {
+            return Some(self.name.clone());
+        }
+        return Some(format!("{} {}", self.name, self.version));
+    }
+}
+
+/// 对局必须的命令
+const REQUIRED_COMMANDS: &[&str] = &["boardsize", "clear_board", "play", "genmove"];
+
+/// 异步的 GTP 客户端, 可以建立在进程的 stdin/stdout 或网络连接之上
+///
+/// 每条命令都带有递增的 id; 超时的命令之后才到达的响应会按 id 丢弃
+pub struct GtpClient<R, W> {
+    reader: R,
+    writer: W,
+    next_id: u32,
+    /// 读到一半的行与响应; 命令超时被取消时保留下来, 下一条命令接着读完再按 id 丢弃
+    line: String,
+    response: String,
+}
+
+/// 响应中的 id, 没有 id 时为 `None`
+fn response_id(text: &str) -> Option<u32> {
+    let digits: String = text
+        .chars()
+        .skip(1)
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> GtpClient<R, W> {
+    pub fn new(reader: R, writer: W) -> Self {
+        GtpClient {
+            reader,
+            writer,
+            next_id: 1,
+            line: String::new(),
+            response: String::new(),
+        }
+    }
+
+    /// 发送一条命令, 返回去掉 `=` 与 id 之后的响应
+    ///
+    /// 引擎返回 `?` 时为 `EngineError`, 超过 `timeout` 没有响应时为 `Timeout`
+    pub async fn command(&mut self, cmd: &str, timeout: Duration) -> Result<String, PlayerError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let resp = match tokio::time::timeout(timeout, self.exchange(id, cmd)).await {
+            Ok(resp) => resp?,
+            Err(_) => return Err(PlayerError::Timeout(cmd.to_string())),
+        };
+        match gtp::parse_response(&resp) {
+            Some(Ok(s)) => Ok(s),
+            Some(Err(e)) => Err(PlayerError::EngineError(format!("{cmd}: {e}"))),
+            None => Err(PlayerError::ProtocolError(format!(
+                "{cmd}: malformed response {resp:?}"
+            ))),
+        }
+    }
+
+    async fn exchange(&mut self, id: u32, cmd: &str) -> Result<String, PlayerError> {
+        self.writer
+            .write_all(format!("{id} {cmd}\n").as_bytes())
+            .await?;
+        self.writer.flush().await?;
+
+        loop {
+            let resp = self.read_response().await?;
+            match response_id(&resp) {
+                // 之前超时的命令的响应
+                Some(resp_id) if resp_id != id => continue,
+                _ => return Ok(resp),
+            }
+        }
+    }
+
+    /// 读一条完整的响应 (以空行结尾), 不含结尾的空行
+    ///
+    /// 读到的内容都先放在 `self` 中, 在任意 `.await` 处被取消也不会丢失
+    async fn read_response(&mut self) -> Result<String, PlayerError> {
+        loop {
+            let size = self.reader.read_line(&mut self.line).await?;
+            if size == 0 {
+                // EOF
+                return Err(PlayerError::Disconnected);
+            }
+            let line = std::mem::take(&mut self.line);
+
+            if line.trim_end_matches(['\r', '\n']).is_empty() {
+                if self.response.is_empty() {
+                    // 响应之前多余的空行
+                    continue;
+                }
+                // response end with a empty newline
+                return Ok(std::mem::take(&mut self.response));
+            }
+
+            self.response.push_str(&line);
+        }
+    }
+}
+
+/// 已经完成能力检查的 GTP 引擎, 收到 `GameStart` 之后由 `new_game` 设置棋盘
+///
+/// 引擎的棋盘只通过 `play` 更新: 优先用 `reg_genmove` 生成着法而不落子,
+/// 引擎不支持时用 `genmove` 之后再 `undo`
+pub struct EngineSession<R, W> {
+    client: GtpClient<R, W>,
+    /// `new_game` 之前为 0
+    size: usize,
+    /// 对局的颜色数, 多于 2 时引擎必须支持多色扩展的 `num_colors` 命令
+    num_colors: usize,
+    startup_commands: Vec<String>,
+    info: EngineInfo,
+    command_timeout: Duration,
+    genmove_timeout: Duration,
+}
+
+impl<R: AsyncBufRead + Unpin, W: AsyncWrite + Unpin> EngineSession<R, W> {
+    /// 检查协议版本与必须的命令
+    pub async fn start(client: GtpClient<R, W>, options: &GtpOptions) -> Result<Self, PlayerError> {
+        let mut session = EngineSession {
+            client,
+            size: 0,
+            num_colors: 2,
+            startup_commands: options.startup_commands.clone(),
+            info: EngineInfo::default(),
+            command_timeout: options.command_timeout,
+            genmove_timeout: options.genmove_timeout,
+        };
+
+        // 能力检查
+        let version = session.command("protocol_version").await?;
+        if version != "2" {
+            return Err(PlayerError::Unsupported(format!(
+                "GTP protocol version {version}"
+            )));
+        }
+        session.info.commands = session
+            .command("list_commands")
+            .await?
+            .lines()
+            .map(|s| s.trim().to_string())
+            .collect();
+        for required in REQUIRED_COMMANDS {
+            if !session.info.supports(required) {
+                return Err(PlayerError::Unsupported(format!("missing '{required}'")));
+            }
+        }
+        if !session.info.supports("reg_genmove") && !session.info.supports("undo") {
+            return Err(PlayerError::Unsupported(
+                "missing both 'reg_genmove' and 'undo'".to_string(),
+            ));
+        }
+        if session.info.supports("name") {
+            session.info.name = session.command("name").await?;
+        }
+        if session.info.supports("version") {
+            session.info.version = session.command("version").await?;
+        }
+        return Ok(session);
+    }
+
+    /// 按开局信息设置颜色数、棋盘、贴目与让子, 然后执行 `options.startup_commands`
+    pub async fn new_game(&mut self, start: &GameStart) -> Result<(), PlayerError> {
+        if start.size > gtp::MAX_SIZE {
+            return Err(PlayerError::Unsupported(format!(
+                "board size {} in GTP",
+                start.size
+            )));
+        }
+        let num_colors = start.teams.len();
+        if num_colors > 2 {
+            if !self.info.supports("num_colors") {
+                return Err(PlayerError::Unsupported(format!(
+                    "{num_colors} colors without 'num_colors'"
+                )));
+            }
+            self.command(&format!("num_colors {num_colors}")).await?;
+        }
+        self.num_colors = num_colors.max(2);
+        self.size = start.size;
+        self.command(&format!("boardsize {}", start.size)).await?;
+        self.command("clear_board").await?;
+        if self.info.supports("komi") {
+            self.command(&format!("komi {}", start.komi)).await?;
+        }
+        if let Some(stone) = start.handicap_stone() {
+            for &coord in &start.handicap {
+                self.play(stone, Some(coord)).await?;
+            }
+        }
+        for cmd in self.startup_commands.clone() {
+            self.command(&cmd).await?;
+        }
+        Ok(())
+    }
+
+    pub fn info(&self) -> &EngineInfo {
+        &self.info
+    }
+
+    pub async fn command(&mut self, cmd: &str) -> Result<String, PlayerError> {
+        self.client.command(cmd, self.command_timeout).await
+    }
+
+    fn color(&self, stone: Stone) -> Result<&'static str, PlayerError> {
+        match gtp::color_name(stone) {
+            Some(name) if stone.as_usize() <= self.num_colors => Ok(name),
+            _ => Err(PlayerError::Unsupported(format!("color {stone:?}"))),
+        }
+    }
+
+    pub async fn play(&mut self, stone: Stone, coord: Option<Coord>) -> Result<(), PlayerError> {
+        let color = self.color(stone)?;
+        let vertex = gtp::vertex_string(coord, self.size);
+        self.command(&format!("play {color} {vertex}")).await?;
+        Ok(())
+    }
+
+    /// 告诉引擎计时方式, 引擎不支持 `time_settings` 时忽略
+    pub async fn time_settings(&mut self, control: &TimeControl) -> Result<(), PlayerError> {
+        let Some((main, byo_yomi, stones)) = control.gtp_time_settings() else {
+            return Ok(());
+        };
+        if !self.info.supports("time_settings") {
+            return Ok(());
+        }
+        self.command(&format!("time_settings {main} {byo_yomi} {stones}"))
+            .await?;
+        Ok(())
+    }
+
+    /// 告诉引擎剩余时间, 引擎不支持 `time_left` 时忽略
+    pub async fn time_left(&mut self, clock: &ClockState) -> Result<(), PlayerError> {
+        if !self.info.supports("time_left") {
+            return Ok(());
+        }
+        let color = self.color(clock.stone)?;
+        let (time, stones) = clock.gtp_time_left;
+        self.command(&format!("time_left {color} {time} {stones}"))
+            .await?;
+        Ok(())
+    }
+
+    /// 引擎认为的死子 (`final_status_list dead`)
+    pub async fn final_status_dead(&mut self) -> Result<Vec<Coord>, PlayerError> {
+        if !self.info.supports("final_status_list") {
+            return Err(PlayerError::Unsupported(
+                "missing 'final_status_list'".to_string(),
+            ));
+        }
+        let resp = self.command("final_status_list dead").await?;
+        return resp
+            .split_whitespace()
+            .map(|vertex| match gtp::parse_vertex(vertex, self.size) {
+                Ok(Some(coord)) => Ok(coord),
+                _ => Err(PlayerError::ProtocolError(format!(
+                    "final_status_list: invalid vertex {vertex:?}"
+                ))),
+            })
+            .collect();
+    }
+
+    pub async fn genmove(&mut self, stone: Stone) -> Result<Action, PlayerError> {
+        let color = self.color(stone)?;
+        let reg = self.info.supports("reg_genmove");
+        let cmd = if reg {
+            format!("reg_genmove {color}")
+        } else {
+            format!("genmove {color}")
+        };
+        let resp = self.client.command(&cmd, self.genmove_timeout).await?;
+
+        if resp.eq_ignore_ascii_case("resign") {
+            return Ok(Action::Resign);
+        }
+        if !reg {
+            // 落子由之后的 PlayerMove 同步
+            self.command("undo").await?;
+        }
+        let coord = gtp::parse_vertex(&resp, self.size)
+            .map_err(|e| PlayerError::ProtocolError(format!("{cmd}: {e}: {resp:?}")))?;
+        match coord {
+            Some(coord) => Ok(Action::Move { stone, coord }),
+            None => Ok(Action::Pass),
+        }
+    }
+}
+
+/// `clocks` 中 `player_id` 下 `stone` 这一手用的时钟
+pub(crate) fn own_clock(
+    clocks: &[ClockState],
+    player_id: PlayerId,
+    stone: Stone,
+) -> Option<ClockState> {
+    clocks
+        .iter()
+        .find(|c| c.stone == stone && c.player_id.is_none_or(|id| id == player_id))
+        .cloned()
+}
+
+/// GTP 引擎在数子阶段的应答
+///
+/// 进入数子阶段时向引擎询问死子, 之后只同意与之相同的死子;
+/// 别人提出不同的死子时再提出一次引擎的死子, 同样的死子第二次出现时要求继续对局
+#[derive(Default)]
+pub(crate) struct ScoringAgent {
+    /// 引擎认为的死子; 引擎不能判断死活时为 `None`, 此时同意任何死子
+    proposal: Option<Vec<Coord>>,
+    /// 最近一次回应过的死子, 同样的死子只回应一次
+    answered: Option<Vec<Coord>>,
+    /// 已经反对过的死子
+    rejected: Vec<Vec<Coord>>,
+}
+
+/// 与 `Game` 一样按 (y, x) 排序并去重, 使死子可以直接比较
+fn sorted(mut dead: Vec<Coord>) -> Vec<Coord> {
+    dead.sort_by_key(|c| (c.y, c.x));
+    dead.dedup();
+    return dead;
+}
+
+impl ScoringAgent {
+    pub(crate) async fn start<R, W>(
+        &mut self,
+        session: &mut EngineSession<R, W>,
+        player_id: PlayerId,
+        dead: Vec<Coord>,
+    ) -> (PlayerMessage, Result<(), PlayerError>)
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        *self = ScoringAgent::default();
+        let res = match session.final_status_dead().await {
+            Ok(proposal) => {
+                self.proposal = Some(sorted(proposal));
+                Ok(())
+            }
+            Err(e) => Err(e),
+        };
+        // 刚开始时没有回应过任何死子, 一定有应答
+        let msg = self.respond(player_id, dead).unwrap();
+        return (msg, res);
+    }
+
+    /// 回应还没有回应过的死子: 与引擎的死子相同时同意, 否则提出引擎的死子,
+    /// 已经反对过的死子再次出现时要求继续对局
+    pub(crate) fn respond(
+        &mut self,
+        player_id: PlayerId,
+        dead: Vec<Coord>,
+    ) -> Option<PlayerMessage> {
+        let dead = sorted(dead);
+        if self.answered.as_ref() == Some(&dead) {
+            return None;
+        }
+        self.answered = Some(dead.clone());
+        let Some(proposal) = &self.proposal else {
+            return Some(PlayerMessage::AcceptScore { player_id });
+        };
+        if *proposal == dead {
+            return Some(PlayerMessage::AcceptScore { player_id });
+        }
+        if self.rejected.contains(&dead) {
+            return Some(PlayerMessage::ResumePlay { player_id });
+        }
+        self.rejected.push(dead);
+        return Some(PlayerMessage::ProposeDead {
+            player_id,
+            dead: proposal.clone(),
+        });
+    }
+}
+
+/// 测试用的开局信息, 每种颜色一队
+#[cfg(test)]
+pub(crate) fn game_start(size: usize, colors: usize) -> GameStart {
+    use crate::team::{TeamId, TeamInfo};
+
+    let teams: Vec<_> = (0..colors)
+        .map(|i| TeamInfo::new(TeamId::new(i), Stone::new(i as u8 + 1), vec![]))
+        .collect();
+    return GameStart {
+        size,
+        rules: crate::game::DEFAULT_RULES.to_string(),
+        komi: 7.5,
+        scoring: Default::default(),
+        handicap: vec![],
+        turn_order: teams.iter().map(|t| t.team_id()).collect(),
+        teams,
+    };
+}
+
+/// 用 rustgo 自带的 GTP 引擎应答, 处理 `limit` 条命令之后断开; 返回收到的命令
+#[cfg(test)]
+pub(crate) async fn serve_rustgo<R, W>(reader: R, mut writer: W, limit: usize) -> Vec<String>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    use rustgo::{bot::RandomBot, gtp::GtpEngine};
+
+    let mut engine = GtpEngine::new(Box::new(RandomBot::with_seed(7)));
+    let mut lines = tokio::io::BufReader::new(reader).lines();
+    let mut received = vec![];
+    while received.len() < limit {
+        let Ok(Some(line)) = lines.next_line().await else {
+            break;
+        };
+        let Some(cmd) = gtp::parse_command(&line) else {
+            continue;
+        };
+        let res = engine.handle(&cmd);
+        writer
+            .write_all(gtp::format_response(cmd.id, &res).as_bytes())
+            .await
+            .unwrap();
+        received.push(format!("{} {}", cmd.name, cmd.args.join(" ")));
+    }
+    return received;
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{BufReader, DuplexStream, ReadHalf, WriteHalf, duplex, split};
+
+    use super::*;
+
+    type Client = GtpClient<BufReader<ReadHalf<DuplexStream>>, WriteHalf<DuplexStream>>;
+
+    /// 客户端以及引擎一侧的读写端
+    fn pair() -> (
+        Client,
+        BufReader<ReadHalf<DuplexStream>>,
+        WriteHalf<DuplexStream>,
+    ) {
+        let (client, engine) = duplex(1024);
+        let (client_r, client_w) = split(client);
+        let (engine_r, engine_w) = split(engine);
+        return (
+            GtpClient::new(BufReader::new(client_r), client_w),
+            BufReader::new(engine_r),
+            engine_w,
+        );
+    }
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[tokio::test(start_paused = true)]
+    async fn ids_and_errors() {
+        let (mut client, mut engine_r, mut engine_w) = pair();
+        let engine = tokio::spawn(async move {
+            let mut line = String::new();
+            engine_r.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "1 name\n");
+            engine_w.write_all(b"\n=1 fake\n\n").await.unwrap();
+
+            line.clear();
+            engine_r.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "2 play B Z1\n");
+            engine_w
+                .write_all(b"?2 invalid coordinate\n\n")
+                .await
+                .unwrap();
+
+            line.clear();
+            engine_r.read_line(&mut line).await.unwrap();
+            engine_w.write_all(b"oops\n\n").await.unwrap();
+        });
+
+        assert_eq!(client.command("name", TIMEOUT).await.unwrap(), "fake");
+        assert!(matches!(
+            client.command("play B Z1", TIMEOUT).await,
+            Err(PlayerError::EngineError(e)) if e == "play B Z1: invalid coordinate"
+        ));
+        assert!(matches!(
+            client.command("version", TIMEOUT).await,
+            Err(PlayerError::ProtocolError(_))
+        ));
+        engine.await.unwrap();
+
+        // 引擎退出
+        assert!(matches!(
+            client.command("name", TIMEOUT).await,
+            Err(PlayerError::Disconnected)
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_and_stale_response() {
+        let (mut client, mut engine_r, mut engine_w) = pair();
+        let engine = tokio::spawn(async move {
+            let mut line = String::new();
+            engine_r.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "1 genmove B\n");
+            // 超时之后才响应
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            engine_w.write_all(b"=1 D4\n\n").await.unwrap();
+
+            line.clear();
+            engine_r.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "2 genmove W\n");
+            engine_w.write_all(b"=2 Q16\n\n").await.unwrap();
+        });
+
+        assert!(matches!(
+            client.command("genmove B", TIMEOUT).await,
+            Err(PlayerError::Timeout(cmd)) if cmd == "genmove B"
+        ));
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(client.command("genmove W", TIMEOUT).await.unwrap(), "Q16");
+        engine.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_in_multi_line_response() {
+        let (mut client, mut engine_r, mut engine_w) = pair();
+        let engine = tokio::spawn(async move {
+            let mut line = String::new();
+            engine_r.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "1 list_commands\n");
+            // 响应写到一半时超时
+            engine_w.write_all(b"=1 name\nver").await.unwrap();
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            engine_w.write_all(b"sion\nplay\n\n").await.unwrap();
+
+            line.clear();
+            engine_r.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "2 name\n");
+            engine_w.write_all(b"=2 engine\n\n").await.unwrap();
+        });
+
+        assert!(matches!(
+            client.command("list_commands", TIMEOUT).await,
+            Err(PlayerError::Timeout(_))
+        ));
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(client.command("name", TIMEOUT).await.unwrap(), "engine");
+        engine.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn multi_color_session() {
+        let (client, engine) = duplex(4096);
+        let (client_r, client_w) = split(client);
+        let (engine_r, engine_w) = split(engine);
+        let engine = tokio::spawn(serve_rustgo(engine_r, engine_w, usize::MAX));
+
+        let client = GtpClient::new(BufReader::new(client_r), client_w);
+        let mut session = EngineSession::start(client, &GtpOptions::default())
+            .await
+            .unwrap();
+        let mut start = game_start(9, 3);
+        start.handicap = vec![Coord::new(2, 6), Coord::new(6, 2)];
+        session.new_game(&start).await.unwrap();
+        session
+            .play(Stone::new(3), Some(Coord::new(4, 4)))
+            .await
+            .unwrap();
+        assert!(matches!(
+            session.genmove(Stone::new(3)).await.unwrap(),
+            Action::Move { stone, .. } if stone == Stone::new(3)
+        ));
+        assert!(matches!(
+            session.genmove(Stone::new(4)).await,
+            Err(PlayerError::Unsupported(_))
+        ));
+        drop(session);
+
+        let received = engine.await.unwrap();
+        assert_eq!(
+            received[4..10],
+            [
+                "num_colors 3",
+                "boardsize 9",
+                "clear_board ",
+                "komi 7.5",
+                "play B C3",
+                "play B G7"
+            ]
+        );
+        assert_eq!(received[10], "play C3 E5");
+        assert_eq!(received[11], "genmove C3");
+    }
+
+    #[tokio::test]
+    async fn time_commands() {
+        let (client, engine) = duplex(4096);
+        let (client_r, client_w) = split(client);
+        let (engine_r, engine_w) = split(engine);
+        let engine = tokio::spawn(serve_rustgo(engine_r, engine_w, usize::MAX));
+
+        let client = GtpClient::new(BufReader::new(client_r), client_w);
+        let mut session = EngineSession::start(client, &GtpOptions::default())
+            .await
+            .unwrap();
+        session.new_game(&game_start(9, 2)).await.unwrap();
+        session
+            .time_settings(&TimeControl::Unlimited)
+            .await
+            .unwrap();
+        let control = TimeControl::Canadian {
+            main: Duration::from_secs(300),
+            period: Duration::from_secs(60),
+            stones: 10,
+        };
+        session.time_settings(&control).await.unwrap();
+        let clock = ClockState {
+            team_id: crate::team::TeamId::new(1),
+            player_id: None,
+            stone: Stone::WHITE,
+            remaining_ms: 45_000,
+            gtp_time_left: (45, 7),
+        };
+        assert_eq!(
+            own_clock(std::slice::from_ref(&clock), PlayerId::new(3), Stone::WHITE),
+            Some(clock.clone())
+        );
+        assert_eq!(
+            own_clock(std::slice::from_ref(&clock), PlayerId::new(3), Stone::BLACK),
+            None
+        );
+        session.time_left(&clock).await.unwrap();
+        drop(session);
+
+        let received = engine.await.unwrap();
+        assert_eq!(
+            received[7..],
+            ["time_settings 300 60 10", "time_left W 45 7"]
+        );
+    }
+
+    #[tokio::test]
+    async fn scoring_agent() {
+        let (client, engine) = duplex(4096);
+        let (client_r, client_w) = split(client);
+        let (engine_r, engine_w) = split(engine);
+        tokio::spawn(serve_rustgo(engine_r, engine_w, usize::MAX));
+
+        let client = GtpClient::new(BufReader::new(client_r), client_w);
+        let mut session = EngineSession::start(client, &GtpOptions::default())
+            .await
+            .unwrap();
+        session.new_game(&game_start(9, 2)).await.unwrap();
+        session
+            .play(Stone::BLACK, Some(Coord::new(4, 4)))
+            .await
+            .unwrap();
+        assert_eq!(session.final_status_dead().await.unwrap(), vec![]);
+
+        let player_id = PlayerId::new(0);
+        let mut agent = ScoringAgent::default();
+        // rustgo 不判断死子, 提出空的死子
+        let (msg, res) = agent
+            .start(&mut session, player_id, vec![Coord::new(4, 4)])
+            .await;
+        assert!(res.is_ok());
+        assert!(matches!(msg, PlayerMessage::ProposeDead { dead, .. } if dead.is_empty()));
+        // 只同意引擎的死子, 且只同意一次
+        assert!(matches!(
+            agent.respond(player_id, vec![]),
+            Some(PlayerMessage::AcceptScore { .. })
+        ));
+        assert!(agent.respond(player_id, vec![]).is_none());
+        // 反对过的死子再次出现时要求继续对局
+        assert!(matches!(
+            agent.respond(player_id, vec![Coord::new(4, 4)]),
+            Some(PlayerMessage::ResumePlay { .. })
+        ));
+
+        let (msg, _) = agent.start(&mut session, player_id, vec![]).await;
+        assert!(matches!(msg, PlayerMessage::AcceptScore { .. }));
+        assert!(agent.respond(player_id, vec![]).is_none());
+        // 别人把引擎的活棋标成死子时, 再提出一次引擎的死子
+        assert!(matches!(
+            agent.respond(player_id, vec![Coord::new(4, 4)]),
+            Some(PlayerMessage::ProposeDead { dead, .. }) if dead.is_empty()
+        ));
+        assert!(matches!(
+            agent.respond(player_id, vec![]),
+            Some(PlayerMessage::AcceptScore { .. })
+        ));
+    }
+}
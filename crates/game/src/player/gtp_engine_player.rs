@@ -0,0 +1,373 @@
+use std::{path::PathBuf, process::Stdio, time::Duration};
+
+use rustgo::{Coord, Stone, gtp};
+use tokio::{
+    io::BufReader,
+    process::{Child, ChildStdin, ChildStdout, Command},
+    sync::mpsc::{Receiver, Sender},
+};
+
+use crate::{
+    Action, PlayerMessage, ServerMessage,
+    clock::ClockState,
+    player::{
+        PlayerError, PlayerId, PlayerTrait,
+        gtp_client::{EngineInfo, EngineSession, GtpClient, GtpOptions, ScoringAgent, own_clock},
+    },
+};
+
+/// 启动本地 GTP 引擎进程所需的配置
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GtpEngineConfig {
+    pub program: String,
+    pub args: Vec<String>,
+    /// 引擎的工作目录, `None` 时继承当前进程的
+    pub working_dir: Option<PathBuf>,
+    pub options: GtpOptions,
+}
+
+impl GtpEngineConfig {
+    pub fn new(program: &str, args: &[&str]) -> Self {
+        GtpEngineConfig {
+            program: program.to_string(),
+            args: args.iter().map(|s| s.to_string()).collect(),
+            working_dir: None,
+            options: GtpOptions::default(),
+        }
+    }
+
+    /// GNU Go, 中国规则
+    pub fn gnugo() -> Self {
+        GtpEngineConfig::new("gnugo", &["--mode=gtp", "--chinese-rules", "--level=10"])
+    }
+}
+
+/// 通过 GTP 协议驱动本地引擎进程 (GNU Go、KataGo、Leela Zero、Pachi 等) 的 Player
+pub struct GtpEnginePlayer {
+    player_id: PlayerId,
+    /// drop 时杀掉引擎进程
+    child: Child,
+    session: EngineSession<BufReader<ChildStdout>, ChildStdin>,
+    /// 最近一次 `GameUpdate` 中的时钟
+    clocks: Vec<ClockState>,
+    scoring: ScoringAgent,
+}
+
+impl GtpEnginePlayer {
+    /// 启动引擎并完成能力检查, 棋盘在收到 `GameStart` 时设置
+    pub async fn new(
+        player_id: PlayerId,
+        config: &GtpEngineConfig,
+    ) -> Result<GtpEnginePlayer, PlayerError> {
+        let mut command = Command::new(&config.program);
+        command
+            .args(&config.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(dir) = &config.working_dir {
+            command.current_dir(dir);
+        }
+        let mut child = command.spawn().inspect_err(|e| {
+            eprintln!("Failed to start GTP engine '{}': {}", config.program, e);
+        })?;
+
+        let reader = BufReader::new(child.stdout.take().unwrap());
+        let writer = child.stdin.take().unwrap();
+        let client = GtpClient::new(reader, writer);
+        let session = EngineSession::start(client, &config.options).await?;
+        return Ok(GtpEnginePlayer {
+            player_id,
+            child,
+            session,
+            clocks: vec![],
+            scoring: ScoringAgent::default(),
+        });
+    }
+
+    pub fn info(&self) -> &EngineInfo {
+        self.session.info()
+    }
+
+    /// 直接向引擎发送一条命令
+    pub async fn command(&mut self, cmd: &str) -> Result<String, PlayerError> {
+        self.session.command(cmd).await
+    }
+
+    async fn play(&mut self, stone: Stone, coord: Option<Coord>) -> Result<(), PlayerError> {
+        self.session.play(stone, coord).await
+    }
+
+    async fn genmove(&mut self, stone: Stone) -> Result<Action, PlayerError> {
+        if let Some(clock) = own_clock(&self.clocks, self.player_id, stone) {
+            self.session.time_left(&clock).await?;
+        }
+        self.session.genmove(stone).await
+    }
+
+    /// 引擎断开后的退出状态, 只用于日志
+    ///
+    /// 关闭 stdout 之后进程可能还没有结束, 所以稍等一会
+    async fn exit_status(&mut self) -> String {
+        match tokio::time::timeout(Duration::from_secs(1), self.child.wait()).await {
+            Ok(Ok(status)) => status.to_string(),
+            _ => "still running".to_string(),
+        }
+    }
+}
+
+impl PlayerTrait for GtpEnginePlayer {
+    fn run(mut self, uplink_tx: Sender<PlayerMessage>, mut downlink_rx: Receiver<ServerMessage>) {
+        tokio::spawn(async move {
+            while let Some(msg) = downlink_rx.recv().await {
+                let res = match msg {
+                    ServerMessage::GameStart(start) => self.session.new_game(&start).await,
+                    ServerMessage::PlayerMove { stone, coord, .. } => {
+                        self.play(stone, Some(coord)).await
+                    }
+                    ServerMessage::PlayerPass { stone, .. } => self.play(stone, None).await,
+                    ServerMessage::TimeSettings(settings) => {
+                        self.session.time_settings(&settings.control).await
+                    }
+                    ServerMessage::GameUpdate { clocks, .. } => {
+                        self.clocks = clocks;
+                        Ok(())
+                    }
+                    ServerMessage::GenMove(stone) => {
+                        let (action, res) = match self.genmove(stone).await {
+                            Ok(action) => (action, Ok(())),
+                            // 引擎出错时认输
+                            Err(e) => (Action::Resign, Err(e)),
+                        };
+                        let msg = PlayerMessage::PlayerAction {
+                            player_id: self.player_id,
+                            action,
+                        };
+                        if uplink_tx.send(msg).await.is_err() {
+                            break;
+                        }
+                        res
+                    }
+                    ServerMessage::ScoringStart { dead } => {
+                        let (msg, res) = self
+                            .scoring
+                            .start(&mut self.session, self.player_id, dead)
+                            .await;
+                        if uplink_tx.send(msg).await.is_err() {
+                            break;
+                        }
+                        res
+                    }
+                    ServerMessage::DeadStones { dead, .. } => {
+                        if let Some(msg) = self.scoring.respond(self.player_id, dead)
+                            && uplink_tx.send(msg).await.is_err()
+                        {
+                            break;
+                        }
+                        Ok(())
+                    }
+                    ServerMessage::GameOver(_) => break,
+                    _ => Ok(()),
+                };
+
+                match res {
+                    Ok(()) => {}
+                    Err(PlayerError::Disconnected) => {
+                        let status = self.exit_status().await;
+                        eprintln!(
+                            "Player[{:?}] engine disconnected ({})",
+                            self.player_id, status
+                        );
+                        break;
+                    }
+                    Err(e) => eprintln!("Player[{:?}] {}", self.player_id, e),
+                }
+            }
+        });
+    }
+
+    fn player_id(&self) -> PlayerId {
+        self.player_id
+    }
+
+    fn player_name(&self) -> String {
+        self.info()
+            .display_name()
+            .unwrap_or_else(|| format!("player-{}", self.player_id.0))
+    }
+
+    /// GTP 的坐标只有 `gtp::MAX_SIZE` 列
+    fn max_board_size(&self) -> usize {
+        gtp::MAX_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustgo::board::Board;
+
+    use super::*;
+    use crate::player::gtp_client::game_start;
+
+    /// 测试用的假引擎, 参数为 genmove 依次返回的着法
+    fn fake_engine(moves: &[&str]) -> GtpEngineConfig {
+        let script = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/fake_gtp.sh");
+        let mut args = vec![script];
+        args.extend_from_slice(moves);
+        GtpEngineConfig::new("sh", &args)
+    }
+
+    #[tokio::test]
+    async fn fake_engine_actions() {
+        let config = fake_engine(&["D4", "pass", "resign", "Z99"]);
+        let mut player = GtpEnginePlayer::new(PlayerId::new(0), &config)
+            .await
+            .unwrap();
+        assert_eq!(player.info().name, "fake");
+        assert!(!player.info().supports("reg_genmove"));
+        assert_eq!(player.player_name(), "fake 1.0");
+        player.session.new_game(&game_start(9, 2)).await.unwrap();
+
+        player
+            .play(Stone::BLACK, Some(Coord::new(0, 0)))
+            .await
+            .unwrap();
+        assert_eq!(
+            player.genmove(Stone::WHITE).await.unwrap(),
+            Action::Move {
+                stone: Stone::WHITE,
+                coord: Coord::new(3, 5),
+            }
+        );
+        assert_eq!(player.genmove(Stone::BLACK).await.unwrap(), Action::Pass);
+        assert_eq!(player.genmove(Stone::WHITE).await.unwrap(), Action::Resign);
+
+        // 引擎拒绝的命令
+        assert!(matches!(
+            player.command("fail").await,
+            Err(PlayerError::EngineError(e)) if e == "fail: boom"
+        ));
+        // 引擎返回无法解析的坐标
+        assert!(matches!(
+            player.genmove(Stone::BLACK).await,
+            Err(PlayerError::ProtocolError(_))
+        ));
+        assert!(matches!(
+            player.genmove(Stone::new(3)).await,
+            Err(PlayerError::Unsupported(_))
+        ));
+
+        // 引擎退出
+        assert!(matches!(
+            player.command("die").await,
+            Err(PlayerError::Disconnected)
+        ));
+        assert_eq!(player.exit_status().await, "exit status: 3");
+        assert!(matches!(
+            player.command("name").await,
+            Err(PlayerError::Disconnected)
+        ));
+    }
+
+    #[tokio::test]
+    async fn fake_engine_startup_commands() {
+        let mut config = fake_engine(&[]);
+        config.options.startup_commands = vec!["komi 6.5".to_string(), "fail".to_string()];
+        let mut player = GtpEnginePlayer::new(PlayerId::new(0), &config)
+            .await
+            .unwrap();
+        let res = player.session.new_game(&game_start(9, 2)).await;
+        assert!(matches!(res, Err(PlayerError::EngineError(_))));
+
+        // 假引擎不支持多色扩展
+        config.options.startup_commands = vec!["komi 6.5".to_string()];
+        config.working_dir = Some(std::env::temp_dir());
+        let mut player = GtpEnginePlayer::new(PlayerId::new(0), &config)
+            .await
+            .unwrap();
+        let res = player.session.new_game(&game_start(9, 3)).await;
+        assert!(matches!(res, Err(PlayerError::Unsupported(_))));
+        assert!(player.session.new_game(&game_start(9, 2)).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn fake_engine_timeout() {
+        let mut config = fake_engine(&["D4"]);
+        config.options.command_timeout = Duration::from_millis(200);
+        let mut player = GtpEnginePlayer::new(PlayerId::new(0), &config)
+            .await
+            .unwrap();
+        player.session.new_game(&game_start(9, 2)).await.unwrap();
+        assert!(matches!(
+            player.command("hang").await,
+            Err(PlayerError::Timeout(cmd)) if cmd == "hang"
+        ));
+        // 迟到的响应被丢弃
+        assert_eq!(
+            player.genmove(Stone::BLACK).await.unwrap(),
+            Action::Move {
+                stone: Stone::BLACK,
+                coord: Coord::new(3, 5),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_engine() {
+        let config = GtpEngineConfig::new("rustgo-no-such-engine", &[]);
+        let res = GtpEnginePlayer::new(PlayerId::new(0), &config).await;
+        assert!(matches!(res, Err(PlayerError::IoError(_))));
+
+        // 不说 GTP 的程序
+        let config = GtpEngineConfig::new("sh", &["-c", "exit 0"]);
+        let res = GtpEnginePlayer::new(PlayerId::new(0), &config).await;
+        assert!(matches!(res, Err(PlayerError::Disconnected)));
+    }
+
+    #[tokio::test]
+    #[ignore = "requires gnugo"]
+    async fn two_gnugo() {
+        const BOARD_SIZE: usize = 9;
+        let config = GtpEngineConfig::gnugo();
+        let mut board = Board::new(BOARD_SIZE);
+        let mut players = [
+            GtpEnginePlayer::new(PlayerId::new(0), &config)
+                .await
+                .unwrap(),
+            GtpEnginePlayer::new(PlayerId::new(1), &config)
+                .await
+                .unwrap(),
+        ];
+        for player in &mut players {
+            player
+                .session
+                .new_game(&game_start(BOARD_SIZE, 2))
+                .await
+                .unwrap();
+        }
+
+        let mut stone = Stone::BLACK;
+        let mut passes = 0;
+        while passes < 2 {
+            let idx = stone.as_usize() - 1;
+            match players[idx].genmove(stone).await.unwrap() {
+                Action::Move { stone, coord } => {
+                    passes = 0;
+                    board.place_stone(coord, stone).unwrap();
+                    for player in &mut players {
+                        player.play(stone, Some(coord)).await.unwrap();
+                    }
+                }
+                Action::Pass => {
+                    passes += 1;
+                    for player in &mut players {
+                        player.play(stone, None).await.unwrap();
+                    }
+                }
+                Action::Resign => break,
+            }
+            stone = stone.next_stone(2);
+            println!("{}", board.board_string());
+        }
+    }
+}
@@ -0,0 +1,533 @@
+use std::time::Duration;
+
+use rustgo::{Coord, Stone, gtp};
+use tokio::{
+    io::BufReader,
+    net::{
+        TcpListener, TcpStream,
+        tcp::{OwnedReadHalf, OwnedWriteHalf},
+    },
+    sync::mpsc::{Receiver, Sender},
+    time::sleep,
+};
+
+use crate::{
+    Action, GameStart, PlayerMessage, ServerMessage,
+    clock::{ClockState, TimeControl},
+    player::{
+        PlayerError, PlayerId, PlayerTrait,
+        gtp_client::{EngineInfo, EngineSession, GtpClient, GtpOptions, ScoringAgent, own_clock},
+    },
+};
+
+/// 连接断开之后如何重连
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// 最多尝试几次, 0 表示不重连
+    pub max_attempts: usize,
+    /// 主动连接时每次尝试之前等待的时间; 监听时每次等待对方连入的最长时间
+    pub delay: Duration,
+}
+
+impl ReconnectPolicy {
+    pub fn never() -> Self {
+        ReconnectPolicy {
+            max_attempts: 0,
+            delay: Duration::ZERO,
+        }
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            max_attempts: 5,
+            delay: Duration::from_secs(2),
+        }
+    }
+}
+
+enum Endpoint {
+    /// 连接到远程引擎监听的地址
+    Connect(String),
+    /// 等待远程引擎连入
+    Listen(TcpListener),
+}
+
+type TcpSession = EngineSession<BufReader<OwnedReadHalf>, OwnedWriteHalf>;
+
+/// 通过 TCP 与远程 GTP 引擎对弈的 Player
+///
+/// 连接断开后按 `ReconnectPolicy` 重连, 并把开局信息和之前的着法重放给新的连接
+pub struct TcpGtpPlayer {
+    player_id: PlayerId,
+    options: GtpOptions,
+    policy: ReconnectPolicy,
+    endpoint: Endpoint,
+    session: TcpSession,
+    /// 最近一次 `GameStart`, 重连之后重新设置棋盘
+    game: Option<GameStart>,
+    /// 引擎已经收到的着法, 重连之后重放
+    moves: Vec<(Stone, Option<Coord>)>,
+    /// 重连之后重新发送
+    time_control: TimeControl,
+    /// 最近一次 `GameUpdate` 中的时钟
+    clocks: Vec<ClockState>,
+    scoring: ScoringAgent,
+}
+
+impl TcpGtpPlayer {
+    /// 连接到 `addr` 上监听的引擎
+    pub async fn connect(
+        player_id: PlayerId,
+        addr: &str,
+        options: GtpOptions,
+        policy: ReconnectPolicy,
+    ) -> Result<TcpGtpPlayer, PlayerError> {
+        return Self::new(
+            player_id,
+            Endpoint::Connect(addr.to_string()),
+            options,
+            policy,
+        )
+        .await;
+    }
+
+    /// 在 `listener` 上等待引擎连入
+    pub async fn listen(
+        player_id: PlayerId,
+        listener: TcpListener,
+        options: GtpOptions,
+        policy: ReconnectPolicy,
+    ) -> Result<TcpGtpPlayer, PlayerError> {
+        return Self::new(player_id, Endpoint::Listen(listener), options, policy).await;
+    }
+
+    async fn new(
+        player_id: PlayerId,
+        endpoint: Endpoint,
+        options: GtpOptions,
+        policy: ReconnectPolicy,
+    ) -> Result<TcpGtpPlayer, PlayerError> {
+        let session = Self::open(&endpoint, &options).await?;
+        return Ok(TcpGtpPlayer {
+            player_id,
+            options,
+            policy,
+            endpoint,
+            session,
+            game: None,
+            moves: vec![],
+            time_control: TimeControl::Unlimited,
+            clocks: vec![],
+            scoring: ScoringAgent::default(),
+        });
+    }
+
+    async fn open(endpoint: &Endpoint, options: &GtpOptions) -> Result<TcpSession, PlayerError> {
+        let stream = match endpoint {
+            Endpoint::Connect(addr) => TcpStream::connect(addr).await?,
+            Endpoint::Listen(listener) => listener.accept().await?.0,
+        };
+        stream.set_nodelay(true)?;
+        let (reader, writer) = stream.into_split();
+        let client = GtpClient::new(BufReader::new(reader), writer);
+        return EngineSession::start(client, options).await;
+    }
+
+    /// 重新设置新连接的棋盘并重放着法
+    async fn restore(&self, session: &mut TcpSession) -> Result<(), PlayerError> {
+        if let Some(start) = &self.game {
+            session.new_game(start).await?;
+        }
+        session.time_settings(&self.time_control).await?;
+        for &(stone, coord) in &self.moves {
+            session.play(stone, coord).await?;
+        }
+        return Ok(());
+    }
+
+    /// 重新建立连接, 然后重新设置棋盘并重放着法; 重放时断开也算一次失败的尝试
+    async fn reconnect(&mut self) -> Result<(), PlayerError> {
+        let mut last_err = PlayerError::Disconnected;
+        for attempt in 1..=self.policy.max_attempts {
+            let res = match &self.endpoint {
+                Endpoint::Connect(_) => {
+                    sleep(self.policy.delay).await;
+                    Self::open(&self.endpoint, &self.options).await
+                }
+                Endpoint::Listen(_) => {
+                    let open = Self::open(&self.endpoint, &self.options);
+                    match tokio::time::timeout(self.policy.delay, open).await {
+                        Ok(res) => res,
+                        Err(_) => Err(PlayerError::Disconnected),
+                    }
+                }
+            };
+            let e = match res {
+                Ok(mut session) => match self.restore(&mut session).await {
+                    Ok(()) => {
+                        self.session = session;
+                        return Ok(());
+                    }
+                    Err(PlayerError::Disconnected) => PlayerError::Disconnected,
+                    Err(e) => return Err(e),
+                },
+                Err(e) => e,
+            };
+            eprintln!(
+                "Player[{:?}] reconnect attempt {attempt} failed: {e}",
+                self.player_id
+            );
+            last_err = e;
+        }
+        return Err(last_err);
+    }
+
+    pub fn info(&self) -> &EngineInfo {
+        self.session.info()
+    }
+
+    async fn play(&mut self, stone: Stone, coord: Option<Coord>) -> Result<(), PlayerError> {
+        match self.session.play(stone, coord).await {
+            Ok(()) => {
+                self.moves.push((stone, coord));
+                Ok(())
+            }
+            Err(PlayerError::Disconnected) => {
+                // 重连时一起重放
+                self.moves.push((stone, coord));
+                self.reconnect().await
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 开始新的一局, 之前的着法不再重放
+    async fn new_game(&mut self, start: GameStart) -> Result<(), PlayerError> {
+        self.moves.clear();
+        let res = self.session.new_game(&start).await;
+        self.game = Some(start);
+        match res {
+            Err(PlayerError::Disconnected) => self.reconnect().await,
+            res => res,
+        }
+    }
+
+    async fn time_settings(&mut self, control: TimeControl) -> Result<(), PlayerError> {
+        self.time_control = control;
+        match self.session.time_settings(&control).await {
+            Err(PlayerError::Disconnected) => self.reconnect().await,
+            res => res,
+        }
+    }
+
+    /// 进入数子阶段时向引擎询问死子, 连接断开时重连之后再问一次
+    async fn scoring_start(
+        &mut self,
+        dead: Vec<Coord>,
+    ) -> (PlayerMessage, Result<(), PlayerError>) {
+        let (msg, res) = self
+            .scoring
+            .start(&mut self.session, self.player_id, dead.clone())
+            .await;
+        if !matches!(res, Err(PlayerError::Disconnected)) {
+            return (msg, res);
+        }
+        if let Err(e) = self.reconnect().await {
+            return (msg, Err(e));
+        }
+        return self
+            .scoring
+            .start(&mut self.session, self.player_id, dead)
+            .await;
+    }
+
+    async fn genmove(&mut self, stone: Stone) -> Result<Action, PlayerError> {
+        let res = match own_clock(&self.clocks, self.player_id, stone) {
+            Some(clock) => self.session.time_left(&clock).await,
+            None => Ok(()),
+        };
+        let res = match res {
+            Ok(()) => self.session.genmove(stone).await,
+            Err(e) => Err(e),
+        };
+        match res {
+            Err(PlayerError::Disconnected) => {
+                self.reconnect().await?;
+                self.session.genmove(stone).await
+            }
+            res => res,
+        }
+    }
+}
+
+impl PlayerTrait for TcpGtpPlayer {
+    fn run(mut self, uplink_tx: Sender<PlayerMessage>, mut downlink_rx: Receiver<ServerMessage>) {
+        tokio::spawn(async move {
+            while let Some(msg) = downlink_rx.recv().await {
+                let res = match msg {
+                    ServerMessage::GameStart(start) => self.new_game(start).await,
+                    ServerMessage::PlayerMove { stone, coord, .. } => {
+                        self.play(stone, Some(coord)).await
+                    }
+                    ServerMessage::PlayerPass { stone, .. } => self.play(stone, None).await,
+                    ServerMessage::TimeSettings(settings) => {
+                        self.time_settings(settings.control).await
+                    }
+                    ServerMessage::GameUpdate { clocks, .. } => {
+                        self.clocks = clocks;
+                        Ok(())
+                    }
+                    ServerMessage::GenMove(stone) => {
+                        let (action, res) = match self.genmove(stone).await {
+                            Ok(action) => (action, Ok(())),
+                            // 引擎出错时认输
+                            Err(e) => (Action::Resign, Err(e)),
+                        };
+                        let msg = PlayerMessage::PlayerAction {
+                            player_id: self.player_id,
+                            action,
+                        };
+                        if uplink_tx.send(msg).await.is_err() {
+                            break;
+                        }
+                        res
+                    }
+                    ServerMessage::ScoringStart { dead } => {
+                        let (msg, res) = self.scoring_start(dead).await;
+                        if uplink_tx.send(msg).await.is_err() {
+                            break;
+                        }
+                        res
+                    }
+                    ServerMessage::DeadStones { dead, .. } => {
+                        if let Some(msg) = self.scoring.respond(self.player_id, dead)
+                            && uplink_tx.send(msg).await.is_err()
+                        {
+                            break;
+                        }
+                        Ok(())
+                    }
+                    ServerMessage::GameOver(_) => break,
+                    _ => Ok(()),
+                };
+
+                match res {
+                    Ok(()) => {}
+                    Err(PlayerError::Disconnected) => {
+                        eprintln!("Player[{:?}] remote engine is gone", self.player_id);
+                        break;
+                    }
+                    Err(e) => eprintln!("Player[{:?}] {}", self.player_id, e),
+                }
+            }
+        });
+    }
+
+    fn player_id(&self) -> PlayerId {
+        self.player_id
+    }
+
+    fn player_name(&self) -> String {
+        self.info()
+            .display_name()
+            .unwrap_or_else(|| format!("player-{}", self.player_id.0))
+    }
+
+    /// GTP 的坐标只有 `gtp::MAX_SIZE` 列
+    fn max_board_size(&self) -> usize {
+        gtp::MAX_SIZE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        game::{GameBuilder, GameConfigError},
+        player::gtp_client::{game_start, serve_rustgo},
+        team::TeamId,
+    };
+
+    async fn serve(stream: TcpStream, limit: usize) -> Vec<String> {
+        let (reader, writer) = stream.into_split();
+        return serve_rustgo(reader, writer, limit).await;
+    }
+
+    fn policy() -> ReconnectPolicy {
+        ReconnectPolicy {
+            max_attempts: 3,
+            delay: Duration::from_millis(10),
+        }
+    }
+
+    /// 握手与开局时的 7 条命令
+    const HANDSHAKE: usize = 7;
+
+    #[tokio::test]
+    async fn connect_and_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            // 第一个连接在两手之后断开
+            let (stream, _) = listener.accept().await.unwrap();
+            let first = serve(stream, HANDSHAKE + 2).await;
+            let (stream, _) = listener.accept().await.unwrap();
+            let second = serve(stream, usize::MAX).await;
+            (first, second)
+        });
+
+        let mut player =
+            TcpGtpPlayer::connect(PlayerId::new(0), &addr, GtpOptions::default(), policy())
+                .await
+                .unwrap();
+        player.new_game(game_start(9, 2)).await.unwrap();
+        assert_eq!(
+            player.player_name(),
+            format!("rustgo {}", player.info().version)
+        );
+
+        player
+            .play(Stone::BLACK, Some(Coord::new(4, 4)))
+            .await
+            .unwrap();
+        player.play(Stone::WHITE, None).await.unwrap();
+        let action = player.genmove(Stone::BLACK).await.unwrap();
+        assert!(matches!(
+            action,
+            Action::Move {
+                stone: Stone::BLACK,
+                ..
+            }
+        ));
+        drop(player);
+
+        let (first, second) = server.await.unwrap();
+        assert_eq!(first[HANDSHAKE..], ["play B E5", "play W pass"]);
+        // 重连之后重放着法
+        assert_eq!(
+            second[HANDSHAKE..],
+            ["play B E5", "play W pass", "genmove B", "undo "]
+        );
+    }
+
+    #[tokio::test]
+    async fn disconnect_while_restoring() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve(stream, HANDSHAKE + 2).await;
+            // 重放到一半又断开
+            let (stream, _) = listener.accept().await.unwrap();
+            let second = serve(stream, HANDSHAKE + 1).await;
+            let (stream, _) = listener.accept().await.unwrap();
+            let third = serve(stream, usize::MAX).await;
+            (second, third)
+        });
+
+        let mut player =
+            TcpGtpPlayer::connect(PlayerId::new(0), &addr, GtpOptions::default(), policy())
+                .await
+                .unwrap();
+        player.new_game(game_start(9, 2)).await.unwrap();
+        player
+            .play(Stone::BLACK, Some(Coord::new(4, 4)))
+            .await
+            .unwrap();
+        player.play(Stone::WHITE, None).await.unwrap();
+        let (msg, res) = player.scoring_start(vec![]).await;
+        res.unwrap();
+        assert!(matches!(msg, PlayerMessage::AcceptScore { .. }));
+        drop(player);
+
+        let (second, third) = server.await.unwrap();
+        assert_eq!(second[HANDSHAKE..], ["play B E5"]);
+        assert_eq!(
+            third[HANDSHAKE..],
+            ["play B E5", "play W pass", "final_status_list dead"]
+        );
+    }
+
+    #[tokio::test]
+    async fn board_too_large_for_gtp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve(stream, usize::MAX).await;
+        });
+
+        let player =
+            TcpGtpPlayer::connect(PlayerId::new(0), &addr, GtpOptions::default(), policy())
+                .await
+                .unwrap();
+        let mut builder = GameBuilder::new(gtp::MAX_SIZE + 1);
+        builder.add_team(TeamId::new(0), Stone::BLACK).unwrap();
+        assert_eq!(
+            builder.add_player(TeamId::new(0), player),
+            Err(GameConfigError::SizeUnsupported(PlayerId::new(0)))
+        );
+    }
+
+    #[tokio::test]
+    async fn give_up_without_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            serve(stream, HANDSHAKE).await;
+        });
+
+        let mut player = TcpGtpPlayer::connect(
+            PlayerId::new(0),
+            &addr,
+            GtpOptions::default(),
+            ReconnectPolicy::never(),
+        )
+        .await
+        .unwrap();
+        player.new_game(game_start(9, 2)).await.unwrap();
+        assert!(matches!(
+            player.genmove(Stone::BLACK).await,
+            Err(PlayerError::Disconnected)
+        ));
+    }
+
+    #[tokio::test]
+    async fn listen_for_engine() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let stream = TcpStream::connect(addr).await.unwrap();
+            serve(stream, usize::MAX).await;
+        });
+
+        let options = GtpOptions {
+            startup_commands: vec!["komi 6.5".to_string()],
+            ..Default::default()
+        };
+        let mut player = TcpGtpPlayer::listen(PlayerId::new(0), listener, options, policy())
+            .await
+            .unwrap();
+        player.new_game(game_start(9, 2)).await.unwrap();
+        player
+            .play(Stone::BLACK, Some(Coord::new(4, 4)))
+            .await
+            .unwrap();
+        // 引擎拒绝的着法不计入重放的着法
+        assert!(matches!(
+            player.play(Stone::WHITE, Some(Coord::new(4, 4))).await,
+            Err(PlayerError::EngineError(_))
+        ));
+        assert_eq!(player.moves.len(), 1);
+        assert!(matches!(
+            player.genmove(Stone::WHITE).await.unwrap(),
+            Action::Move {
+                stone: Stone::WHITE,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,223 @@
+//! JSON 格式的对局记录, 给统计分析用
+//!
+//! 与 SGF 不同, 这里记录的是对局服务器看到的一切: 队伍与棋手、每个动作的时间、聊天以及结果。
+//! 格式有变化时增加 `GAME_RECORD_VERSION`。
+
+use std::fmt::Display;
+
+use rustgo::Coord;
+use serde::{Deserialize, Serialize};
+
+use crate::{Action, player::PlayerId, team::TeamId, team::TeamInfo};
+
+/// 当前的记录格式版本
+pub const GAME_RECORD_VERSION: u32 = 1;
+
+/// 棋盘的拓扑结构
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    /// 普通的 size x size 棋盘
+    Grid,
+}
+
+/// 计分方法
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringRule {
+    /// 数子法: 活棋加围住的空点
+    #[default]
+    Area,
+    /// 数目法: 围住的空点加俘虏
+    Territory,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardSpec {
+    pub size: usize,
+    pub topology: Topology,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActionRecord {
+    pub player_id: PlayerId,
+    pub team_id: TeamId,
+    pub action: Action,
+    /// 距离对局开始的毫秒数
+    pub timestamp_ms: u64,
+    /// 动作完成后该棋手剩余的时间 (毫秒), 没有计时时为 `None`
+    pub time_left_ms: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChatRecord {
+    pub player_id: PlayerId,
+    pub chat: String,
+    /// 距离对局开始的毫秒数
+    pub timestamp_ms: u64,
+}
+
+/// 对局结束的原因
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndReason {
+    Resign,
+    Score,
+    Timeout,
+    /// 其余队伍因为非法着法太多被判负
+    Forfeit,
+    /// 其余队伍的棋子都被提光
+    Elimination,
+    /// 所有棋手都离开了, 对局没有下完
+    Abandoned,
+    /// 由 `GameHandle::abort` 中止
+    Aborted,
+}
+
+/// 队伍在对局中的状态, 不是 `Active` 的队伍不再轮到
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TeamStatus {
+    #[default]
+    Active,
+    Resigned,
+    /// 棋子被全部提走
+    Eliminated,
+    TimedOut,
+    /// 非法着法太多, 被判负
+    Forfeited,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TeamScore {
+    pub team_id: TeamId,
+    /// 数子的结果, 包括贴目
+    pub score: f64,
+    /// 对局结束时的状态
+    #[serde(default)]
+    pub status: TeamStatus,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameResult {
+    /// `None` 表示没有胜者 (和棋或对局没有下完)
+    pub winner: Option<TeamId>,
+    pub reason: EndReason,
+    /// 按队伍顺序的得分, 没有数子时为空
+    #[serde(default)]
+    pub scores: Vec<TeamScore>,
+    /// 名次: 仍在对局中的队伍按得分排在前面, 出局的队伍越晚出局名次越靠前
+    #[serde(default)]
+    pub ranking: Vec<TeamId>,
+    /// 数子阶段各方同意的死子
+    #[serde(default)]
+    pub dead: Vec<Coord>,
+    /// 补充说明, 如中止对局的原因
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRecord {
+    pub version: u32,
+    /// 对局开始时的 Unix 时间 (毫秒)
+    pub started_at_ms: u64,
+    pub rules: String,
+    #[serde(default)]
+    pub komi: f64,
+    #[serde(default)]
+    pub scoring: ScoringRule,
+    pub board: BoardSpec,
+    /// 第一队的让子
+    #[serde(default)]
+    pub handicap: Vec<Coord>,
+    pub teams: Vec<TeamInfo>,
+    pub actions: Vec<ActionRecord>,
+    pub chats: Vec<ChatRecord>,
+    pub result: Option<GameResult>,
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    Json(serde_json::Error),
+    /// 记录的版本比当前程序支持的版本新
+    UnsupportedVersion(u32),
+}
+
+impl Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordError::Json(e) => write!(f, "invalid game record: {e}"),
+            RecordError::UnsupportedVersion(v) => {
+                write!(f, "unsupported game record version: {v}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordError {}
+
+impl From<serde_json::Error> for RecordError {
+    fn from(value: serde_json::Error) -> Self {
+        RecordError::Json(value)
+    }
+}
+
+impl GameRecord {
+    pub fn new(rules: &str, size: usize, teams: Vec<TeamInfo>, started_at_ms: u64) -> Self {
+        GameRecord {
+            version: GAME_RECORD_VERSION,
+            started_at_ms,
+            rules: rules.to_string(),
+            komi: 0.0,
+            scoring: ScoringRule::Area,
+            board: BoardSpec {
+                size,
+                topology: Topology::Grid,
+            },
+            handicap: vec![],
+            teams,
+            actions: vec![],
+            chats: vec![],
+            result: None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(s: &str) -> Result<Self, RecordError> {
+        // 先只读版本号, 新版本的记录可能无法按当前的结构解析
+        #[derive(Deserialize)]
+        struct Version {
+            version: u32,
+        }
+        let Version { version } = serde_json::from_str(s)?;
+        if version > GAME_RECORD_VERSION {
+            return Err(RecordError::UnsupportedVersion(version));
+        }
+        Ok(serde_json::from_str(s)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::DEFAULT_RULES;
+
+    #[test]
+    fn record_version_check() {
+        let mut record = GameRecord::new(DEFAULT_RULES, 19, vec![], 0);
+        record.version = GAME_RECORD_VERSION + 1;
+        let res = GameRecord::from_json(&record.to_json());
+        assert!(
+            matches!(res, Err(RecordError::UnsupportedVersion(v)) if v == GAME_RECORD_VERSION + 1)
+        );
+
+        assert!(matches!(
+            GameRecord::from_json("{\"version\": 1}"),
+            Err(RecordError::Json(_))
+        ));
+    }
+}
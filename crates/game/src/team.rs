@@ -1,11 +1,12 @@
 use rustgo::Stone;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     ServerMessage,
     player::{PlayerHandle, PlayerId, PlayerInfo},
 };
 
-#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq, Debug, Serialize, Deserialize)]
 pub struct TeamId(usize);
 impl TeamId {
     pub fn new(id: usize) -> Self {
@@ -13,12 +14,33 @@ impl TeamId {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TeamInfo {
     team_id: TeamId,
     stone: Stone,
     players: Vec<PlayerInfo>,
 }
+impl TeamInfo {
+    pub fn new(team_id: TeamId, stone: Stone, players: Vec<PlayerInfo>) -> Self {
+        Self {
+            team_id,
+            stone,
+            players,
+        }
+    }
+
+    pub fn team_id(&self) -> TeamId {
+        self.team_id
+    }
+
+    pub fn stone(&self) -> Stone {
+        self.stone
+    }
+
+    pub fn players(&self) -> &[PlayerInfo] {
+        &self.players
+    }
+}
 
 pub struct TeamHandle {
     pub team_id: TeamId,
@@ -55,6 +77,21 @@ impl TeamHandle {
         self.team_id
     }
 
+    pub fn team_info(&self) -> TeamInfo {
+        let players = self
+            .players
+            .iter()
+            .map(|p| PlayerInfo {
+                player_id: p.player_id,
+                team_id: self.team_id,
+                player_name: p.player_name.clone(),
+                eaten_stones: 0,
+                time_left: 0,
+            })
+            .collect();
+        TeamInfo::new(self.team_id, self.stone, players)
+    }
+
     pub async fn broadcast(&mut self, msg: ServerMessage) {
         for player in &mut self.players {
             player.send(msg.clone()).await;
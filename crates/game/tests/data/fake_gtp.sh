@@ -0,0 +1,45 @@
+#!/bin/sh
+# 测试用的假 GTP 引擎
+#
+# 参数为 genmove 依次返回的着法, 用完之后返回 resign; `fail` 命令总是失败,
+# `hang` 在 1 秒之后才响应, `die` 以状态 3 退出
+moves="$*"
+while IFS= read -r line; do
+    set -f
+    set -- $line
+    set +f
+    id=""
+    case "$1" in
+        '' | *[!0-9]*) ;;
+        *) id="$1"; shift ;;
+    esac
+    cmd="$1"
+    case "$cmd" in
+        '') continue ;;
+        protocol_version) echo "=$id 2" ;;
+        name) echo "=$id fake" ;;
+        version) echo "=$id 1.0" ;;
+        list_commands)
+            echo "=$id protocol_version"
+            for c in name version list_commands boardsize clear_board komi play genmove undo quit fail; do
+                echo "$c"
+            done
+            ;;
+        genmove)
+            set -- $moves
+            if [ $# -gt 0 ]; then
+                echo "=$id $1"
+                shift
+                moves="$*"
+            else
+                echo "=$id resign"
+            fi
+            ;;
+        fail) echo "?$id boom" ;;
+        hang) sleep 1; echo "=$id" ;;
+        die) exit 3 ;;
+        quit) printf '=%s\n\n' "$id"; exit 0 ;;
+        *) echo "=$id" ;;
+    esac
+    echo
+done
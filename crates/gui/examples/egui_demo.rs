@@ -2,12 +2,15 @@ use std::sync::{Arc, Mutex};
 
 use eframe::egui::{self, Color32, Vec2};
 use game::{
-    Action, PlayerMessage, ServerMessage,
+    Action, GameStart, PlayerMessage, ServerMessage,
     game::GameBuilder,
     player::{PlayerId, channel_player::ChannelPlayer, dummy_player::DummyPlayer},
     team::TeamId,
 };
-use rustgo::{Coord, Stone, board::Board};
+use rustgo::{
+    Coord, Stone,
+    board::{Board, GoBoard},
+};
 use tokio::sync::mpsc::{self, Receiver, Sender};
 
 // 19x19棋盘的星位
@@ -46,25 +49,37 @@ static COLOR32_LUT: &[Color32] = &[
     Color32::GOLD,
 ];
 
-struct UiBoard {
+struct UiBoard<B: GoBoard = Board> {
     player_id: PlayerId,
     size: usize,
-    board: Board,
+    board: B,
     pending_move: Option<Stone>,
     ui_tx: Sender<PlayerMessage>, // 点击事件，发出信息
 }
 
-impl UiBoard {
+impl<B: GoBoard> UiBoard<B> {
     fn new(player_id: PlayerId, size: usize, ui_tx: Sender<PlayerMessage>) -> Self {
         Self {
             player_id,
             size,
-            board: Board::new(size),
+            board: B::new(size),
             pending_move: None,
             ui_tx,
         }
     }
 
+    /// 按开局信息重置棋盘
+    fn new_game(&mut self, start: &GameStart) {
+        self.size = start.size;
+        self.board = B::new(start.size);
+        if let Some(stone) = start.handicap_stone() {
+            for &coord in &start.handicap {
+                self.board.place_stone(coord, stone).unwrap();
+            }
+        }
+        self.pending_move = None;
+    }
+
     pub fn ui(&mut self, ui: &mut egui::Ui) {
         let total_size = ui.available_size().min_elem();
 
@@ -141,12 +156,10 @@ impl UiBoard {
 
         // --- draw stones ---
         let radius = cell * 0.35;
-        let board = self.board.board_array();
 
         for y in 0..self.size {
             for x in 0..self.size {
-                let idx = y * self.size + x;
-                let stone = board[idx];
+                let stone = self.board.stone(Coord::new(x, y));
                 if stone != Stone::VOID {
                     let center = board_left_top + egui::vec2(x as f32 * cell, y as f32 * cell);
 
@@ -187,7 +200,7 @@ impl MyApp {
         ui_tx: Sender<PlayerMessage>,
         mut ui_rx: Receiver<ServerMessage>,
     ) -> Self {
-        let board_ui = Arc::new(Mutex::new(UiBoard::new(player_id, size, ui_tx)));
+        let board_ui = Arc::new(Mutex::new(UiBoard::<Board>::new(player_id, size, ui_tx)));
 
         {
             let ctx = cc.egui_ctx.clone();
@@ -195,11 +208,15 @@ impl MyApp {
             tokio::spawn(async move {
                 while let Some(msg) = ui_rx.recv().await {
                     match msg {
-                        ServerMessage::GameStart(team_infos) => todo!(),
+                        ServerMessage::GameStart(start) => {
+                            println!("game start: {:?}", start);
+                            board_ui.lock().unwrap().new_game(&start);
+                        }
                         ServerMessage::GameUpdate {
                             cur_team,
                             cur_player,
                             player_info,
+                            clocks,
                         } => {
                             // TODO
                         }
@@ -218,13 +235,49 @@ impl MyApp {
                         ServerMessage::PlayerChat { player_id, chat } => {
                             println!("egui hear {} from player[{:?}]", chat, player_id)
                         }
+                        ServerMessage::ScoringStart { dead } => {
+                            println!("scoring, dead stones: {:?}", dead)
+                        }
+                        ServerMessage::DeadStones { dead, accepted } => {
+                            println!("dead stones: {:?}, accepted by {:?}", dead, accepted)
+                        }
+                        ServerMessage::PlayResumed { player_id } => {
+                            println!("player[{:?}] resumes play", player_id)
+                        }
+                        ServerMessage::TimeSettings(settings) => {
+                            println!("time settings: {:?}", settings)
+                        }
                         ServerMessage::GenMove(stone) => {
                             board_ui.lock().unwrap().pending_move = Some(stone)
                         }
                         ServerMessage::Error(msg) => {
                             println!("ServerMessage::Error: {}", msg);
                         }
-                        ServerMessage::GameOver => todo!(),
+                        ServerMessage::MoveRejected {
+                            action,
+                            error,
+                            retries_left,
+                        } => {
+                            println!(
+                                "{:?} rejected: {}, retries left: {:?}",
+                                action, error, retries_left
+                            );
+                        }
+                        ServerMessage::PlayerPass { player_id, stone } => {
+                            println!("player[{:?}] ({:?}) passes", player_id, stone)
+                        }
+                        ServerMessage::PlayerResign { player_id, team_id } => {
+                            println!("player[{:?}] of team[{:?}] resigns", player_id, team_id)
+                        }
+                        ServerMessage::TeamOut { team_id, status } => {
+                            println!("team[{:?}] is out: {:?}", team_id, status)
+                        }
+                        ServerMessage::GamePaused => println!("game paused"),
+                        ServerMessage::GameResumed => println!("game resumed"),
+                        ServerMessage::GameOver(result) => {
+                            println!("game over: {:?}", result);
+                            board_ui.lock().unwrap().pending_move = None;
+                        }
                     }
                     ctx.request_repaint();
                 }
@@ -289,23 +342,26 @@ async fn main() -> eframe::Result<()> {
 
     tokio::spawn(async move {
         let mut game = GameBuilder::new(BOARD_SIZE);
-        game.add_team(TeamId::new(0), Stone::BLACK);
+        game.add_team(TeamId::new(0), Stone::BLACK).unwrap();
         game.add_player(
             TeamId::new(0),
             ChannelPlayer::new(PlayerId::new(0), downlink_to_ui, uplink_from_ui),
-        );
+        )
+        .unwrap();
 
         for team_n in 1..6 {
-            game.add_team(TeamId::new(team_n), Stone::new(1 + team_n as u8));
+            game.add_team(TeamId::new(team_n), Stone::new(1 + team_n as u8))
+                .unwrap();
             for player_n in 0..team_n {
                 game.add_player(
                     TeamId::new(team_n),
-                    DummyPlayer::new(PlayerId::new(team_n * 10 + player_n), BOARD_SIZE),
-                );
+                    DummyPlayer::new(PlayerId::new(team_n * 10 + player_n)),
+                )
+                .unwrap();
             }
         }
 
-        let mut game = game.build();
+        let mut game = game.build().unwrap();
         game.run().await;
     });
 
@@ -2,6 +2,7 @@ use criterion::{Criterion, criterion_group, criterion_main};
 use rand::RngExt;
 use rustgo::DisjointSet;
 use rustgo::IdxTrait;
+use rustgo::RollbackDisjointSet;
 use std::hint::black_box;
 
 #[derive(Clone, Copy)]
@@ -58,7 +59,78 @@ fn run_ops<T: IdxTrait>(ops: &[Op]) {
     }
 }
 
+fn run_ops_rollback<T: IdxTrait>(ops: &[Op]) {
+    let mut ds = RollbackDisjointSet::<T>::new(TEST_SIZE);
+
+    for op in ops {
+        match *op {
+            Op::Insert(i) => {
+                ds.insert(i);
+            }
+            Op::Connect(a, b) => {
+                ds.connect(a, b);
+            }
+            Op::DeleteGroup(i) => {
+                ds.delete_group(i);
+            }
+        }
+    }
+}
+
+/// 模拟搜索: 每 `SEARCH_DEPTH` 个操作之后回到之前的状态
+///
+/// DisjointSet 只能 clone 一份再恢复, RollbackDisjointSet 用 checkpoint/rollback
+fn run_search<T: IdxTrait>(ops: &[Op]) {
+    let mut ds = DisjointSet::<T>::new(TEST_SIZE);
+
+    for chunk in ops.chunks(SEARCH_DEPTH) {
+        let saved = ds.clone();
+        for op in chunk {
+            match *op {
+                Op::Insert(i) => ds.insert(i),
+                Op::Connect(a, b) => ds.connect(a, b),
+                Op::DeleteGroup(i) => {
+                    ds.delete_group(i);
+                }
+            }
+        }
+        // 只保留 insert, 其他修改撤销
+        let mut kept = saved;
+        for op in chunk {
+            if let Op::Insert(i) = *op {
+                kept.insert(i);
+            }
+        }
+        ds = kept;
+    }
+}
+
+fn run_search_rollback<T: IdxTrait>(ops: &[Op]) {
+    let mut ds = RollbackDisjointSet::<T>::new(TEST_SIZE);
+
+    for chunk in ops.chunks(SEARCH_DEPTH) {
+        let checkpoint = ds.checkpoint();
+        for op in chunk {
+            match *op {
+                Op::Insert(i) => ds.insert(i),
+                Op::Connect(a, b) => ds.connect(a, b),
+                Op::DeleteGroup(i) => {
+                    ds.delete_group(i);
+                }
+            }
+        }
+        // 只保留 insert, 其他修改撤销
+        ds.rollback(checkpoint);
+        for op in chunk {
+            if let Op::Insert(i) = *op {
+                ds.insert(i);
+            }
+        }
+    }
+}
+
 const TEST_SIZE: usize = 19 * 19;
+const SEARCH_DEPTH: usize = 20;
 
 fn bench_all(c: &mut Criterion) {
     let mut group = c.benchmark_group("disjoint_set_compare");
@@ -82,6 +154,26 @@ fn bench_all(c: &mut Criterion) {
         })
     });
 
+    group.bench_function("rollback_u16", |b| {
+        b.iter(|| {
+            run_ops_rollback::<u16>(black_box(&ops));
+        })
+    });
+
+    group.finish();
+
+    let mut group = c.benchmark_group("disjoint_set_search");
+    group.bench_function("clone_u16", |b| {
+        b.iter(|| {
+            run_search::<u16>(black_box(&ops));
+        })
+    });
+    group.bench_function("rollback_u16", |b| {
+        b.iter(|| {
+            run_search_rollback::<u16>(black_box(&ops));
+        })
+    });
+
     group.finish();
 }
 
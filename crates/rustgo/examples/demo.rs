@@ -12,10 +12,13 @@ use cursive::{
 use cursive::traits::Nameable;
 use cursive::view::Resizable;
 use cursive::views::{Dialog, LinearLayout, Panel};
-use rustgo::{Coord, Stone, board::Board};
+use rustgo::{
+    Coord, Stone,
+    board::{Board, GoBoard},
+};
 
-pub struct BoardView {
-    board: Board,
+pub struct BoardView<B: GoBoard = Board> {
+    board: B,
     cur_stone: Stone,
     n_stone: u8,
 }
@@ -39,10 +42,10 @@ const STAR: [(usize, usize); 9] = [
     (9, 9),
 ];
 
-impl BoardView {
+impl<B: GoBoard> BoardView<B> {
     pub fn new(size: usize, n_stone: u8) -> Self {
         Self {
-            board: Board::new(size),
+            board: B::new(size),
             cur_stone: Stone::BLACK,
             n_stone,
         }
@@ -69,9 +72,8 @@ impl BoardView {
         let coord = Coord::new(pos.x, pos.y);
         let result = self.board.place_stone(coord, self.cur_stone);
         match result {
-            Ok(eaten) => {
-                // TODO
-                eaten;
+            Ok(_eaten) => {
+                // TODO 显示提子
                 self.cur_stone = self.cur_stone.next_stone(self.n_stone);
                 EventResult::with_cb_once(move |s| append_log(s, coord.to_string()))
             }
@@ -82,18 +84,16 @@ impl BoardView {
     }
 }
 
-impl View for BoardView {
+impl<B: GoBoard + Send + Sync + 'static> View for BoardView<B> {
     fn draw(&self, printer: &Printer) {
         let size = self.board.size();
-        let board = self.board.board_array();
 
         let mut line = String::with_capacity(size);
         for y in 0..size {
             line.clear();
             line.push(' ');
             for x in 0..size {
-                let idx = y * size + x;
-                let c = match board[idx] {
+                let c = match self.board.stone(Coord::new(x, y)) {
                     Stone::VOID => {
                         if size == 19 && STAR.contains(&(x, y)) {
                             '+'
@@ -148,7 +148,7 @@ fn append_log(s: &mut cursive::Cursive, mut msg: String) {
 fn main() {
     let mut siv = cursive::default();
 
-    let board = BoardView::new(19, 3);
+    let board = BoardView::<Board>::new(19, 3);
     let log_view = TextView::new("Log:\n").with_name("log").min_width(30);
 
     siv.add_layer(
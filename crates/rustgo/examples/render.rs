@@ -0,0 +1,47 @@
+//! 把 SGF 棋谱某一手的局面导出为图片
+//!
+//! cargo run --example render -- game.sgf out.svg [手数]
+//! cargo run --example render --features png -- game.sgf out.png [手数]
+
+use rustgo::{
+    game_tree::GameTree,
+    render::{self, RenderStyle},
+    sgf,
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        eprintln!("usage: {} <game.sgf> <out.svg|out.png> [move]", args[0]);
+        std::process::exit(1);
+    }
+
+    let text = std::fs::read_to_string(&args[1]).expect("无法读取棋谱");
+    let root = sgf::parse_game(&text).expect("无法解析棋谱");
+    let mut tree = GameTree::from_sgf(&root).expect("棋谱中有非法落子");
+    match args.get(3) {
+        Some(n) => {
+            tree.go_to_move(n.parse().expect("手数必须是数字"));
+        }
+        None => while tree.go_next() {},
+    }
+
+    let svg = render::tree_svg(&tree, &RenderStyle::default(), true);
+    if args[2].ends_with(".png") {
+        write_png(&args[2], &svg);
+    } else {
+        std::fs::write(&args[2], svg).expect("无法写入图片");
+    }
+}
+
+#[cfg(feature = "png")]
+fn write_png(path: &str, svg: &str) {
+    let png = render::svg_to_png(svg).expect("无法生成 PNG");
+    std::fs::write(path, png).expect("无法写入图片");
+}
+
+#[cfg(not(feature = "png"))]
+fn write_png(_path: &str, _svg: &str) {
+    eprintln!("PNG 需要开启 png feature");
+    std::process::exit(1);
+}
@@ -0,0 +1,39 @@
+//! 把 SGF 棋谱的主变化导出为动画
+//!
+//! cargo run --example replay -- game.sgf out.svg
+//! cargo run --example replay --features gif -- game.sgf out.gif
+
+use rustgo::{
+    render::replay::{Replay, ReplayOptions},
+    sgf,
+};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        eprintln!("usage: {} <game.sgf> <out.svg|out.gif>", args[0]);
+        std::process::exit(1);
+    }
+
+    let text = std::fs::read_to_string(&args[1]).expect("无法读取棋谱");
+    let replay = Replay::from_sgf(&sgf::parse_game(&text).expect("无法解析棋谱"));
+    let options = ReplayOptions::default();
+    if args[2].ends_with(".gif") {
+        write_gif(&args[2], &replay, &options);
+    } else {
+        let svg = replay.to_svg(&options).expect("棋谱中有非法落子");
+        std::fs::write(&args[2], svg).expect("无法写入动画");
+    }
+}
+
+#[cfg(feature = "gif")]
+fn write_gif(path: &str, replay: &Replay, options: &ReplayOptions) {
+    let gif = replay.to_gif(options).expect("无法生成 GIF");
+    std::fs::write(path, gif).expect("无法写入动画");
+}
+
+#[cfg(not(feature = "gif"))]
+fn write_gif(_path: &str, _replay: &Replay, _options: &ReplayOptions) {
+    eprintln!("GIF 需要开启 gif feature");
+    std::process::exit(1);
+}
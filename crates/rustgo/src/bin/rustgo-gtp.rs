@@ -0,0 +1,47 @@
+//! GTP 引擎: 在 stdin/stdout 上运行 GTP v2
+//!
+//! 用法: `rustgo-gtp [--book <file>] [--seed <n>]`
+
+use std::{fs::File, io, process};
+
+use rustgo::{
+    book::OpeningBook,
+    bot::{BookBot, Bot, RandomBot},
+    gtp::GtpEngine,
+};
+
+fn usage() -> ! {
+    eprintln!("usage: rustgo-gtp [--book <file>] [--seed <n>]");
+    process::exit(2);
+}
+
+fn main() -> io::Result<()> {
+    let mut book = None;
+    let mut seed = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--book" => {
+                let path = args.next().unwrap_or_else(|| usage());
+                book = Some(OpeningBook::load(File::open(path)?)?);
+            }
+            "--seed" => {
+                let n = args.next().unwrap_or_else(|| usage());
+                seed = Some(n.parse::<u64>().unwrap_or_else(|_| usage()));
+            }
+            _ => usage(),
+        }
+    }
+
+    let random = match seed {
+        Some(seed) => RandomBot::with_seed(seed),
+        None => RandomBot::new(),
+    };
+    let bot: Box<dyn Bot> = match book {
+        Some(book) => Box::new(BookBot::new(book, random)),
+        None => Box::new(random),
+    };
+
+    let mut engine = GtpEngine::new(bot);
+    return engine.run(io::stdin().lock(), io::stdout().lock());
+}
@@ -1,6 +1,8 @@
 use std::collections::{HashSet, VecDeque};
 
-use crate::{Array, Coord, DisjointSet, Stone};
+use crate::{Array, Checkpoint, Coord, RollbackDisjointSet, Stone};
+
+pub mod bitboard;
 
 const MAX_STATES_RECORD: usize = 30;
 
@@ -9,6 +11,72 @@ type Idx = usize;
 pub type PlaceStoneResult = Result<Vec<Coord>, &'static str>; // TODO err type
 pub type BoardArray = Array<Stone>;
 
+/// 棋盘的公共接口, 让 `Board` 与其他实现 (如 `bitboard::BitBoard`) 可以互相替换
+///
+/// 所有实现的落子规则、错误信息、hash 都必须与 `Board` 一致
+pub trait GoBoard {
+    /// 支持的最大棋盘大小
+    const MAX_SIZE: usize = usize::MAX;
+
+    fn new(size: usize) -> Self
+    where
+        Self: Sized;
+
+    fn size(&self) -> usize;
+
+    fn stone(&self, coord: Coord) -> Stone;
+
+    /// 落子, 返回被提走的棋子
+    fn place_stone(&mut self, coord: Coord, stone: Stone) -> PlaceStoneResult;
+
+    /// 是否可以落子; 检查完之后棋盘保持不变
+    ///
+    /// 默认实现为落子后再 undo, 所以需要 `&mut self`
+    fn is_legal(&mut self, coord: Coord, stone: Stone) -> bool {
+        if self.place_stone(coord, stone).is_ok() {
+            self.undo();
+            return true;
+        }
+        return false;
+    }
+
+    /// `stone` 一方累计提走了多少颗棋子
+    fn captures(&self, stone: Stone) -> usize;
+
+    /// 局面的 Zobrist hash, 见 `zobrist_key`
+    fn hash(&self) -> u64;
+
+    /// 撤销最近一次落子, 没有可撤销的落子时返回 false
+    fn undo(&mut self) -> bool;
+
+    fn board_string(&self) -> String {
+        let size = self.size();
+        let mut s = String::with_capacity(size * (size + 1));
+        for y in 0..size {
+            for x in 0..size {
+                s.push(self.stone(Coord::new(x, y)).as_char());
+            }
+            s.push('\n');
+        }
+        return s;
+    }
+}
+
+/// 悔棋所需的落子记录
+#[derive(Clone)]
+struct MoveRecord {
+    idx: Idx,
+    /// 被提走的棋子 (坐标, 颜色)
+    eaten: Vec<(Idx, Stone)>,
+    /// 落子时被挤出 history_b_array 的最旧记录
+    dropped_history: Option<BoardArray>,
+    /// 落子之前的 group_ds, undo 时回滚到这里
+    checkpoint: Checkpoint,
+    /// 落子时改过的 group_qi (下标, 旧值), 按修改顺序
+    qi_log: Vec<(Idx, usize)>,
+}
+
+#[derive(Clone)]
 pub struct Board {
     /// 棋盘的长宽 (长==宽)
     size: usize,
@@ -20,10 +88,10 @@ pub struct Board {
 
     /// 棋子的分组信息
     ///
-    /// 同色、连续的棋子在运行时使用 disjoint set 记录分组
+    /// 同色、连续的棋子在运行时使用 disjoint set 记录分组, undo 时按落子记录中的 checkpoint 回滚
     ///
     /// group root 所对应的下标 idx 在 self.group_qi[idx] 中会记录"气"
-    group_ds: DisjointSet<u16>,
+    group_ds: RollbackDisjointSet<u16>,
 
     /// 棋子组的气
     ///
@@ -39,6 +107,15 @@ pub struct Board {
     ///
     /// 新记录 push_front, 超出的 pop_back
     history_b_array: VecDeque<BoardArray>, // TODO fixed size deque
+
+    /// 当前局面的 Zobrist hash, 随落子、提子增量更新
+    hash: u64,
+
+    /// 落子记录, 用于 undo
+    move_records: Vec<MoveRecord>,
+
+    /// captures[stone.as_usize()] == 该颜色累计提走的棋子数
+    captures: Vec<usize>,
 }
 
 impl Board {
@@ -46,9 +123,12 @@ impl Board {
         Board {
             size: size,
             b_array: vec![Stone::VOID; size * size].into_boxed_slice(),
-            group_ds: DisjointSet::new(size * size),
+            group_ds: RollbackDisjointSet::new(size * size),
             group_qi: vec![0; size * size].into_boxed_slice(),
             history_b_array: VecDeque::with_capacity(MAX_STATES_RECORD),
+            hash: 0,
+            move_records: vec![],
+            captures: vec![],
         }
     }
 
@@ -57,38 +137,61 @@ impl Board {
         let mut board = Board {
             size,
             b_array,
-            group_ds: DisjointSet::new(size * size),
+            group_ds: RollbackDisjointSet::new(size * size),
             group_qi: vec![0; size * size].into_boxed_slice(),
             history_b_array: VecDeque::with_capacity(MAX_STATES_RECORD),
+            hash: 0,
+            move_records: vec![],
+            captures: vec![],
         };
+        board.rebuild_groups();
+        // 初始局面不可回滚
+        board.group_ds.clear_history();
+        for idx in 0..board.b_array.len() {
+            if board.have_stone(idx) {
+                board.hash ^= zobrist_key(idx, board.b_array[idx]);
+            }
+        }
+
+        return board;
+    }
+
+    /// 根据 b_array 从头计算 group_ds 与 group_qi
+    fn rebuild_groups(&mut self) {
+        self.group_ds = RollbackDisjointSet::new(self.b_array.len());
+        self.group_qi.fill(0);
 
         // 对于每个坐标的棋子，向右向下与同色棋子连接
-        for cur_idx in 0..board.b_array.len() {
-            let cur_stone = board.b_array[cur_idx];
+        for cur_idx in 0..self.b_array.len() {
+            let cur_stone = self.b_array[cur_idx];
 
             if cur_stone != Stone::VOID {
-                board.group_ds.insert(cur_idx);
+                self.group_ds.insert(cur_idx);
 
-                if let Some(neighbor_idx) = board.right_neighbor(cur_idx)
-                    && board.b_array[neighbor_idx] == cur_stone
+                if let Some(neighbor_idx) = self.right_neighbor(cur_idx)
+                    && self.b_array[neighbor_idx] == cur_stone
                 {
-                    board.group_ds.connect(cur_idx, neighbor_idx);
+                    self.group_ds.connect(cur_idx, neighbor_idx);
                 }
-                if let Some(neighbor_idx) = board.down_neighbor(cur_idx)
-                    && board.b_array[neighbor_idx] == cur_stone
+                if let Some(neighbor_idx) = self.down_neighbor(cur_idx)
+                    && self.b_array[neighbor_idx] == cur_stone
                 {
-                    board.group_ds.connect(cur_idx, neighbor_idx);
+                    self.group_ds.connect(cur_idx, neighbor_idx);
                 }
             }
         }
 
         // 对于所有组，计算 group_qi
-        for root_idx in board.group_ds.group_roots() {
-            let members = board.group_ds.group_members(root_idx).unwrap().clone();
-            board.group_qi[root_idx] = board.calc_qi(&members);
+        for root_idx in self.group_ds.group_roots() {
+            let members = self.group_ds.group_members(root_idx).unwrap();
+            self.group_qi[root_idx] = self.calc_qi(&members);
         }
+    }
 
-        return board;
+    /// 修改组的气, 并把旧值记进 `qi_log`, 供 undo 恢复
+    fn set_qi(&mut self, qi_log: &mut Vec<(Idx, usize)>, root_idx: Idx, qi: usize) {
+        qi_log.push((root_idx, self.group_qi[root_idx]));
+        self.group_qi[root_idx] = qi;
     }
 
     pub fn idx(&self, coord: Coord) -> Idx {
@@ -224,9 +327,8 @@ impl Board {
     fn verbose_check(&self) {
         for idx in 0..self.b_array.len() {
             if self.have_stone(idx) {
-                let mut tmp = self.group_ds.clone();
-                let root_idx = tmp.find_root(idx).unwrap();
-                let members: Vec<usize> = tmp.group_members(root_idx).unwrap().clone();
+                let root_idx = self.group_ds.find_root(idx).unwrap();
+                let members: Vec<usize> = self.group_ds.group_members(root_idx).unwrap();
 
                 // check group members
                 let a: HashSet<Idx> = members.clone().into_iter().collect();
@@ -308,7 +410,7 @@ impl Board {
         let mut new_b_array = self.b_array.clone();
         new_b_array[cur_idx] = stone;
         for &root_idx in &eaten_groups {
-            for &idx in self.group_ds.group_members(root_idx).unwrap() {
+            for idx in self.group_ds.group_members(root_idx).unwrap() {
                 new_b_array[idx] = Stone::VOID;
             }
         }
@@ -320,30 +422,33 @@ impl Board {
 
         // 6. 之后便允许落子
         self.b_array[cur_idx] = stone;
+        let checkpoint = self.group_ds.checkpoint();
+        let mut qi_log: Vec<(Idx, usize)> = vec![];
 
         // 6.1 如果有"己方组", 则将落子与"己方组"merge, group root可能会更新, 在group root中更新"气"和members
         //     (此时气可能为0, 要等到提子后才还会被接着更新)
         if ally_groups.is_empty() {
             // 自己成组
             self.group_ds.insert(cur_idx);
-            self.group_qi[cur_idx] = cur_qi;
+            self.set_qi(&mut qi_log, cur_idx, cur_qi);
         } else {
             // TODO 很难归纳出通过简单加加减减merge group气的算法, 因为还需要考虑公气
 
             // 这里直接粗暴merge
             for root_idx in ally_groups {
                 self.group_ds.connect(cur_idx, root_idx);
-                self.group_qi[root_idx] = 0;
+                self.set_qi(&mut qi_log, root_idx, 0);
             }
             // 重新计算整个group的气
             let root_idx = self.group_ds.find_root(cur_idx).unwrap();
-            let members = self.group_ds.group_members(root_idx).unwrap().clone();
-            self.group_qi[root_idx] = self.calc_qi(&members);
+            let members = self.group_ds.group_members(root_idx).unwrap();
+            let qi = self.calc_qi(&members);
+            self.set_qi(&mut qi_log, root_idx, qi);
         }
 
         // 6.2 如果有"非己方组"且不是"提子组", 则用落子更新"气"
         for root_idx in opponent_groups {
-            self.group_qi[root_idx] -= 1;
+            self.set_qi(&mut qi_log, root_idx, self.group_qi[root_idx] - 1);
         }
 
         // 6.3 如果有"提子组", 则把所有"提子组"的members统计为一个list, 棋盘上这些坐标置空, 遍历list, 对于每个member遗址, 更新遗址周围的组的"气"
@@ -352,24 +457,40 @@ impl Board {
         // TODO test n色棋
         let mut eaten_stones: Vec<Idx> = vec![];
         for root_idx in eaten_groups {
-            self.group_qi[root_idx] = 0;
+            self.set_qi(&mut qi_log, root_idx, 0);
 
             eaten_stones.append(&mut self.group_ds.delete_group(root_idx).unwrap());
         }
+        let mut eaten_record: Vec<(Idx, Stone)> = Vec::with_capacity(eaten_stones.len());
         for &idx in &eaten_stones {
+            eaten_record.push((idx, self.b_array[idx]));
+            self.hash ^= zobrist_key(idx, self.b_array[idx]);
             self.b_array[idx] = Stone::VOID;
         }
+        self.hash ^= zobrist_key(cur_idx, stone);
+        if self.captures.len() <= stone.as_usize() {
+            self.captures.resize(stone.as_usize() + 1, 0);
+        }
+        self.captures[stone.as_usize()] += eaten_stones.len();
         for &idx in &eaten_stones {
             for root_idx in self.neighbor_groups(idx) {
-                self.group_qi[root_idx] += 1;
+                self.set_qi(&mut qi_log, root_idx, self.group_qi[root_idx] + 1);
             }
         }
 
         debug_assert!(new_b_array == self.b_array);
+        let mut dropped_history = None;
         if self.history_b_array.len() == MAX_STATES_RECORD {
-            self.history_b_array.pop_back();
+            dropped_history = self.history_b_array.pop_back();
         }
         self.history_b_array.push_front(new_b_array);
+        self.move_records.push(MoveRecord {
+            idx: cur_idx,
+            eaten: eaten_record,
+            dropped_history,
+            checkpoint,
+            qi_log,
+        });
 
         Ok(eaten_stones
             .iter()
@@ -377,6 +498,55 @@ impl Board {
             .collect())
     }
 
+    /// 撤销最近一次落子 (包括其提子), 并恢复全局同形的历史记录
+    ///
+    /// 如果没有可撤销的落子, 则返回 false
+    pub fn undo(&mut self) -> bool {
+        let Some(record) = self.move_records.pop() else {
+            return false;
+        };
+
+        let stone = self.b_array[record.idx];
+        self.hash ^= zobrist_key(record.idx, stone);
+        self.captures[stone.as_usize()] -= record.eaten.len();
+        self.b_array[record.idx] = Stone::VOID;
+        for &(idx, stone) in &record.eaten {
+            self.hash ^= zobrist_key(idx, stone);
+            self.b_array[idx] = stone;
+        }
+
+        self.history_b_array.pop_front();
+        if let Some(b_array) = record.dropped_history {
+            self.history_b_array.push_back(b_array);
+        }
+
+        // 只回滚这一手改过的组与气
+        self.group_ds.rollback(record.checkpoint);
+        for &(root_idx, qi) in record.qi_log.iter().rev() {
+            self.group_qi[root_idx] = qi;
+        }
+        return true;
+    }
+
+    /// 可以撤销的落子数
+    pub fn move_count(&self) -> usize {
+        self.move_records.len()
+    }
+
+    /// `stone` 一方累计提走了多少颗棋子
+    pub fn captures(&self, stone: Stone) -> usize {
+        self.captures.get(stone.as_usize()).copied().unwrap_or(0)
+    }
+
+    /// 当前局面的 Zobrist hash (只与棋盘上的棋子有关, 与落子顺序无关)
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn stone(&self, coord: Coord) -> Stone {
+        self.b_array[self.idx(coord)]
+    }
+
     pub fn size(&self) -> usize {
         self.size
     }
@@ -404,6 +574,50 @@ impl Board {
     }
 }
 
+impl GoBoard for Board {
+    fn new(size: usize) -> Self {
+        Board::new(size)
+    }
+
+    fn size(&self) -> usize {
+        self.size()
+    }
+
+    fn stone(&self, coord: Coord) -> Stone {
+        self.stone(coord)
+    }
+
+    fn place_stone(&mut self, coord: Coord, stone: Stone) -> PlaceStoneResult {
+        self.place_stone(coord, stone)
+    }
+
+    fn captures(&self, stone: Stone) -> usize {
+        self.captures(stone)
+    }
+
+    fn hash(&self) -> u64 {
+        self.hash()
+    }
+
+    fn undo(&mut self) -> bool {
+        self.undo()
+    }
+
+    fn board_string(&self) -> String {
+        self.board_string()
+    }
+}
+
+/// 坐标 `idx` 上放置 `stone` 时对应的 Zobrist key
+///
+/// 不查表, 直接用 splitmix64 混合 (idx, stone), 对任意大小的棋盘和任意颜色数都适用
+pub fn zobrist_key(idx: usize, stone: Stone) -> u64 {
+    let mut z = ((idx as u64) << 8 | stone.as_usize() as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    return z ^ (z >> 31);
+}
+
 fn push_if_not_exist(v: &mut Vec<usize>, x: usize) {
     if !v.contains(&x) {
         v.push(x);
@@ -0,0 +1,414 @@
+use std::collections::VecDeque;
+
+use crate::{
+    Coord, Stone,
+    board::{GoBoard, MAX_STATES_RECORD, PlaceStoneResult, zobrist_key},
+};
+
+/// 19x19 以内的棋盘最多需要 361 位
+const WORDS: usize = 6;
+pub const MAX_BITBOARD_SIZE: usize = 19;
+
+/// 颜色数 (包括 VOID), 与 `Stone` 能显示的颜色数一致
+const N_STONES: usize = 7;
+
+/// 定长位集合, 第 idx 位对应 `Board::idx` 的下标
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Bits([u64; WORDS]);
+
+impl Bits {
+    pub const EMPTY: Bits = Bits([0; WORDS]);
+
+    #[inline]
+    pub fn get(&self, idx: usize) -> bool {
+        self.0[idx / 64] >> (idx % 64) & 1 != 0
+    }
+
+    #[inline]
+    pub fn set(&mut self, idx: usize) {
+        self.0[idx / 64] |= 1 << (idx % 64);
+    }
+
+    #[inline]
+    pub fn clear(&mut self, idx: usize) {
+        self.0[idx / 64] &= !(1 << (idx % 64));
+    }
+
+    pub fn single(idx: usize) -> Self {
+        let mut bits = Bits::EMPTY;
+        bits.set(idx);
+        bits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&w| w == 0)
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    #[inline]
+    pub fn and(self, other: Bits) -> Bits {
+        Bits(std::array::from_fn(|i| self.0[i] & other.0[i]))
+    }
+
+    #[inline]
+    pub fn or(self, other: Bits) -> Bits {
+        Bits(std::array::from_fn(|i| self.0[i] | other.0[i]))
+    }
+
+    #[inline]
+    pub fn and_not(self, other: Bits) -> Bits {
+        Bits(std::array::from_fn(|i| self.0[i] & !other.0[i]))
+    }
+
+    /// 整体左移 `k` 位 (下标变大), `0 < k < 64`
+    #[inline]
+    fn shl(self, k: u32) -> Bits {
+        let w = &self.0;
+        Bits(std::array::from_fn(|i| {
+            let carry = if i > 0 { w[i - 1] >> (64 - k) } else { 0 };
+            (w[i] << k) | carry
+        }))
+    }
+
+    /// 整体右移 `k` 位 (下标变小), `0 < k < 64`
+    #[inline]
+    fn shr(self, k: u32) -> Bits {
+        let w = &self.0;
+        Bits(std::array::from_fn(|i| {
+            let carry = if i + 1 < WORDS {
+                w[i + 1] << (64 - k)
+            } else {
+                0
+            };
+            (w[i] >> k) | carry
+        }))
+    }
+
+    /// 所有为1的下标 (升序)
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, &word)| {
+            let mut w = word;
+            std::iter::from_fn(move || {
+                if w == 0 {
+                    return None;
+                }
+                let bit = w.trailing_zeros() as usize;
+                w &= w - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+/// 每种颜色各用一个 `Bits` 存储的棋盘, 气、连通块、提子都用移位和掩码计算
+///
+/// 规则与 `Board` 完全一致 (包括全局同形的历史记录条数), 只支持 19 路以内
+#[derive(Clone)]
+pub struct BitBoard {
+    size: usize,
+
+    /// stones[stone.as_usize()] 为该颜色所有棋子的位置, stones[0] 不使用
+    stones: [Bits; N_STONES],
+
+    /// 棋盘内的所有点
+    on_board: Bits,
+    /// 不在第一列的点 (左移1位之后用它过滤掉换行的部分)
+    not_first_col: Bits,
+    /// 不在最后一列的点
+    not_last_col: Bits,
+
+    /// 为了判断全局同形而记录的历史状态, 新记录 push_front, 超出的 pop_back
+    history: VecDeque<[Bits; N_STONES]>,
+
+    hash: u64,
+
+    /// 悔棋记录: 落子前的棋子分布与 hash, 以及落子时被挤出的历史记录
+    undo_records: Vec<UndoRecord>,
+
+    /// captures[stone.as_usize()] == 该颜色累计提走的棋子数
+    captures: [usize; N_STONES],
+}
+
+#[derive(Clone)]
+struct UndoRecord {
+    stones: [Bits; N_STONES],
+    hash: u64,
+    captures: [usize; N_STONES],
+    dropped_history: Option<[Bits; N_STONES]>,
+}
+
+impl BitBoard {
+    pub fn new(size: usize) -> Self {
+        assert!(
+            size > 0 && size <= MAX_BITBOARD_SIZE,
+            "BitBoard only supports size 1..={MAX_BITBOARD_SIZE}"
+        );
+
+        let mut on_board = Bits::EMPTY;
+        let mut not_first_col = Bits::EMPTY;
+        let mut not_last_col = Bits::EMPTY;
+        for idx in 0..size * size {
+            on_board.set(idx);
+            if idx % size != 0 {
+                not_first_col.set(idx);
+            }
+            if idx % size != size - 1 {
+                not_last_col.set(idx);
+            }
+        }
+
+        BitBoard {
+            size,
+            stones: [Bits::EMPTY; N_STONES],
+            on_board,
+            not_first_col,
+            not_last_col,
+            history: VecDeque::with_capacity(MAX_STATES_RECORD),
+            hash: 0,
+            undo_records: vec![],
+            captures: [0; N_STONES],
+        }
+    }
+
+    pub fn new_with_board(size: usize, b_array: &[Stone]) -> Self {
+        debug_assert!(size * size == b_array.len());
+        let mut board = BitBoard::new(size);
+        for (idx, &stone) in b_array.iter().enumerate() {
+            if stone != Stone::VOID {
+                board.stones[stone.as_usize()].set(idx);
+                board.hash ^= zobrist_key(idx, stone);
+            }
+        }
+        return board;
+    }
+
+    pub fn idx(&self, coord: Coord) -> usize {
+        debug_assert!(coord.x < self.size && coord.y < self.size);
+        coord.y * self.size + coord.x
+    }
+
+    pub fn coord(&self, idx: usize) -> Coord {
+        Coord::new(idx % self.size, idx / self.size)
+    }
+
+    /// 所有有棋子的点
+    fn occupied(&self) -> Bits {
+        self.stones[1..]
+            .iter()
+            .fold(Bits::EMPTY, |acc, &bits| acc.or(bits))
+    }
+
+    fn empty(&self) -> Bits {
+        self.on_board.and_not(self.occupied())
+    }
+
+    /// `bits` 的上下左右邻点 (不包括 `bits` 自身)
+    fn neighbors(&self, bits: Bits) -> Bits {
+        let k = self.size as u32;
+        let right = bits.shl(1).and(self.not_first_col);
+        let left = bits.shr(1).and(self.not_last_col);
+        let down = bits.shl(k);
+        let up = bits.shr(k);
+        right
+            .or(left)
+            .or(down)
+            .or(up)
+            .and(self.on_board)
+            .and_not(bits)
+    }
+
+    /// 从 `seed` 出发, 在 `mask` 内做 flood fill
+    fn flood(&self, seed: Bits, mask: Bits) -> Bits {
+        let mut group = seed.and(mask);
+        loop {
+            let grown = group.or(self.neighbors(group).and(mask));
+            if grown == group {
+                return group;
+            }
+            group = grown;
+        }
+    }
+
+    fn stone_at(&self, idx: usize) -> Stone {
+        for (i, bits) in self.stones.iter().enumerate().skip(1) {
+            if bits.get(idx) {
+                return Stone::new(i as u8);
+            }
+        }
+        return Stone::VOID;
+    }
+
+    /// 包含 `idx` 的棋子组
+    pub fn group(&self, idx: usize) -> Bits {
+        let stone = self.stone_at(idx);
+        if stone == Stone::VOID {
+            return Bits::EMPTY;
+        }
+        self.flood(Bits::single(idx), self.stones[stone.as_usize()])
+    }
+
+    /// 包含 `idx` 的棋子组的气
+    pub fn liberties(&self, idx: usize) -> usize {
+        self.neighbors(self.group(idx)).and(self.empty()).count()
+    }
+
+    pub fn place_stone(&mut self, coord: Coord, stone: Stone) -> PlaceStoneResult {
+        debug_assert!(stone != Stone::VOID);
+        if coord.x >= self.size || coord.y >= self.size {
+            return Err("非法坐标");
+        }
+        let cur_idx = self.idx(coord);
+
+        // 1. 禁止下到已有的棋子上
+        if self.stone_at(cur_idx) != Stone::VOID {
+            return Err("禁止下到已有的棋子上");
+        }
+
+        // 2. 落子后, 提走周围没有气的非己方组
+        let mut new_stones = self.stones;
+        new_stones[stone.as_usize()].set(cur_idx);
+        let mut empty = self.empty();
+        empty.clear(cur_idx);
+
+        let cur = Bits::single(cur_idx);
+        let mut eaten = Bits::EMPTY;
+        for (i, bits) in new_stones.iter_mut().enumerate().skip(1) {
+            if i == stone.as_usize() {
+                continue;
+            }
+            let touching = self.neighbors(cur).and(*bits);
+            for idx in touching.ones() {
+                if eaten.get(idx) {
+                    continue;
+                }
+                let group = self.flood(Bits::single(idx), *bits);
+                if self.neighbors(group).and(empty).is_empty() {
+                    eaten = eaten.or(group);
+                }
+            }
+            *bits = bits.and_not(eaten);
+        }
+
+        // 3. 禁止使己方气尽
+        if eaten.is_empty() {
+            let own = self.flood(cur, new_stones[stone.as_usize()]);
+            if self.neighbors(own).and(empty).is_empty() {
+                return Err("禁止使己方气尽");
+            }
+        }
+
+        // 4. 禁止全局同形
+        if self.history.contains(&new_stones) {
+            return Err("禁止全局同形");
+        }
+
+        // 5. 之后便允许落子
+        let mut new_hash = self.hash ^ zobrist_key(cur_idx, stone);
+        for idx in eaten.ones() {
+            new_hash ^= zobrist_key(idx, self.stone_at(idx));
+        }
+
+        let mut dropped = None;
+        if self.history.len() == MAX_STATES_RECORD {
+            dropped = self.history.pop_back();
+        }
+        self.history.push_front(new_stones);
+        self.undo_records.push(UndoRecord {
+            stones: self.stones,
+            hash: self.hash,
+            captures: self.captures,
+            dropped_history: dropped,
+        });
+
+        self.stones = new_stones;
+        self.hash = new_hash;
+        self.captures[stone.as_usize()] += eaten.count();
+
+        Ok(eaten.ones().map(|idx| self.coord(idx)).collect())
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(record) = self.undo_records.pop() else {
+            return false;
+        };
+        self.stones = record.stones;
+        self.hash = record.hash;
+        self.captures = record.captures;
+        self.history.pop_front();
+        if let Some(stones) = record.dropped_history {
+            self.history.push_back(stones);
+        }
+        return true;
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn stone(&self, coord: Coord) -> Stone {
+        self.stone_at(self.idx(coord))
+    }
+
+    /// 颜色为 `stone` 的所有棋子
+    pub fn stones(&self, stone: Stone) -> Bits {
+        self.stones[stone.as_usize()]
+    }
+
+    pub fn captures(&self, stone: Stone) -> usize {
+        self.captures[stone.as_usize()]
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn board_string(&self) -> String {
+        let mut s = String::with_capacity(self.size * (self.size + 1));
+        for y in 0..self.size {
+            for x in 0..self.size {
+                s.push(self.stone(Coord::new(x, y)).as_char());
+            }
+            s.push('\n');
+        }
+        return s;
+    }
+}
+
+impl GoBoard for BitBoard {
+    const MAX_SIZE: usize = MAX_BITBOARD_SIZE;
+
+    fn new(size: usize) -> Self {
+        BitBoard::new(size)
+    }
+
+    fn size(&self) -> usize {
+        self.size()
+    }
+
+    fn stone(&self, coord: Coord) -> Stone {
+        self.stone(coord)
+    }
+
+    fn place_stone(&mut self, coord: Coord, stone: Stone) -> PlaceStoneResult {
+        self.place_stone(coord, stone)
+    }
+
+    fn captures(&self, stone: Stone) -> usize {
+        self.captures(stone)
+    }
+
+    fn hash(&self) -> u64 {
+        self.hash()
+    }
+
+    fn undo(&mut self) -> bool {
+        self.undo()
+    }
+
+    fn board_string(&self) -> String {
+        self.board_string()
+    }
+}
@@ -0,0 +1,413 @@
+//! 开局库: 从棋谱目录统计前 N 手的着法频率与胜率
+//!
+//! 局面以 8 种对称变换下最小的 Zobrist hash 作为键, 所以对称的开局会被合并统计;
+//! 着法也按同一个对称变换存储, 查询时再变换回实际棋盘的方向。
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    Coord, Stone,
+    board::{Board, zobrist_key},
+    gtp,
+    sgf::{self, SgfNode},
+};
+
+const MAGIC: &[u8; 4] = b"RGOB";
+const FORMAT_VERSION: u8 = 1;
+
+/// 存储时 pass 的下标
+const PASS_IDX: u16 = u16::MAX;
+
+/// 一局棋谱中开局库需要的信息
+#[derive(Clone, Debug)]
+pub struct BookGame {
+    pub size: usize,
+    /// 依次的落子, `None` 表示 pass
+    pub moves: Vec<(Stone, Option<Coord>)>,
+    /// `None` 表示和棋或结果未知
+    pub winner: Option<Stone>,
+}
+
+/// 开局库中某局面下的一个着法
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BookMove {
+    /// `None` 表示 pass
+    pub coord: Option<Coord>,
+    /// 出现次数
+    pub count: u32,
+    /// 走这一手的一方最终获胜的次数
+    pub wins: u32,
+    /// 走这一手的一方最终落败的次数
+    pub losses: u32,
+}
+
+impl BookGame {
+    /// 取 SGF 棋谱的主变化, 胜负由 RE 的第一个字母决定
+    pub fn from_sgf(root: &SgfNode) -> Self {
+        let winner = match root.get("RE").and_then(|re| re.trim().chars().next()) {
+            Some('B') | Some('b') => Some(Stone::BLACK),
+            Some('W') | Some('w') => Some(Stone::WHITE),
+            _ => None,
+        };
+        BookGame {
+            size: root.size(),
+            moves: root.main_line_moves(),
+            winner,
+        }
+    }
+}
+
+impl BookMove {
+    /// 不计入结果未知的对局; 没有已知结果时返回 None
+    pub fn win_rate(&self) -> Option<f64> {
+        let decided = self.wins + self.losses;
+        if decided == 0 {
+            return None;
+        }
+        return Some(self.wins as f64 / decided as f64);
+    }
+}
+
+/// 存储时的着法, 坐标是规范方向下的下标
+#[derive(Clone, Copy, Debug)]
+struct Stat {
+    idx: u16,
+    count: u32,
+    wins: u32,
+    losses: u32,
+}
+
+pub struct OpeningBook {
+    size: usize,
+    /// 只统计每局的前 `max_moves` 手
+    max_moves: usize,
+    entries: HashMap<u64, Vec<Stat>>,
+}
+
+impl OpeningBook {
+    pub fn new(size: usize, max_moves: usize) -> Self {
+        assert!(size * size < PASS_IDX as usize);
+        OpeningBook {
+            size,
+            max_moves,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn max_moves(&self) -> usize {
+        self.max_moves
+    }
+
+    /// 收录的局面数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 收录一局棋谱的前 `max_moves` 手
+    ///
+    /// 棋盘大小不一致时不收录并返回 false; 遇到非法落子时只收录非法落子之前的部分
+    pub fn add_game(&mut self, game: &BookGame) -> bool {
+        if game.size != self.size {
+            return false;
+        }
+
+        let mut board = Board::new(self.size);
+        for &(stone, coord) in game.moves.iter().take(self.max_moves) {
+            let (key, sym) = self.canonical(&board, stone);
+            let idx = match coord {
+                Some(coord) => self.canonical_move(&board, stone, key, sym, coord),
+                None => PASS_IDX,
+            };
+            // 先落子, 非法的落子不收录
+            if let Some(coord) = coord
+                && board.place_stone(coord, stone).is_err()
+            {
+                break;
+            }
+
+            let stats = self.entries.entry(key).or_default();
+            let stat = match stats.iter_mut().find(|s| s.idx == idx) {
+                Some(stat) => stat,
+                None => {
+                    stats.push(Stat {
+                        idx,
+                        count: 0,
+                        wins: 0,
+                        losses: 0,
+                    });
+                    stats.last_mut().unwrap()
+                }
+            };
+            stat.count += 1;
+            match game.winner {
+                Some(winner) if winner == stone => stat.wins += 1,
+                Some(_) => stat.losses += 1,
+                None => {}
+            }
+        }
+        return true;
+    }
+
+    /// 收录一份 SGF 棋谱的主变化
+    pub fn add_sgf(&mut self, text: &str) -> bool {
+        match sgf::parse_game(text) {
+            Ok(root) => self.add_game(&BookGame::from_sgf(&root)),
+            Err(_) => false,
+        }
+    }
+
+    /// 收录目录下所有 `.sgf` 文件, 返回成功收录的对局数
+    ///
+    /// 非 UTF-8 编码的棋谱按 lossy 方式读取; 读不出的文件直接跳过, 不影响其他文件
+    pub fn add_dir(&mut self, dir: impl AsRef<Path>) -> io::Result<usize> {
+        let mut n = 0;
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let is_sgf = path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("sgf"));
+            if !is_sgf || !path.is_file() {
+                continue;
+            }
+            let Ok(bytes) = fs::read(&path) else {
+                continue;
+            };
+            if self.add_sgf(&String::from_utf8_lossy(&bytes)) {
+                n += 1;
+            }
+        }
+        return Ok(n);
+    }
+
+    /// 查询当前局面下 `to_play` 的开局库着法, 按出现次数降序
+    pub fn lookup(&self, board: &Board, to_play: Stone) -> Vec<BookMove> {
+        if board.size() != self.size {
+            return vec![];
+        }
+        let (key, sym) = self.canonical(board, to_play);
+        let Some(stats) = self.entries.get(&key) else {
+            return vec![];
+        };
+
+        let mut moves: Vec<BookMove> = stats
+            .iter()
+            .map(|stat| BookMove {
+                coord: if stat.idx == PASS_IDX {
+                    None
+                } else {
+                    let coord = board.coord(stat.idx as usize);
+                    Some(inverse(coord, sym, self.size))
+                },
+                count: stat.count,
+                wins: stat.wins,
+                losses: stat.losses,
+            })
+            .collect();
+        moves.sort_by_key(|m| std::cmp::Reverse(m.count));
+        return moves;
+    }
+
+    /// 在第 `move_number` 手 (从0开始) 时给出最常见的开局库着法
+    ///
+    /// 超出 `max_moves` 或局面不在库中时返回 None
+    pub fn suggest(&self, board: &Board, to_play: Stone, move_number: usize) -> Option<BookMove> {
+        if move_number >= self.max_moves {
+            return None;
+        }
+        self.lookup(board, to_play).into_iter().next()
+    }
+
+    /// 规范化的局面 hash, 以及取到该 hash 的对称变换
+    fn canonical(&self, board: &Board, to_play: Stone) -> (u64, u8) {
+        let mut best = (u64::MAX, 0);
+        for sym in 0..8 {
+            let hash = position_key(board, sym, to_play);
+            if hash < best.0 {
+                best = (hash, sym);
+            }
+        }
+        return best;
+    }
+
+    /// 把着法变换到规范方向
+    ///
+    /// 对称的局面可能有多个变换取到同一个 hash, 此时取变换后下标最小的着法, 使等价的着法合并
+    fn canonical_move(
+        &self,
+        board: &Board,
+        to_play: Stone,
+        key: u64,
+        sym: u8,
+        coord: Coord,
+    ) -> u16 {
+        let mut idx = board.idx(transform(coord, sym, self.size));
+        for other in 0..8 {
+            if other != sym && position_key(board, other, to_play) == key {
+                idx = idx.min(board.idx(transform(coord, other, self.size)));
+            }
+        }
+        return idx as u16;
+    }
+
+    /// 写入紧凑的二进制格式 (little endian)
+    ///
+    /// `magic, version: u8, size: u8, max_moves: u16, entries: u32,
+    /// [hash: u64, moves: u16, [idx: u16, count: u32, wins: u32, losses: u32]]`
+    ///
+    /// `max_moves` 超过 `u16::MAX` 时返回 `InvalidInput`
+    pub fn save(&self, mut w: impl Write) -> io::Result<()> {
+        let Ok(max_moves) = u16::try_from(self.max_moves) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "max_moves does not fit in the book format",
+            ));
+        };
+        w.write_all(MAGIC)?;
+        w.write_all(&[FORMAT_VERSION, self.size as u8])?;
+        w.write_all(&max_moves.to_le_bytes())?;
+        w.write_all(&(self.entries.len() as u32).to_le_bytes())?;
+
+        // 按 hash 排序, 使同样的开局库总是写出同样的文件
+        let mut keys: Vec<&u64> = self.entries.keys().collect();
+        keys.sort_unstable();
+        for key in keys {
+            let stats = &self.entries[key];
+            w.write_all(&key.to_le_bytes())?;
+            w.write_all(&(stats.len() as u16).to_le_bytes())?;
+            for stat in stats {
+                w.write_all(&stat.idx.to_le_bytes())?;
+                w.write_all(&stat.count.to_le_bytes())?;
+                w.write_all(&stat.wins.to_le_bytes())?;
+                w.write_all(&stat.losses.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(mut r: impl Read) -> io::Result<Self> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a book file",
+            ));
+        }
+        let mut header = [0u8; 2];
+        r.read_exact(&mut header)?;
+        if header[0] != FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unsupported book version",
+            ));
+        }
+
+        let size = header[1] as usize;
+        if !(2..=gtp::MAX_SIZE).contains(&size) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "invalid board size",
+            ));
+        }
+
+        let mut book = OpeningBook::new(size, read_u16(&mut r)? as usize);
+        let n_entries = read_u32(&mut r)?;
+        for _ in 0..n_entries {
+            let key = read_u64(&mut r)?;
+            let n_moves = read_u16(&mut r)?;
+            let mut stats = Vec::with_capacity(n_moves as usize);
+            for _ in 0..n_moves {
+                let idx = read_u16(&mut r)?;
+                if idx != PASS_IDX && idx as usize >= size * size {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "move out of board",
+                    ));
+                }
+                stats.push(Stat {
+                    idx,
+                    count: read_u32(&mut r)?,
+                    wins: read_u32(&mut r)?,
+                    losses: read_u32(&mut r)?,
+                });
+            }
+            book.entries.insert(key, stats);
+        }
+        return Ok(book);
+    }
+}
+
+fn read_u16(r: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    r.read_exact(&mut buf)?;
+    Ok(u16::from_le_bytes(buf))
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// 8 种对称变换: bit0 左右翻转, bit1 上下翻转, bit2 沿对角线翻转 (在前两者之后)
+fn transform(coord: Coord, sym: u8, size: usize) -> Coord {
+    let mut x = coord.x;
+    let mut y = coord.y;
+    if sym & 1 != 0 {
+        x = size - 1 - x;
+    }
+    if sym & 2 != 0 {
+        y = size - 1 - y;
+    }
+    if sym & 4 != 0 {
+        std::mem::swap(&mut x, &mut y);
+    }
+    return Coord::new(x, y);
+}
+
+fn inverse(coord: Coord, sym: u8, size: usize) -> Coord {
+    let mut x = coord.x;
+    let mut y = coord.y;
+    if sym & 4 != 0 {
+        std::mem::swap(&mut x, &mut y);
+    }
+    if sym & 1 != 0 {
+        x = size - 1 - x;
+    }
+    if sym & 2 != 0 {
+        y = size - 1 - y;
+    }
+    return Coord::new(x, y);
+}
+
+/// 对称变换 `sym` 之后的局面 hash, 包括轮到谁走
+fn position_key(board: &Board, sym: u8, to_play: Stone) -> u64 {
+    // 用棋盘之外的下标作为"轮到谁走"的 key
+    let mut hash = zobrist_key(usize::MAX >> 8, to_play);
+    for (idx, &stone) in board.board_array().iter().enumerate() {
+        if stone != Stone::VOID {
+            let coord = transform(board.coord(idx), sym, board.size());
+            hash ^= zobrist_key(board.idx(coord), stone);
+        }
+    }
+    return hash;
+}
@@ -0,0 +1,99 @@
+//! 简单的电脑棋手, 供 GTP 引擎使用
+
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+
+use crate::{
+    Coord, Stone,
+    board::{Board, GoBoard},
+    book::OpeningBook,
+};
+
+/// 在 `B` 棋盘上对弈的电脑棋手, 默认为 `Board`
+pub trait Bot<B: GoBoard = Board>: Send {
+    /// 为 `stone` 一方选一手棋, `None` 表示 pass
+    ///
+    /// `move_number` 为已经下过的手数 (从0开始); 返回的着法必须合法, 返回时棋盘保持不变
+    fn genmove(&mut self, board: &mut B, stone: Stone, move_number: usize) -> Option<Coord>;
+}
+
+/// 随机选一个合法的点, 但不填自己的眼; 没有这样的点时 pass
+pub struct RandomBot {
+    rng: StdRng,
+}
+
+impl RandomBot {
+    pub fn new() -> Self {
+        RandomBot {
+            rng: rand::make_rng(),
+        }
+    }
+
+    /// 固定种子, 结果可以复现
+    pub fn with_seed(seed: u64) -> Self {
+        RandomBot {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Default for RandomBot {
+    fn default() -> Self {
+        RandomBot::new()
+    }
+}
+
+/// 四周都是 `stone` 的棋子 (或棋盘边缘) 的空点
+fn is_own_eye<B: GoBoard>(board: &B, coord: Coord, stone: Stone) -> bool {
+    let size = board.size();
+    let (x, y) = (coord.x, coord.y);
+    let neighbors = [
+        (x > 0).then(|| Coord::new(x - 1, y)),
+        (x + 1 < size).then(|| Coord::new(x + 1, y)),
+        (y > 0).then(|| Coord::new(x, y - 1)),
+        (y + 1 < size).then(|| Coord::new(x, y + 1)),
+    ];
+    return neighbors
+        .into_iter()
+        .flatten()
+        .all(|n| board.stone(n) == stone);
+}
+
+impl<B: GoBoard> Bot<B> for RandomBot {
+    fn genmove(&mut self, board: &mut B, stone: Stone, _move_number: usize) -> Option<Coord> {
+        let size = board.size();
+        let mut candidates: Vec<Coord> = (0..size * size)
+            .map(|idx| Coord::new(idx % size, idx / size))
+            .filter(|&coord| board.stone(coord) == Stone::VOID)
+            .collect();
+        candidates.shuffle(&mut self.rng);
+        return candidates
+            .into_iter()
+            .find(|&coord| !is_own_eye(board, coord, stone) && board.is_legal(coord, stone));
+    }
+}
+
+/// 开局阶段按开局库落子, 之后 (或库中没有当前局面时) 交给 `fallback`
+///
+/// 开局库只能查询 `Board`, 所以只能在 `Board` 上使用
+pub struct BookBot<B: Bot> {
+    book: OpeningBook,
+    fallback: B,
+}
+
+impl<B: Bot> BookBot<B> {
+    pub fn new(book: OpeningBook, fallback: B) -> Self {
+        BookBot { book, fallback }
+    }
+}
+
+impl<B: Bot> Bot for BookBot<B> {
+    fn genmove(&mut self, board: &mut Board, stone: Stone, move_number: usize) -> Option<Coord> {
+        if let Some(book_move) = self.book.suggest(board, stone, move_number)
+            && let Some(coord) = book_move.coord
+            && board.is_legal(coord, stone)
+        {
+            return Some(coord);
+        }
+        return self.fallback.genmove(board, stone, move_number);
+    }
+}
@@ -2,9 +2,11 @@ pub type Array<T> = Box<[T]>;
 
 mod coord;
 mod disjoint_set;
+mod rollback_disjoint_set;
 mod stone;
 
 pub use coord::Coord;
 pub use disjoint_set::DisjointSet;
 pub use disjoint_set::IdxTrait;
+pub use rollback_disjoint_set::{Checkpoint, RollbackDisjointSet};
 pub use stone::Stone;
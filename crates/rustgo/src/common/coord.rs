@@ -1,7 +1,9 @@
 use std::fmt::{Debug, Display};
 
+use serde::{Deserialize, Serialize};
+
 // TODO translate 1-1 coord and A1 coord
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Coord {
     pub x: usize,
     pub y: usize,
@@ -48,6 +48,9 @@ pub struct DisjointSet<T: IdxTrait> {
     /// 非根节点: None
     /// 根节点: Vec of members
     group_members: Array<Option<Vec<usize>>>,
+
+    /// group 的个数, 在 insert/connect/delete_group 时维护, 避免每次 O(N) 统计
+    len: usize,
 }
 
 impl<T: IdxTrait> DisjointSet<T> {
@@ -63,6 +66,7 @@ impl<T: IdxTrait> DisjointSet<T> {
         DisjointSet {
             parent_idx: vec![None; capacity].into_boxed_slice(),
             group_members: vec![None; capacity].into_boxed_slice(),
+            len: 0,
         }
     }
 
@@ -71,9 +75,9 @@ impl<T: IdxTrait> DisjointSet<T> {
         self.parent_idx.len()
     }
 
-    /// 已经存储了多少元素
+    /// 有多少个 group
     pub fn len(&self) -> usize {
-        self.group_members.iter().filter(|x| x.is_some()).count()
+        self.len
     }
 
     pub fn is_empty(&self) -> bool {
@@ -84,20 +88,19 @@ impl<T: IdxTrait> DisjointSet<T> {
     ///
     /// 如果不存在 group, 则返回 None
     pub fn find_root(&mut self, idx: usize) -> Option<usize> {
-        let parent_idx = self.parent_idx[idx];
-        if let Some(parent_idx) = parent_idx {
-            let root = self.find_root(parent_idx.to_usize());
-            if let Some(root_idx) = root {
-                self.parent_idx[idx] = Some(T::from_usize(root_idx)); // 路径压缩
-            }
-            return root;
-        } else {
-            if self.group_members[idx].is_none() {
-                return None;
-            } else {
-                return Some(idx);
-            }
+        let mut root_idx = idx;
+        while let Some(parent_idx) = self.parent_idx[root_idx] {
+            root_idx = parent_idx.to_usize();
+        }
+        self.group_members[root_idx].as_ref()?;
+
+        // 路径压缩: 路径上的节点都直接挂到 root 下
+        let mut cur_idx = idx;
+        while let Some(parent_idx) = self.parent_idx[cur_idx] {
+            self.parent_idx[cur_idx] = Some(T::from_usize(root_idx));
+            cur_idx = parent_idx.to_usize();
         }
+        return Some(root_idx);
     }
 
     /// 强制做一次路径压缩, 一般情况无须手动调用
@@ -151,6 +154,7 @@ impl<T: IdxTrait> DisjointSet<T> {
             // 恢复初始值
             self.parent_idx[idx] = None;
         }
+        self.len -= 1;
         members.sort_unstable(); // 排序不放在 connect 里, 因为 connect 调用的更频繁
         return Some(members);
     }
@@ -173,6 +177,7 @@ impl<T: IdxTrait> DisjointSet<T> {
     pub fn insert(&mut self, idx: usize) {
         if !self.contains(idx) {
             self.group_members[idx] = Some(vec![idx]);
+            self.len += 1;
         }
     }
 
@@ -188,15 +193,24 @@ impl<T: IdxTrait> DisjointSet<T> {
         let root_a = self.find_root(idx_a).unwrap();
         let root_b = self.find_root(idx_b).unwrap();
         if root_a != root_b {
-            // 把a挂到b下
-            let mut members_a = self.group_members[root_a].take().unwrap(); // take out, leave as None
+            // 把小的 group 挂到大的下, 成员也从小的并入大的
+            let size_a = self.group_members[root_a].as_ref().unwrap().len();
+            let size_b = self.group_members[root_b].as_ref().unwrap().len();
+            let (child, parent) = if size_a <= size_b {
+                (root_a, root_b)
+            } else {
+                (root_b, root_a)
+            };
+
+            let mut members = self.group_members[child].take().unwrap(); // take out, leave as None
 
-            self.group_members[root_b]
+            self.group_members[parent]
                 .as_mut()
                 .unwrap()
-                .append(&mut members_a);
+                .append(&mut members);
 
-            self.parent_idx[root_a] = Some(T::from_usize(root_b));
+            self.parent_idx[child] = Some(T::from_usize(parent));
+            self.len -= 1;
         }
     }
 }
@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use crate::{Array, IdxTrait};
+
+/// 回滚点, 由 `RollbackDisjointSet::checkpoint` 创建
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Checkpoint(usize);
+
+/// 一次修改的记录, 用于回滚
+#[derive(Clone, Debug)]
+enum Change {
+    Insert(usize),
+    /// `child` 挂到了 `parent` 下 (两者都是当时的 group root)
+    Connect {
+        child: usize,
+        parent: usize,
+    },
+    /// 被删除的 group 的所有成员: (idx, parent_idx, next, group_size)
+    Delete(Vec<(usize, Option<usize>, usize, usize)>),
+}
+
+/// 可回滚的并查集
+///
+/// 与 `DisjointSet` 的区别:
+/// - 按 group 大小合并, 不做路径压缩, 所以查询都是 `&self` 的, 树高为 O(log N)
+/// - 每次修改都记录在日志里, 可以在 O(修改次数) 内回滚到任意 `Checkpoint`
+#[derive(Clone)]
+pub struct RollbackDisjointSet<T: IdxTrait> {
+    /// 所属父级信息
+    /// 非根节点: 父级下标
+    /// 根节点或不存在: None
+    parent_idx: Array<Option<T>>,
+
+    /// 组大小
+    /// 根节点: 成员个数
+    /// 非根节点: 被合并时所在 group 的成员个数 (回滚合并时需要)
+    /// 不存在: 0
+    group_size: Array<T>,
+
+    /// 组成员的环形链表, 合并两个组只需交换两个 root 的 next, 再交换一次即可拆开
+    ///
+    /// 不存在的元素: next[idx] == idx
+    next: Array<T>,
+
+    /// group 的个数
+    len: usize,
+
+    log: Vec<Change>,
+}
+
+impl<T: IdxTrait> RollbackDisjointSet<T> {
+    /// 创建容量为 `capacity` 的并查集 (不可扩容)
+    pub fn new(capacity: usize) -> Self {
+        assert!(
+            capacity <= T::max_value(),
+            "capacity {} exceeds max storable index {}",
+            capacity,
+            T::max_value()
+        );
+
+        RollbackDisjointSet {
+            parent_idx: vec![None; capacity].into_boxed_slice(),
+            group_size: vec![T::from_usize(0); capacity].into_boxed_slice(),
+            next: (0..capacity)
+                .map(T::from_usize)
+                .collect::<Vec<T>>()
+                .into_boxed_slice(),
+            len: 0,
+            log: vec![],
+        }
+    }
+
+    /// 最大能承载多少元素
+    pub fn capacity(&self) -> usize {
+        self.parent_idx.len()
+    }
+
+    /// 有多少个 group (与 `DisjointSet::len` 一致)
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 记录当前状态, 之后可以用 `rollback` 回到这里
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.log.len())
+    }
+
+    /// 撤销 `checkpoint` 之后的所有修改
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        assert!(
+            checkpoint.0 <= self.log.len(),
+            "checkpoint already discarded"
+        );
+        while self.log.len() > checkpoint.0 {
+            match self.log.pop().unwrap() {
+                Change::Insert(idx) => {
+                    self.group_size[idx] = T::from_usize(0);
+                    self.len -= 1;
+                }
+                Change::Connect { child, parent } => {
+                    self.parent_idx[child] = None;
+                    let size =
+                        self.group_size[parent].to_usize() - self.group_size[child].to_usize();
+                    self.group_size[parent] = T::from_usize(size);
+                    self.next.swap(child, parent);
+                    self.len += 1;
+                }
+                Change::Delete(members) => {
+                    for (idx, parent_idx, next, group_size) in members {
+                        self.parent_idx[idx] = parent_idx.map(T::from_usize);
+                        self.next[idx] = T::from_usize(next);
+                        self.group_size[idx] = T::from_usize(group_size);
+                    }
+                    self.len += 1;
+                }
+            }
+        }
+    }
+
+    /// 丢弃所有修改记录 (之前的 `Checkpoint` 都将失效)
+    pub fn clear_history(&mut self) {
+        self.log.clear();
+    }
+
+    /// 寻找 idx 所属 group 的 group root
+    ///
+    /// 如果不存在 group, 则返回 None
+    pub fn find_root(&self, idx: usize) -> Option<usize> {
+        let mut cur = idx;
+        while let Some(parent) = self.parent_idx[cur] {
+            cur = parent.to_usize();
+        }
+        if self.group_size[cur].to_usize() == 0 {
+            return None;
+        }
+        return Some(cur);
+    }
+
+    /// 返回 idx 所属 group 的所有 member 的个数
+    ///
+    /// 如果不存在 group, 则返回 0
+    pub fn group_size(&self, idx: usize) -> usize {
+        match self.find_root(idx) {
+            Some(root_idx) => self.group_size[root_idx].to_usize(),
+            None => 0,
+        }
+    }
+
+    /// 返回 idx 所属 group 的所有 member (保证升序)
+    pub fn group_members(&self, idx: usize) -> Option<Vec<usize>> {
+        let root_idx = self.find_root(idx)?;
+        let mut members = self.members_unsorted(root_idx);
+        members.sort_unstable();
+        return Some(members);
+    }
+
+    fn members_unsorted(&self, root_idx: usize) -> Vec<usize> {
+        let mut members = Vec::with_capacity(self.group_size[root_idx].to_usize());
+        let mut idx = root_idx;
+        loop {
+            members.push(idx);
+            idx = self.next[idx].to_usize();
+            if idx == root_idx {
+                break;
+            }
+        }
+        return members;
+    }
+
+    /// 返回所有的 group root
+    ///
+    /// 时间复杂度 O(N)
+    pub fn group_roots(&self) -> Vec<usize> {
+        (0..self.capacity())
+            .filter(|&idx| self.parent_idx[idx].is_none() && self.group_size[idx].to_usize() > 0)
+            .collect()
+    }
+
+    /// 删除 idx 所属 group 的所有 members, 并返回该 group 的 Some(members) (保证升序)
+    ///
+    /// 如果不存在 group, 则 None
+    pub fn delete_group(&mut self, idx: usize) -> Option<Vec<usize>> {
+        let root_idx = self.find_root(idx)?;
+
+        let mut members = self.members_unsorted(root_idx);
+        let record = members
+            .iter()
+            .map(|&idx| {
+                (
+                    idx,
+                    self.parent_idx[idx].map(|p| p.to_usize()),
+                    self.next[idx].to_usize(),
+                    self.group_size[idx].to_usize(),
+                )
+            })
+            .collect();
+        self.log.push(Change::Delete(record));
+
+        for &idx in &members {
+            // 恢复初始值
+            self.parent_idx[idx] = None;
+            self.next[idx] = T::from_usize(idx);
+            self.group_size[idx] = T::from_usize(0);
+        }
+        self.len -= 1;
+
+        members.sort_unstable();
+        return Some(members);
+    }
+
+    pub fn is_connected(&self, idx_a: usize, idx_b: usize) -> bool {
+        match (self.find_root(idx_a), self.find_root(idx_b)) {
+            (Some(root_a), Some(root_b)) => root_a == root_b,
+            _ => false,
+        }
+    }
+
+    /// 添加一个元素, 创建孤立的 group
+    ///
+    /// 如果元素已经存在, 则什么都不做
+    pub fn insert(&mut self, idx: usize) {
+        if !self.contains(idx) {
+            self.group_size[idx] = T::from_usize(1);
+            self.len += 1;
+            self.log.push(Change::Insert(idx));
+        }
+    }
+
+    /// 是否存在元素
+    pub fn contains(&self, idx: usize) -> bool {
+        self.parent_idx[idx].is_some() || self.group_size[idx].to_usize() > 0
+    }
+
+    pub fn connect(&mut self, idx_a: usize, idx_b: usize) {
+        self.insert(idx_a);
+        self.insert(idx_b);
+
+        let root_a = self.find_root(idx_a).unwrap();
+        let root_b = self.find_root(idx_b).unwrap();
+        if root_a != root_b {
+            // 小的挂到大的下
+            let (child, parent) =
+                if self.group_size[root_a].to_usize() <= self.group_size[root_b].to_usize() {
+                    (root_a, root_b)
+                } else {
+                    (root_b, root_a)
+                };
+
+            self.parent_idx[child] = Some(T::from_usize(parent));
+            let size = self.group_size[parent].to_usize() + self.group_size[child].to_usize();
+            self.group_size[parent] = T::from_usize(size);
+            self.next.swap(child, parent);
+            self.len -= 1;
+            self.log.push(Change::Connect { child, parent });
+        }
+    }
+}
+
+impl<T: IdxTrait> Debug for RollbackDisjointSet<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:?}", self.parent_idx)?;
+        writeln!(f, "{:?}", self.group_size)?;
+
+        // root_idx -> member_idxs
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for idx in 0..self.capacity() {
+            if let Some(root_idx) = self.find_root(idx) {
+                groups.entry(root_idx).or_default().push(idx);
+            }
+        }
+
+        writeln!(f, "Groups:")?;
+        for (root_idx, member_idxs) in groups {
+            writeln!(f, "    root@[{}], members: {:?}", root_idx, member_idxs)?;
+        }
+        writeln!(f, "Changes: {}", self.log.len())?;
+        Ok(())
+    }
+}
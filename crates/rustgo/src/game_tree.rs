@@ -0,0 +1,486 @@
+//! 带变化的棋谱树, 以及在树上移动的光标
+//!
+//! 节点保存落子、摆子、注释与标记, 光标所在位置的局面由 `Board` 实时维护。
+//! 与 SGF 之间通过 `GameTree::from_sgf` / `GameTree::to_sgf` 互相转换。
+
+use crate::{
+    Coord, Stone,
+    board::Board,
+    sgf::{self, GameInfo, SgfMove, SgfNode, SgfProperty},
+};
+
+/// 节点在 `GameTree` 中的编号, 根节点为 `GameTree::ROOT`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MarkupKind {
+    /// CR
+    Circle,
+    /// SQ
+    Square,
+    /// TR
+    Triangle,
+    /// MA
+    Cross,
+    /// LB
+    Label(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Markup {
+    pub coord: Coord,
+    pub kind: MarkupKind,
+}
+
+const MARKUP_IDENTS: [(&str, MarkupKind); 4] = [
+    ("CR", MarkupKind::Circle),
+    ("SQ", MarkupKind::Square),
+    ("TR", MarkupKind::Triangle),
+    ("MA", MarkupKind::Cross),
+];
+
+#[derive(Clone, Debug, Default)]
+pub struct TreeNode {
+    pub parent: Option<NodeId>,
+    /// `children[0]` 为主变化
+    pub children: Vec<NodeId>,
+    /// 落子, 坐标为 `None` 表示 pass
+    pub mv: Option<SgfMove>,
+    /// 摆子, 颜色为 `Stone::VOID` 表示清除
+    pub setup: Vec<(Stone, Coord)>,
+    pub comment: Option<String>,
+    pub markup: Vec<Markup>,
+    /// 其他不认识的 SGF 属性, 写回 SGF 时原样保留
+    pub extra: Vec<SgfProperty>,
+
+    /// 从这个节点往下走时选择的子节点 (即最近一次访问的变化)
+    selected: usize,
+}
+
+/// 棋谱树与光标
+///
+/// 光标处的局面总是等于从根节点依次应用路径上所有节点的结果
+#[derive(Clone)]
+pub struct GameTree {
+    info: GameInfo,
+    nodes: Vec<TreeNode>,
+    current: NodeId,
+    board: Board,
+}
+
+impl GameTree {
+    pub const ROOT: NodeId = NodeId(0);
+
+    pub fn new(info: GameInfo) -> Self {
+        let board = Board::new(info.size);
+        GameTree {
+            info,
+            nodes: vec![TreeNode::default()],
+            current: Self::ROOT,
+            board,
+        }
+    }
+
+    /// 从 SGF 棋谱创建, 会检查所有变化中的落子是否合法
+    pub fn from_sgf(root: &SgfNode) -> Result<Self, &'static str> {
+        let mut tree = GameTree::new(root.game_info());
+        let size = tree.info.size;
+
+        // (sgf 节点, 父节点), 先序遍历, 保证子节点的顺序
+        let mut stack = vec![(root, None)];
+        while let Some((sgf_node, parent)) = stack.pop() {
+            let node = tree_node(sgf_node, size);
+            let id = match parent {
+                None => {
+                    tree.nodes[0] = node;
+                    Self::ROOT
+                }
+                Some(parent) => tree.push_node(parent, node),
+            };
+            for child in sgf_node.children.iter().rev() {
+                stack.push((child, Some(id)));
+            }
+        }
+
+        tree.check_legal()?;
+        tree.apply(Self::ROOT)?;
+        return Ok(tree);
+    }
+
+    /// 深度优先地复盘所有变化
+    fn check_legal(&self) -> Result<(), &'static str> {
+        let mut board = Board::new(self.info.size);
+        let mut stack = vec![(Self::ROOT, None)];
+        while let Some((id, saved)) = stack.pop() {
+            if let Some(saved) = saved {
+                // 回到了父节点
+                board = saved;
+                continue;
+            }
+            let saved = board.clone();
+            let node = self.node(id);
+            sgf::apply_setup(&mut board, &node.setup);
+            if let Some((stone, Some(coord))) = node.mv {
+                board.place_stone(coord, stone)?;
+            }
+            stack.push((id, Some(saved)));
+            for &child in node.children.iter().rev() {
+                stack.push((child, None));
+            }
+        }
+        Ok(())
+    }
+
+    /// 转换为 SGF 棋谱树
+    pub fn to_sgf(&self) -> SgfNode {
+        let mut root = self.sgf_node(Self::ROOT);
+        root.set_game_info(&self.info);
+
+        // 后序地把子节点挂到父节点上, 避免递归
+        let mut order = vec![];
+        let mut stack = vec![Self::ROOT];
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            stack.extend(self.node(id).children.iter().copied());
+        }
+        let mut built: Vec<Option<SgfNode>> = vec![None; self.nodes.len()];
+        for &id in order.iter().rev() {
+            let mut sgf_node = if id == Self::ROOT {
+                std::mem::take(&mut root)
+            } else {
+                self.sgf_node(id)
+            };
+            for child in &self.node(id).children {
+                sgf_node.children.push(built[child.0].take().unwrap());
+            }
+            built[id.0] = Some(sgf_node);
+        }
+        return built[Self::ROOT.0].take().unwrap();
+    }
+
+    fn sgf_node(&self, id: NodeId) -> SgfNode {
+        let node = self.node(id);
+        let mut sgf_node = SgfNode::new();
+        sgf_node.properties = node.extra.clone();
+        for &(stone, coord) in &node.setup {
+            sgf_node.add_setup(stone, coord);
+        }
+        if let Some((stone, coord)) = node.mv {
+            sgf_node.set_move(stone, coord);
+        }
+        if let Some(comment) = &node.comment {
+            sgf_node.set_comment(comment);
+        }
+        for markup in &node.markup {
+            let (ident, value) = match &markup.kind {
+                MarkupKind::Label(text) => (
+                    "LB",
+                    format!("{}:{}", sgf::point_string(markup.coord), text),
+                ),
+                kind => {
+                    let (ident, _) = MARKUP_IDENTS.iter().find(|(_, k)| k == kind).unwrap();
+                    (*ident, sgf::point_string(markup.coord))
+                }
+            };
+            let mut values = sgf_node.get_all(ident).unwrap_or_default().to_vec();
+            values.push(value);
+            sgf_node.set(ident, values);
+        }
+        return sgf_node;
+    }
+
+    pub fn info(&self) -> &GameInfo {
+        &self.info
+    }
+
+    pub fn info_mut(&mut self) -> &mut GameInfo {
+        &mut self.info
+    }
+
+    pub fn node(&self, id: NodeId) -> &TreeNode {
+        &self.nodes[id.0]
+    }
+
+    /// 节点总数 (包括根节点)
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.len() == 1
+    }
+
+    /// 光标所在的节点
+    pub fn current(&self) -> NodeId {
+        self.current
+    }
+
+    /// 光标处的局面
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// 从根节点到光标处 (包括两端) 的路径
+    pub fn path(&self) -> Vec<NodeId> {
+        self.path_to(self.current)
+    }
+
+    fn path_to(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path = vec![id];
+        let mut id = id;
+        while let Some(parent) = self.node(id).parent {
+            path.push(parent);
+            id = parent;
+        }
+        path.reverse();
+        return path;
+    }
+
+    /// 光标处是第几手 (根节点为 0, pass 也算一手)
+    pub fn move_number(&self) -> usize {
+        self.path()
+            .into_iter()
+            .filter(|&id| self.node(id).mv.is_some())
+            .count()
+    }
+
+    /// 光标处轮到谁下
+    pub fn to_play(&self) -> Stone {
+        let n = self.info.num_colors as u8;
+        for id in self.path().into_iter().rev() {
+            if let Some((stone, _)) = self.node(id).mv {
+                return stone.next_stone(n);
+            }
+        }
+        // 让子棋由白先下
+        if self.info.handicap.is_some_and(|h| h >= 2) {
+            return Stone::WHITE;
+        }
+        return Stone::BLACK;
+    }
+
+    /// 光标处的所有变化
+    pub fn variations(&self) -> &[NodeId] {
+        &self.node(self.current).children
+    }
+
+    /// 沿当前选择的变化前进一步
+    pub fn go_next(&mut self) -> bool {
+        let node = self.node(self.current);
+        match node.children.get(node.selected) {
+            Some(&child) => self.enter(child),
+            None => false,
+        }
+    }
+
+    /// 后退一步
+    pub fn go_prev(&mut self) -> bool {
+        let Some(parent) = self.node(self.current).parent else {
+            return false;
+        };
+        let node = self.node(self.current);
+        if !node.setup.is_empty() {
+            // 摆子无法撤销, 从头复盘
+            self.go_to(parent);
+            return true;
+        }
+        if let Some((_, Some(_))) = node.mv {
+            let undone = self.board.undo();
+            debug_assert!(undone);
+        }
+        self.current = parent;
+        return true;
+    }
+
+    /// 进入第 `i` 个变化
+    pub fn variation(&mut self, i: usize) -> bool {
+        match self.variations().get(i) {
+            Some(&child) => self.enter(child),
+            None => false,
+        }
+    }
+
+    /// 进入光标处的某个子节点, 并记住这个选择
+    fn enter(&mut self, child: NodeId) -> bool {
+        let Some(i) = self.variations().iter().position(|&c| c == child) else {
+            return false;
+        };
+        if self.apply(child).is_err() {
+            return false;
+        }
+        self.nodes[self.current.0].selected = i;
+        self.current = child;
+        return true;
+    }
+
+    /// 把节点的摆子与落子应用到棋盘上
+    fn apply(&mut self, id: NodeId) -> Result<(), &'static str> {
+        let node = &self.nodes[id.0];
+        sgf::apply_setup(&mut self.board, &node.setup);
+        if let Some((stone, Some(coord))) = node.mv {
+            self.board.place_stone(coord, stone)?;
+        }
+        Ok(())
+    }
+
+    /// 跳到任意节点, 从根节点重新复盘
+    pub fn go_to(&mut self, id: NodeId) {
+        let path = self.path_to(id);
+        self.board = Board::new(self.info.size);
+        for (i, &node) in path.iter().enumerate() {
+            // 落子在创建节点时都检查过, 不会失败
+            self.apply(node).unwrap();
+            if let Some(&child) = path.get(i + 1) {
+                let selected = self.node(node).children.iter().position(|&c| c == child);
+                self.nodes[node.0].selected = selected.unwrap();
+            }
+        }
+        self.current = id;
+    }
+
+    /// 回到根节点
+    pub fn go_to_root(&mut self) {
+        self.go_to(Self::ROOT);
+    }
+
+    /// 沿当前选择的变化走到第 `n` 手, 超出时停在最后一个节点
+    ///
+    /// 返回是否恰好到达了第 `n` 手
+    pub fn go_to_move(&mut self, n: usize) -> bool {
+        let mut number = self.move_number();
+        if n < number {
+            self.go_to_root();
+            number = self.move_number();
+        }
+        while number < n {
+            if !self.go_next() {
+                return false;
+            }
+            if self.node(self.current).mv.is_some() {
+                number += 1;
+            }
+        }
+        return true;
+    }
+
+    /// 在光标处落子
+    ///
+    /// 如果已经有相同落子的变化, 则直接进入; 否则创建新的变化 (分支)
+    pub fn play(&mut self, stone: Stone, coord: Option<Coord>) -> Result<NodeId, &'static str> {
+        let mv = Some((stone, coord));
+        let existing = self
+            .variations()
+            .iter()
+            .copied()
+            .find(|&c| self.node(c).mv == mv && self.node(c).setup.is_empty());
+        if let Some(child) = existing {
+            self.enter(child);
+            return Ok(child);
+        }
+
+        if let Some(coord) = coord {
+            self.board.place_stone(coord, stone)?;
+        }
+        let child = self.push_node(
+            self.current,
+            TreeNode {
+                mv,
+                ..Default::default()
+            },
+        );
+        let children = &self.node(self.current).children;
+        self.nodes[self.current.0].selected = children.len() - 1;
+        self.current = child;
+        return Ok(child);
+    }
+
+    /// 在光标处新建一个只有摆子的节点, 并进入该节点
+    pub fn add_setup(&mut self, setup: Vec<(Stone, Coord)>) -> NodeId {
+        let child = self.push_node(
+            self.current,
+            TreeNode {
+                setup,
+                ..Default::default()
+            },
+        );
+        self.enter(child);
+        return child;
+    }
+
+    fn push_node(&mut self, parent: NodeId, mut node: TreeNode) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        node.parent = Some(parent);
+        self.nodes.push(node);
+        self.nodes[parent.0].children.push(id);
+        return id;
+    }
+
+    pub fn set_comment(&mut self, comment: Option<String>) {
+        self.nodes[self.current.0].comment = comment;
+    }
+
+    pub fn add_markup(&mut self, markup: Markup) {
+        self.nodes[self.current.0].markup.push(markup);
+    }
+
+    pub fn clear_markup(&mut self) {
+        self.nodes[self.current.0].markup.clear();
+    }
+
+    /// 把光标处所在的变化提升为父节点的主变化
+    pub fn promote_variation(&mut self) {
+        let Some(parent) = self.node(self.current).parent else {
+            return;
+        };
+        let children = &mut self.nodes[parent.0].children;
+        let i = children.iter().position(|&c| c == self.current).unwrap();
+        let child = children.remove(i);
+        children.insert(0, child);
+        self.nodes[parent.0].selected = 0;
+    }
+}
+
+/// 由 SGF 节点得到树节点 (不包括父子关系)
+fn tree_node(sgf_node: &SgfNode, size: usize) -> TreeNode {
+    let mut node = TreeNode {
+        mv: sgf_node.sgf_move(size),
+        setup: sgf_node.setup(size),
+        comment: sgf_node.comment().map(|c| c.to_string()),
+        ..Default::default()
+    };
+    for (ident, kind) in MARKUP_IDENTS {
+        for value in sgf_node.get_all(ident).unwrap_or_default() {
+            for coord in sgf::parse_point_list(value, size) {
+                node.markup.push(Markup {
+                    coord,
+                    kind: kind.clone(),
+                });
+            }
+        }
+    }
+    for value in sgf_node.get_all("LB").unwrap_or_default() {
+        if let Some((point, text)) = value.split_once(':')
+            && let Some(coord) = sgf::parse_point(point, size)
+        {
+            node.markup.push(Markup {
+                coord,
+                kind: MarkupKind::Label(text.to_string()),
+            });
+        }
+    }
+
+    let handled = |ident: &str| {
+        sgf::ident_color(ident).is_some()
+            || (ident.starts_with('A') && sgf::ident_color(&ident[1..]).is_some())
+            || ["AE", "C", "LB"].contains(&ident)
+            || MARKUP_IDENTS.iter().any(|(i, _)| *i == ident)
+    };
+    node.extra = sgf_node
+        .properties
+        .iter()
+        .filter(|p| !handled(&p.ident))
+        .cloned()
+        .collect();
+    return node;
+}
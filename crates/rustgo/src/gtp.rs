@@ -0,0 +1,449 @@
+//! GTP v2 (Go Text Protocol) 引擎前端
+//!
+//! 把 `Board` 与一个 `Bot` 包装成 GTP 程序, 供 Sabaki、GoGui、twogtp 等工具调用。
+//! 协议见 <https://www.lysator.liu.se/~gunnar/gtp/gtp2-spec-draft2/gtp2-spec.html>
+//!
+//! `final_score` 用数子法, 且不判断死子 (`final_status_list dead` 总是为空)。
+//! `time_settings`/`time_left` 只检查参数, 随机落子用不到时间。
+//!
+//! # 多色扩展
+//!
+//! | 内容 | 说明 |
+//! |------|------|
+//! | 颜色 | 第 3~6 种颜色写作 `C3`~`C6` (不区分大小写), 黑白仍为 `B`/`W` |
+//! | `num_colors n` | 设置颜色数 (2~6) 并清空棋盘, 默认为 2 |
+//! | `final_score` | 多于两色时为 `<领先的颜色>+<与第二名的差>`, 贴目加给黑以外的每一方 |
+
+use std::io::{self, BufRead, Write};
+
+use crate::{Coord, Stone, board::Board, bot::Bot, score, sgf::MAX_COLORS};
+
+/// GTP 坐标的列字母, 跳过 I
+const COLUMNS: &[u8] = b"ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+/// GTP 坐标能表示的最大棋盘
+pub const MAX_SIZE: usize = COLUMNS.len();
+
+pub const DEFAULT_SIZE: usize = 19;
+pub const DEFAULT_KOMI: f64 = 7.5;
+
+/// 支持的命令, 即 `list_commands` 的输出
+pub const COMMANDS: &[&str] = &[
+    "boardsize",
+    "clear_board",
+    "final_score",
+    "final_status_list",
+    "fixed_handicap",
+    "genmove",
+    "known_command",
+    "komi",
+    "list_commands",
+    "name",
+    "num_colors",
+    "play",
+    "protocol_version",
+    "quit",
+    "showboard",
+    "time_left",
+    "time_settings",
+    "undo",
+    "version",
+];
+
+/// 一条 GTP 命令
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GtpCommand {
+    pub id: Option<u32>,
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+/// 解析一行输入; 空行与注释行返回 `None`
+///
+/// 按协议要求先去掉控制字符与 `#` 之后的注释, 并把 tab 当作空格
+pub fn parse_command(line: &str) -> Option<GtpCommand> {
+    let line: String = line
+        .split('#')
+        .next()
+        .unwrap_or("")
+        .chars()
+        .filter_map(|c| match c {
+            '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect();
+
+    let mut tokens = line.split_whitespace();
+    let mut first = tokens.next()?;
+    let mut id = None;
+    if first.bytes().all(|b| b.is_ascii_digit()) {
+        id = first.parse().ok();
+        first = tokens.next()?;
+    }
+    return Some(GtpCommand {
+        id,
+        name: first.to_string(),
+        args: tokens.map(str::to_string).collect(),
+    });
+}
+
+/// 把命令的结果格式化为 GTP 响应 (以空行结尾)
+pub fn format_response(id: Option<u32>, res: &Result<String, &'static str>) -> String {
+    let (prefix, text) = match res {
+        Ok(text) => ('=', text.as_str()),
+        Err(text) => ('?', *text),
+    };
+    let id = id.map(|id| id.to_string()).unwrap_or_default();
+    if text.is_empty() {
+        return format!("{prefix}{id}\n\n");
+    }
+    return format!("{prefix}{id} {text}\n\n");
+}
+
+/// 各颜色在 GTP 中的名字, 下标为 `stone.as_usize() - 1`
+const COLOR_NAMES: [&str; MAX_COLORS] = ["B", "W", "C3", "C4", "C5", "C6"];
+
+/// `b`/`black`/`w`/`white` 以及扩展的 `c3`~`c6`, 不区分大小写
+pub fn parse_color(s: &str) -> Option<Stone> {
+    match s.to_ascii_lowercase().as_str() {
+        "black" => return Some(Stone::BLACK),
+        "white" => return Some(Stone::WHITE),
+        _ => {}
+    }
+    let n = COLOR_NAMES
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(s))?;
+    return Some(Stone::new(n as u8 + 1));
+}
+
+/// GTP 中的颜色名, 第 3 种以后的颜色用扩展的名字
+pub fn color_name(stone: Stone) -> Option<&'static str> {
+    if stone == Stone::VOID {
+        return None;
+    }
+    COLOR_NAMES.get(stone.as_usize() - 1).copied()
+}
+
+/// 解析引擎返回的一条完整响应 (不含结尾的空行), 去掉 `=`/`?` 与 id
+///
+/// 成功为 `Ok`, 失败为 `Err`; 格式不对时返回 `None`
+pub fn parse_response(text: &str) -> Option<Result<String, String>> {
+    let (ok, rest) = match text.chars().next()? {
+        '=' => (true, &text[1..]),
+        '?' => (false, &text[1..]),
+        _ => return None,
+    };
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+    let body = rest.trim().to_string();
+    return Some(if ok { Ok(body) } else { Err(body) });
+}
+
+/// 解析 GTP 坐标 (如 `D4`, 不区分大小写); `pass` 返回 `Ok(None)`
+pub fn parse_vertex(s: &str, size: usize) -> Result<Option<Coord>, &'static str> {
+    let s = s.to_ascii_uppercase();
+    if s == "PASS" {
+        return Ok(None);
+    }
+    let Some((&col, row)) = s.as_bytes().split_first() else {
+        return Err("invalid coordinate");
+    };
+    let Some(x) = COLUMNS.iter().position(|&c| c == col) else {
+        return Err("invalid coordinate");
+    };
+    let Ok(row) = std::str::from_utf8(row).unwrap_or("").parse::<usize>() else {
+        return Err("invalid coordinate");
+    };
+    if x >= size || row == 0 || row > size {
+        return Err("invalid coordinate");
+    }
+    return Ok(Some(Coord::new(x, size - row)));
+}
+
+/// GTP 坐标, `None` 为 `pass`
+pub fn vertex_string(coord: Option<Coord>, size: usize) -> String {
+    match coord {
+        Some(coord) => format!("{}{}", COLUMNS[coord.x] as char, size - coord.y),
+        None => "pass".to_string(),
+    }
+}
+
+/// 协议规定的让子位置, 按 `fixed_handicap` 输出的顺序
+///
+/// 7x7 以下的棋盘不能让子; 偶数路或 7 路最多让 4 子, 其他最多 9 子
+pub fn handicap_points(size: usize, n: usize) -> Option<Vec<Coord>> {
+    let max = if size < 7 {
+        0
+    } else if size == 7 || size.is_multiple_of(2) {
+        4
+    } else {
+        9
+    };
+    if n < 2 || n > max {
+        return None;
+    }
+
+    let low = if size >= 13 { 3 } else { 2 };
+    let high = size - 1 - low;
+    let mid = size / 2;
+    // 坐标以左上角为原点, 所以 D4 是 (low, high)
+    let mut points = vec![
+        Coord::new(low, high),
+        Coord::new(high, low),
+        Coord::new(low, low),
+        Coord::new(high, high),
+    ];
+    if n >= 6 {
+        points.push(Coord::new(low, mid));
+        points.push(Coord::new(high, mid));
+    }
+    if n >= 8 {
+        points.push(Coord::new(mid, high));
+        points.push(Coord::new(mid, low));
+    }
+    // 5 子以上的奇数让子再加上天元
+    if n >= 5 && n % 2 == 1 {
+        points.truncate(n - 1);
+        points.push(Coord::new(mid, mid));
+    } else {
+        points.truncate(n);
+    }
+    return Some(points);
+}
+
+pub struct GtpEngine {
+    board: Board,
+    komi: f64,
+    num_colors: u8,
+    /// 对局中的着法 (不包括让子), 用于 undo
+    moves: Vec<(Stone, Option<Coord>)>,
+    bot: Box<dyn Bot<Board>>,
+}
+
+impl GtpEngine {
+    pub fn new(bot: Box<dyn Bot<Board>>) -> Self {
+        GtpEngine {
+            board: Board::new(DEFAULT_SIZE),
+            komi: DEFAULT_KOMI,
+            num_colors: 2,
+            moves: vec![],
+            bot,
+        }
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn komi(&self) -> f64 {
+        self.komi
+    }
+
+    pub fn num_colors(&self) -> u8 {
+        self.num_colors
+    }
+
+    /// 当前颜色数下合法的颜色
+    fn color(&self, s: &str) -> Result<Stone, &'static str> {
+        match parse_color(s) {
+            Some(stone) if stone.as_usize() <= self.num_colors as usize => Ok(stone),
+            _ => Err("invalid color"),
+        }
+    }
+
+    pub fn moves(&self) -> &[(Stone, Option<Coord>)] {
+        &self.moves
+    }
+
+    /// 执行一条命令, 返回响应的内容 (不含 `=`/`?` 与 id)
+    pub fn handle(&mut self, cmd: &GtpCommand) -> Result<String, &'static str> {
+        let args: Vec<&str> = cmd.args.iter().map(String::as_str).collect();
+        let size = self.board.size();
+        match (cmd.name.as_str(), args.as_slice()) {
+            ("protocol_version", _) => return Ok("2".to_string()),
+            ("name", _) => return Ok("rustgo".to_string()),
+            ("version", _) => return Ok(env!("CARGO_PKG_VERSION").to_string()),
+            ("known_command", [name]) => return Ok(COMMANDS.contains(name).to_string()),
+            ("list_commands", _) => return Ok(COMMANDS.join("\n")),
+            ("quit", _) => return Ok(String::new()),
+
+            ("boardsize", [n]) => {
+                let n: usize = n.parse().map_err(|_| "syntax error")?;
+                if !(2..=MAX_SIZE).contains(&n) {
+                    return Err("unacceptable size");
+                }
+                self.board = Board::new(n);
+                self.moves.clear();
+                return Ok(String::new());
+            }
+            ("clear_board", _) => {
+                self.board = Board::new(size);
+                self.moves.clear();
+                return Ok(String::new());
+            }
+            ("num_colors", [n]) => {
+                let n: u8 = n.parse().map_err(|_| "syntax error")?;
+                if !(2..=MAX_COLORS as u8).contains(&n) {
+                    return Err("unacceptable number of colors");
+                }
+                self.num_colors = n;
+                self.board = Board::new(size);
+                self.moves.clear();
+                return Ok(String::new());
+            }
+            ("komi", [komi]) => {
+                self.komi = komi.parse().map_err(|_| "syntax error")?;
+                return Ok(String::new());
+            }
+            ("time_settings", [main, byo_yomi, stones]) => {
+                main.parse::<u64>().map_err(|_| "syntax error")?;
+                byo_yomi.parse::<u64>().map_err(|_| "syntax error")?;
+                stones.parse::<u32>().map_err(|_| "syntax error")?;
+                return Ok(String::new());
+            }
+            ("time_left", [color, time, stones]) => {
+                self.color(color)?;
+                time.parse::<u64>().map_err(|_| "syntax error")?;
+                stones.parse::<u32>().map_err(|_| "syntax error")?;
+                return Ok(String::new());
+            }
+
+            ("play", [color, vertex]) => {
+                let stone = self.color(color)?;
+                let coord = parse_vertex(vertex, size)?;
+                if let Some(coord) = coord {
+                    self.board
+                        .place_stone(coord, stone)
+                        .map_err(|_| "illegal move")?;
+                }
+                self.moves.push((stone, coord));
+                return Ok(String::new());
+            }
+            ("genmove", [color]) => {
+                let stone = self.color(color)?;
+                let coord = self.bot.genmove(&mut self.board, stone, self.moves.len());
+                if let Some(coord) = coord {
+                    self.board
+                        .place_stone(coord, stone)
+                        .map_err(|_| "bot generated an illegal move")?;
+                }
+                self.moves.push((stone, coord));
+                return Ok(vertex_string(coord, size));
+            }
+            ("undo", _) => {
+                let Some((_, coord)) = self.moves.pop() else {
+                    return Err("cannot undo");
+                };
+                if coord.is_some() {
+                    self.board.undo();
+                }
+                return Ok(String::new());
+            }
+
+            ("fixed_handicap", [n]) => {
+                let n: usize = n.parse().map_err(|_| "syntax error")?;
+                if !self.moves.is_empty()
+                    || self.board.board_array().iter().any(|&s| s != Stone::VOID)
+                {
+                    return Err("board not empty");
+                }
+                let points = handicap_points(size, n).ok_or("invalid number of stones")?;
+                for &coord in &points {
+                    self.board.place_stone(coord, Stone::BLACK).unwrap();
+                }
+                let vertices: Vec<String> = points
+                    .into_iter()
+                    .map(|coord| vertex_string(Some(coord), size))
+                    .collect();
+                return Ok(vertices.join(" "));
+            }
+
+            ("final_score", _) => return Ok(self.final_score()),
+            ("final_status_list", [status]) => match *status {
+                "alive" => {
+                    let vertices: Vec<String> = (0..size * size)
+                        .map(|idx| self.board.coord(idx))
+                        .filter(|&coord| self.board.stone(coord) != Stone::VOID)
+                        .map(|coord| vertex_string(Some(coord), size))
+                        .collect();
+                    return Ok(vertices.join(" "));
+                }
+                "dead" | "seki" => return Ok(String::new()),
+                _ => return Err("syntax error"),
+            },
+            ("showboard", _) => return Ok(self.showboard()),
+
+            (name, _) if COMMANDS.contains(&name) => return Err("syntax error"),
+            _ => return Err("unknown command"),
+        }
+    }
+
+    /// 数子, 贴目加给黑以外的每一方
+    fn final_score(&self) -> String {
+        let max_stone = Stone::new(self.num_colors);
+        let area = score::area_score(&self.board, max_stone, &[]);
+        let mut scores: Vec<(Stone, f64)> = (1..=self.num_colors)
+            .map(|n| {
+                let stone = Stone::new(n);
+                let komi = if stone == Stone::BLACK {
+                    0.0
+                } else {
+                    self.komi
+                };
+                (stone, area[stone.as_usize()] as f64 + komi)
+            })
+            .collect();
+        // 稳定排序, 同分时颜色靠前的排在前面
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let (winner, first) = scores[0];
+        let diff = first - scores[1].1;
+        if diff == 0.0 {
+            return "0".to_string();
+        }
+        return format!("{}+{diff}", color_name(winner).unwrap());
+    }
+
+    /// 带坐标的文本棋盘, 第一行为空, 使响应从新的一行开始
+    fn showboard(&self) -> String {
+        let size = self.board.size();
+        let header: String = COLUMNS[..size]
+            .iter()
+            .map(|&c| format!(" {}", c as char))
+            .collect();
+        let mut s = format!("\n  {header}\n");
+        for y in 0..size {
+            let row = size - y;
+            s.push_str(&format!("{row:2}"));
+            for x in 0..size {
+                let ch = match self.board.stone(Coord::new(x, y)) {
+                    Stone::VOID => '.',
+                    Stone::BLACK => 'X',
+                    Stone::WHITE => 'O',
+                    stone => stone.as_char(),
+                };
+                s.push(' ');
+                s.push(ch);
+            }
+            s.push_str(&format!(" {row}\n"));
+        }
+        s.push_str(&format!("  {header}"));
+        return s;
+    }
+
+    /// 从 `input` 逐行读命令并把响应写到 `output`, 直到 `quit` 或输入结束
+    pub fn run(&mut self, input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let Some(cmd) = parse_command(&line?) else {
+                continue;
+            };
+            let res = self.handle(&cmd);
+            output.write_all(format_response(cmd.id, &res).as_bytes())?;
+            output.flush()?;
+            if cmd.name == "quit" {
+                break;
+            }
+        }
+        return Ok(());
+    }
+}
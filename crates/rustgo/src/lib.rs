@@ -1,4 +1,12 @@
 pub mod board;
+pub mod book;
+pub mod bot;
 pub mod common;
+pub mod game_tree;
+pub mod gtp;
+pub mod render;
+pub mod score;
+pub mod sgf;
+pub mod tsumego;
 
 pub use common::*;
@@ -0,0 +1,526 @@
+//! 把局面画成 SVG 图片, 开启 `png` feature 后还可以转成 PNG
+//!
+//! 只生成字符串, 不做任何 IO, 命令行与服务端都可以直接使用
+
+pub mod replay;
+
+use std::fmt::Write;
+
+use crate::{
+    Coord, Stone,
+    board::GoBoard,
+    game_tree::{GameTree, Markup, MarkupKind},
+};
+
+/// 一种颜色的棋子的画法
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoneColor {
+    pub fill: String,
+    pub stroke: String,
+    /// 棋子上的手数、标记的颜色
+    pub text: String,
+}
+
+impl StoneColor {
+    pub fn new(fill: &str, stroke: &str, text: &str) -> Self {
+        StoneColor {
+            fill: fill.to_string(),
+            stroke: stroke.to_string(),
+            text: text.to_string(),
+        }
+    }
+}
+
+/// 图片样式
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderStyle {
+    /// 格子边长 (像素)
+    pub cell: f64,
+    /// 是否在四周画坐标
+    pub coordinates: bool,
+    pub board_color: String,
+    pub line_color: String,
+    /// 被提子等需要突出显示的标记的颜色
+    pub highlight_color: String,
+    /// palette[i] 为第 i+1 色棋子的画法, 颜色比 palette 多时循环使用
+    pub palette: Vec<StoneColor>,
+}
+
+impl Default for RenderStyle {
+    fn default() -> Self {
+        RenderStyle {
+            cell: 32.0,
+            coordinates: true,
+            board_color: "#dcb35c".to_string(),
+            line_color: "#000000".to_string(),
+            highlight_color: "#e3120b".to_string(),
+            palette: vec![
+                StoneColor::new("#000000", "#000000", "#ffffff"),
+                StoneColor::new("#ffffff", "#000000", "#000000"),
+                StoneColor::new("#d62728", "#7a1516", "#ffffff"),
+                StoneColor::new("#1f77b4", "#10405f", "#ffffff"),
+                StoneColor::new("#2ca02c", "#185818", "#ffffff"),
+                StoneColor::new("#ff7f0e", "#8a4508", "#000000"),
+            ],
+        }
+    }
+}
+
+impl RenderStyle {
+    pub fn stone_color(&self, stone: Stone) -> &StoneColor {
+        debug_assert!(stone != Stone::VOID);
+        &self.palette[(stone.as_usize() - 1) % self.palette.len()]
+    }
+}
+
+/// 画在局面上的附加信息
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Annotations {
+    /// 最后一手的位置, 画一个圆圈 (如果该点有手数则只画手数)
+    pub last_move: Option<Coord>,
+    /// 棋子上的手数, 点上没有棋子时忽略
+    pub move_numbers: Vec<(Coord, usize)>,
+    /// ownership[idx] == (归属, 0~1 的把握), 按 `y * size + x` 排列
+    pub ownership: Option<Vec<(Stone, f32)>>,
+    pub markup: Vec<Markup>,
+    /// 刚被提走的棋子, 画成半透明的棋子加上叉
+    pub captured: Vec<(Coord, Stone)>,
+}
+
+/// 星位: 9 路以上四角的星位 (13 路以上在四线), 奇数路的天元, 15 路以上奇数路的边星
+pub fn star_points(size: usize) -> Vec<Coord> {
+    let mut lines = vec![];
+    if size >= 7 {
+        let d = if size >= 13 { 3 } else { 2 };
+        lines.push(d);
+        if size % 2 == 1 && size >= 15 {
+            lines.push(size / 2);
+        }
+        lines.push(size - 1 - d);
+    }
+
+    let mut points = vec![];
+    for &y in &lines {
+        for &x in &lines {
+            points.push(Coord::new(x, y));
+        }
+    }
+    if size % 2 == 1 && (5..15).contains(&size) {
+        points.push(Coord::new(size / 2, size / 2));
+    }
+    points.sort_by_key(|c| (c.y, c.x));
+    return points;
+}
+
+/// 列坐标: A-Z 跳过 I, 超出 25 列时用数字
+pub fn column_label(x: usize) -> String {
+    if x >= 25 {
+        return (x + 1).to_string();
+    }
+    let mut col = x as u8 + b'A';
+    if col >= b'I' {
+        col += 1;
+    }
+    (col as char).to_string()
+}
+
+/// 像素坐标的计算
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Layout {
+    pub size: usize,
+    pub cell: f64,
+    pub margin: f64,
+}
+
+impl Layout {
+    pub fn new(size: usize, style: &RenderStyle) -> Self {
+        // 取整, 保证图片尺寸是整数像素
+        let margin = if style.coordinates {
+            (style.cell * 1.2).round()
+        } else {
+            (style.cell * 0.6).round()
+        };
+        Layout {
+            size,
+            cell: style.cell,
+            margin,
+        }
+    }
+
+    /// 图片边长
+    pub fn width(&self) -> f64 {
+        self.margin * 2.0 + self.cell * (self.size - 1) as f64
+    }
+
+    pub fn center(&self, coord: Coord) -> (f64, f64) {
+        (
+            self.margin + self.cell * coord.x as f64,
+            self.margin + self.cell * coord.y as f64,
+        )
+    }
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => s.push_str("&amp;"),
+            '<' => s.push_str("&lt;"),
+            '>' => s.push_str("&gt;"),
+            '"' => s.push_str("&quot;"),
+            c => s.push(c),
+        }
+    }
+    return s;
+}
+
+pub(crate) fn svg_header(s: &mut String, width: f64) {
+    writeln!(
+        s,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{width}" viewBox="0 0 {width} {width}">"#
+    )
+    .unwrap();
+}
+
+/// 棋盘底色、线、星位与坐标
+pub(crate) fn write_grid(s: &mut String, layout: &Layout, style: &RenderStyle) {
+    let width = layout.width();
+    writeln!(
+        s,
+        r#"<rect width="{width}" height="{width}" fill="{}"/>"#,
+        style.board_color
+    )
+    .unwrap();
+
+    let start = layout.margin;
+    let end = layout.margin + layout.cell * (layout.size - 1) as f64;
+    writeln!(
+        s,
+        r#"<g stroke="{}" stroke-width="1" stroke-linecap="square">"#,
+        style.line_color
+    )
+    .unwrap();
+    for i in 0..layout.size {
+        let p = layout.margin + layout.cell * i as f64;
+        writeln!(s, r#"<line x1="{start}" y1="{p}" x2="{end}" y2="{p}"/>"#).unwrap();
+        writeln!(s, r#"<line x1="{p}" y1="{start}" x2="{p}" y2="{end}"/>"#).unwrap();
+    }
+    writeln!(s, "</g>").unwrap();
+
+    for coord in star_points(layout.size) {
+        let (cx, cy) = layout.center(coord);
+        writeln!(
+            s,
+            r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="{}"/>"#,
+            layout.cell * 0.1,
+            style.line_color
+        )
+        .unwrap();
+    }
+
+    if style.coordinates {
+        let font = layout.cell * 0.4;
+        writeln!(
+            s,
+            r#"<g font-family="sans-serif" font-size="{font}" text-anchor="middle" fill="{}">"#,
+            style.line_color
+        )
+        .unwrap();
+        let near = layout.margin - layout.cell * 0.75;
+        let far = layout.width() - near;
+        for i in 0..layout.size {
+            let p = layout.margin + layout.cell * i as f64;
+            let col = column_label(i);
+            let row = layout.size - i;
+            for y in [near, far] {
+                writeln!(s, r#"<text x="{p}" y="{y}" dy="0.35em">{col}</text>"#).unwrap();
+            }
+            for x in [near, far] {
+                writeln!(s, r#"<text x="{x}" y="{p}" dy="0.35em">{row}</text>"#).unwrap();
+            }
+        }
+        writeln!(s, "</g>").unwrap();
+    }
+}
+
+/// 一颗棋子, `attrs` 会原样加在 circle 上
+pub(crate) fn write_stone(
+    s: &mut String,
+    layout: &Layout,
+    style: &RenderStyle,
+    coord: Coord,
+    stone: Stone,
+    attrs: &str,
+) {
+    let (cx, cy) = layout.center(coord);
+    let color = style.stone_color(stone);
+    writeln!(
+        s,
+        r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="{}" stroke="{}" stroke-width="1"{attrs}/>"#,
+        layout.cell * 0.47,
+        color.fill,
+        color.stroke
+    )
+    .unwrap();
+}
+
+/// 被提走的棋子, `attrs` 会原样加在外层的 g 上
+pub(crate) fn write_captured(
+    s: &mut String,
+    layout: &Layout,
+    style: &RenderStyle,
+    coord: Coord,
+    stone: Stone,
+    attrs: &str,
+) {
+    let (cx, cy) = layout.center(coord);
+    let r = layout.cell * 0.3;
+    writeln!(s, "<g{attrs}>").unwrap();
+    write_stone(s, layout, style, coord, stone, r#" opacity="0.35""#);
+    writeln!(
+        s,
+        r#"<path d="M{} {}L{} {}M{} {}L{} {}" stroke="{}" stroke-width="3"/>"#,
+        cx - r,
+        cy - r,
+        cx + r,
+        cy + r,
+        cx - r,
+        cy + r,
+        cx + r,
+        cy - r,
+        style.highlight_color
+    )
+    .unwrap();
+    writeln!(s, "</g>").unwrap();
+}
+
+/// 点上的文字 (手数、标签)
+pub(crate) fn write_text(s: &mut String, layout: &Layout, coord: Coord, text: &str, fill: &str) {
+    let (cx, cy) = layout.center(coord);
+    // 三位数的手数缩小一点
+    let scale = if text.chars().count() >= 3 { 0.38 } else { 0.5 };
+    writeln!(
+        s,
+        r#"<text x="{cx}" y="{cy}" dy="0.35em" font-family="sans-serif" font-size="{}" text-anchor="middle" fill="{fill}">{}</text>"#,
+        layout.cell * scale,
+        escape(text)
+    )
+    .unwrap();
+}
+
+fn write_markup(s: &mut String, layout: &Layout, markup: &Markup, color: &str) {
+    let (cx, cy) = layout.center(markup.coord);
+    let r = layout.cell * 0.25;
+    let stroke = format!(r#"fill="none" stroke="{color}" stroke-width="2""#);
+    match &markup.kind {
+        MarkupKind::Circle => {
+            writeln!(s, r#"<circle cx="{cx}" cy="{cy}" r="{r}" {stroke}/>"#).unwrap();
+        }
+        MarkupKind::Square => {
+            writeln!(
+                s,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" {stroke}/>"#,
+                cx - r,
+                cy - r,
+                r * 2.0,
+                r * 2.0
+            )
+            .unwrap();
+        }
+        MarkupKind::Triangle => {
+            let h = r * 0.87;
+            writeln!(
+                s,
+                r#"<polygon points="{cx},{} {},{} {},{}" {stroke}/>"#,
+                cy - r,
+                cx - h,
+                cy + r * 0.5,
+                cx + h,
+                cy + r * 0.5
+            )
+            .unwrap();
+        }
+        MarkupKind::Cross => {
+            writeln!(
+                s,
+                r#"<path d="M{} {}L{} {}M{} {}L{} {}" {stroke}/>"#,
+                cx - r,
+                cy - r,
+                cx + r,
+                cy + r,
+                cx - r,
+                cy + r,
+                cx + r,
+                cy - r
+            )
+            .unwrap();
+        }
+        MarkupKind::Label(text) => {
+            write_text(s, layout, markup.coord, text, color);
+        }
+    }
+}
+
+/// 把局面画成 SVG
+pub fn board_svg<B: GoBoard>(board: &B, annotations: &Annotations, style: &RenderStyle) -> String {
+    let size = board.size();
+    let layout = Layout::new(size, style);
+    let mut s = String::new();
+    svg_header(&mut s, layout.width());
+    write_grid(&mut s, &layout, style);
+
+    let text_color = |coord: Coord| match board.stone(coord) {
+        Stone::VOID => style.line_color.as_str(),
+        stone => style.stone_color(stone).text.as_str(),
+    };
+
+    for y in 0..size {
+        for x in 0..size {
+            let coord = Coord::new(x, y);
+            let stone = board.stone(coord);
+            if stone != Stone::VOID {
+                write_stone(&mut s, &layout, style, coord, stone, "");
+            }
+        }
+    }
+
+    if let Some(ownership) = &annotations.ownership {
+        debug_assert!(ownership.len() == size * size);
+        for (idx, &(owner, strength)) in ownership.iter().enumerate() {
+            if owner == Stone::VOID || strength <= 0.0 {
+                continue;
+            }
+            let coord = Coord::new(idx % size, idx / size);
+            let (cx, cy) = layout.center(coord);
+            let half = layout.cell * 0.22;
+            writeln!(
+                s,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="{}"/>"#,
+                cx - half,
+                cy - half,
+                half * 2.0,
+                half * 2.0,
+                style.stone_color(owner).fill,
+                strength.min(1.0) * 0.8
+            )
+            .unwrap();
+        }
+    }
+
+    for &(coord, stone) in &annotations.captured {
+        if board.stone(coord) == Stone::VOID {
+            write_captured(&mut s, &layout, style, coord, stone, "");
+        }
+    }
+
+    let mut numbered = vec![];
+    for &(coord, number) in &annotations.move_numbers {
+        if board.stone(coord) != Stone::VOID {
+            write_text(
+                &mut s,
+                &layout,
+                coord,
+                &number.to_string(),
+                text_color(coord),
+            );
+            numbered.push(coord);
+        }
+    }
+
+    if let Some(coord) = annotations.last_move
+        && !numbered.contains(&coord)
+    {
+        let (cx, cy) = layout.center(coord);
+        writeln!(
+            s,
+            r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+            layout.cell * 0.25,
+            text_color(coord)
+        )
+        .unwrap();
+    }
+
+    for markup in &annotations.markup {
+        let color = text_color(markup.coord);
+        if let MarkupKind::Label(_) = markup.kind
+            && board.stone(markup.coord) == Stone::VOID
+        {
+            // 空点上的标签先盖住棋盘线
+            let (cx, cy) = layout.center(markup.coord);
+            writeln!(
+                s,
+                r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="{}"/>"#,
+                layout.cell * 0.3,
+                style.board_color
+            )
+            .unwrap();
+        }
+        write_markup(&mut s, &layout, markup, color);
+    }
+
+    s.push_str("</svg>\n");
+    return s;
+}
+
+/// 画出棋谱树光标处的局面, 包括最后一手、节点上的标记, 以及 (可选的) 手数
+pub fn tree_svg(tree: &GameTree, style: &RenderStyle, move_numbers: bool) -> String {
+    let mut annotations = Annotations {
+        markup: tree.node(tree.current()).markup.clone(),
+        ..Default::default()
+    };
+    if let Some((_, coord)) = tree.node(tree.current()).mv {
+        annotations.last_move = coord;
+    }
+    if move_numbers {
+        let mut number = 0;
+        for id in tree.path() {
+            let Some((_, coord)) = tree.node(id).mv else {
+                continue;
+            };
+            number += 1;
+            if let Some(coord) = coord {
+                // 同一个点上只保留最近的手数
+                annotations.move_numbers.retain(|&(c, _)| c != coord);
+                annotations.move_numbers.push((coord, number));
+            }
+        }
+    }
+    return board_svg(tree.board(), &annotations, style);
+}
+
+/// 用 resvg 把 SVG 转成 PNG
+///
+/// 坐标与手数需要系统字体, 没有可用字体时图片中不会有文字
+#[cfg(feature = "png")]
+pub fn svg_to_png(svg: &str) -> Result<Vec<u8>, &'static str> {
+    Rasterizer::new()
+        .render(svg)?
+        .encode_png()
+        .map_err(|_| "无法编码 PNG")
+}
+
+/// 加载字体比较慢, 需要画很多张图时复用同一个 `Rasterizer`
+#[cfg(feature = "png")]
+pub(crate) struct Rasterizer {
+    options: resvg::usvg::Options<'static>,
+}
+
+#[cfg(feature = "png")]
+impl Rasterizer {
+    pub fn new() -> Self {
+        let mut options = resvg::usvg::Options::default();
+        options.fontdb_mut().load_system_fonts();
+        Rasterizer { options }
+    }
+
+    pub fn render(&self, svg: &str) -> Result<resvg::tiny_skia::Pixmap, &'static str> {
+        use resvg::{tiny_skia, usvg};
+
+        let tree = usvg::Tree::from_str(svg, &self.options).map_err(|_| "无法解析 SVG")?;
+        let size = tree.size().to_int_size();
+        let mut pixmap =
+            tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("图片尺寸非法")?;
+        resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+        Ok(pixmap)
+    }
+}
@@ -0,0 +1,250 @@
+//! 整局棋的动画: 用 SMIL 动画的 SVG, 开启 `gif` feature 后还可以导出 GIF
+//!
+//! 每一手占一帧, 被提走的棋子会在提子的那一帧画成半透明加红叉
+
+use std::fmt::Write;
+
+use crate::{
+    Coord, Stone,
+    board::Board,
+    render::{self, Layout, RenderStyle},
+    sgf::{self, SgfMove, SgfNode},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayOptions {
+    pub style: RenderStyle,
+    /// 每一手的显示时间
+    pub frame_ms: u32,
+    /// 最后一帧的显示时间 (只用于 GIF, SVG 动画结束后停在终局)
+    pub end_ms: u32,
+}
+
+impl Default for ReplayOptions {
+    fn default() -> Self {
+        ReplayOptions {
+            style: RenderStyle::default(),
+            frame_ms: 500,
+            end_ms: 3000,
+        }
+    }
+}
+
+/// 一局棋的初始局面与着法
+#[derive(Clone)]
+pub struct Replay {
+    initial: Board,
+    moves: Vec<SgfMove>,
+}
+
+/// 复盘得到的一帧
+struct Frame {
+    board: Board,
+    last_move: Option<Coord>,
+    captured: Vec<(Coord, Stone)>,
+}
+
+/// SVG 中一颗棋子从出现到被提走的时间 (以帧为单位)
+struct StoneSpan {
+    coord: Coord,
+    stone: Stone,
+    appear: usize,
+    captured: Option<usize>,
+}
+
+impl Replay {
+    pub fn new(initial: Board, moves: Vec<SgfMove>) -> Self {
+        Replay { initial, moves }
+    }
+
+    /// 根节点的摆子作为初始局面, 之后沿主变化落子 (忽略中途的摆子)
+    pub fn from_sgf(root: &SgfNode) -> Self {
+        let mut initial = Board::new(root.size());
+        sgf::apply_setup(&mut initial, &root.setup(root.size()));
+        Replay::new(initial, root.main_line_moves())
+    }
+
+    pub fn moves(&self) -> &[SgfMove] {
+        &self.moves
+    }
+
+    /// 依次复盘, 第 0 帧为初始局面, 第 i 帧为第 i 手之后
+    fn frames(&self) -> Result<Vec<Frame>, &'static str> {
+        let mut board = self.initial.clone();
+        let mut frames = vec![Frame {
+            board: board.clone(),
+            last_move: None,
+            captured: vec![],
+        }];
+        for &(stone, coord) in &self.moves {
+            let mut captured = vec![];
+            if let Some(coord) = coord {
+                let before = board.clone();
+                for eaten in board.place_stone(coord, stone)? {
+                    captured.push((eaten, before.stone(eaten)));
+                }
+            }
+            frames.push(Frame {
+                board: board.clone(),
+                last_move: coord,
+                captured,
+            });
+        }
+        return Ok(frames);
+    }
+
+    fn spans(&self, frames: &[Frame]) -> Vec<StoneSpan> {
+        let size = self.initial.size();
+        // 每个点上当前棋子对应的 span
+        let mut current: Vec<Option<usize>> = vec![None; size * size];
+        let mut spans: Vec<StoneSpan> = vec![];
+        for (i, frame) in frames.iter().enumerate() {
+            for &(coord, _) in &frame.captured {
+                if let Some(span) = current[coord.y * size + coord.x].take() {
+                    spans[span].captured = Some(i);
+                }
+            }
+            for y in 0..size {
+                for x in 0..size {
+                    let coord = Coord::new(x, y);
+                    let stone = frame.board.stone(coord);
+                    let idx = y * size + x;
+                    if stone != Stone::VOID && current[idx].is_none() {
+                        current[idx] = Some(spans.len());
+                        spans.push(StoneSpan {
+                            coord,
+                            stone,
+                            appear: i,
+                            captured: None,
+                        });
+                    }
+                }
+            }
+        }
+        return spans;
+    }
+
+    /// 导出 SMIL 动画的 SVG
+    pub fn to_svg(&self, options: &ReplayOptions) -> Result<String, &'static str> {
+        let frames = self.frames()?;
+        let style = &options.style;
+        let layout = Layout::new(self.initial.size(), style);
+        let time = |frame: usize| frame as f64 * options.frame_ms as f64 / 1000.0;
+
+        let mut s = String::new();
+        render::svg_header(&mut s, layout.width());
+        render::write_grid(&mut s, &layout, style);
+
+        for span in self.spans(&frames) {
+            let appear = (span.appear > 0).then(|| time(span.appear));
+            let hide = span.captured.map(time);
+            write_timed(&mut s, appear, hide);
+            render::write_stone(&mut s, &layout, style, span.coord, span.stone, "");
+            s.push_str("</g>\n");
+        }
+
+        for (i, frame) in frames.iter().enumerate() {
+            // 提子的那一帧
+            for &(coord, stone) in &frame.captured {
+                write_timed(&mut s, Some(time(i)), Some(time(i + 1)));
+                render::write_captured(&mut s, &layout, style, coord, stone, "");
+                s.push_str("</g>\n");
+            }
+
+            // 最后一手的标记, 显示到下一手为止
+            if let Some(coord) = frame.last_move {
+                let next = (i + 1 < frames.len()).then(|| time(i + 1));
+                let (cx, cy) = layout.center(coord);
+                let color = &style.stone_color(frame.board.stone(coord)).text;
+                write_timed(&mut s, Some(time(i)), next);
+                writeln!(
+                    s,
+                    r#"<circle cx="{cx}" cy="{cy}" r="{}" fill="none" stroke="{color}" stroke-width="2"/>"#,
+                    layout.cell * 0.25,
+                )
+                .unwrap();
+                s.push_str("</g>\n");
+            }
+        }
+
+        s.push_str("</svg>\n");
+        return Ok(s);
+    }
+
+    /// 导出循环播放的 GIF
+    ///
+    /// 每一帧都用 `render::board_svg` 画出再栅格化, 与静态图片的样式完全一致
+    #[cfg(feature = "gif")]
+    pub fn to_gif(&self, options: &ReplayOptions) -> Result<Vec<u8>, &'static str> {
+        let frames = self.frames()?;
+        let rasterizer = render::Rasterizer::new();
+        let render_frame = |frame: &Frame| {
+            let annotations = render::Annotations {
+                last_move: frame.last_move,
+                captured: frame.captured.clone(),
+                ..Default::default()
+            };
+            let svg = render::board_svg(&frame.board, &annotations, &options.style);
+            rasterizer.render(&svg)
+        };
+
+        // 先画第一帧得到图片尺寸
+        let first = render_frame(&frames[0])?;
+        let width = u16::try_from(first.width()).map_err(|_| "图片太大")?;
+        let height = u16::try_from(first.height()).map_err(|_| "图片太大")?;
+
+        let mut bytes = vec![];
+        let mut encoder =
+            gif::Encoder::new(&mut bytes, width, height, &[]).map_err(|_| "无法编码 GIF")?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(|_| "无法编码 GIF")?;
+
+        let mut pixmap = Some(first);
+        for (i, frame) in frames.iter().enumerate() {
+            let pixmap = match pixmap.take() {
+                Some(pixmap) => pixmap,
+                None => render_frame(frame)?,
+            };
+            // 棋盘底色不透明, 所以预乘 alpha 的像素与普通 RGBA 相同
+            let mut rgba = pixmap.take();
+            let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut rgba, 10);
+            let ms = if i + 1 == frames.len() {
+                options.end_ms
+            } else {
+                options.frame_ms
+            };
+            gif_frame.delay = (ms / 10).min(u16::MAX as u32) as u16;
+            encoder
+                .write_frame(&gif_frame)
+                .map_err(|_| "无法编码 GIF")?;
+        }
+        drop(encoder);
+        return Ok(bytes);
+    }
+}
+
+/// 开一个 `<g>`, 在 `appear` 秒时显示, `hide` 秒时隐藏; `appear` 为 `None` 表示一开始就显示
+///
+/// 调用者负责写内容与 `</g>`
+fn write_timed(s: &mut String, appear: Option<f64>, hide: Option<f64>) {
+    match appear {
+        Some(t) => {
+            s.push_str(r#"<g visibility="hidden">"#);
+            write!(
+                s,
+                r#"<set attributeName="visibility" to="visible" begin="{t}s" fill="freeze"/>"#
+            )
+            .unwrap();
+        }
+        None => s.push_str("<g>"),
+    }
+    if let Some(t) = hide {
+        write!(
+            s,
+            r#"<set attributeName="visibility" to="hidden" begin="{t}s" fill="freeze"/>"#
+        )
+        .unwrap();
+    }
+    s.push('\n');
+}
@@ -0,0 +1,121 @@
+//! 数子法 (面积) 与数目法 (地域) 计分
+//!
+//! 不判断死子: 死子由调用者给出, 计分时当作已经提走
+
+use crate::{Coord, Stone, board::GoBoard};
+
+/// 每个点的归属, 以 `y * size + x` 为下标
+///
+/// 活棋归棋子的颜色; 空点与死子所在的点归包围它的唯一颜色, 与多种颜色 (或没有棋子) 相邻时为 `Stone::VOID`
+pub fn ownership<B: GoBoard>(board: &B, dead: &[Coord]) -> Vec<Stone> {
+    let size = board.size();
+    let alive = |coord: Coord| {
+        if dead.contains(&coord) {
+            Stone::VOID
+        } else {
+            board.stone(coord)
+        }
+    };
+    let mut owner = vec![Stone::VOID; size * size];
+    let mut visited = vec![false; size * size];
+
+    for y in 0..size {
+        for x in 0..size {
+            let stone = alive(Coord::new(x, y));
+            if stone != Stone::VOID {
+                owner[y * size + x] = stone;
+                continue;
+            }
+            if visited[y * size + x] {
+                continue;
+            }
+
+            // 找出整块空地以及与它相邻的颜色
+            let mut region = vec![];
+            let mut region_owner: Option<Stone> = None;
+            let mut shared = false;
+            let mut stack = vec![Coord::new(x, y)];
+            visited[y * size + x] = true;
+            while let Some(coord) = stack.pop() {
+                region.push(coord);
+                for n in neighbors(coord, size) {
+                    let stone = alive(n);
+                    if stone == Stone::VOID {
+                        if !visited[n.y * size + n.x] {
+                            visited[n.y * size + n.x] = true;
+                            stack.push(n);
+                        }
+                    } else if region_owner.is_none() {
+                        region_owner = Some(stone);
+                    } else if region_owner != Some(stone) {
+                        shared = true;
+                    }
+                }
+            }
+
+            if let Some(region_owner) = region_owner
+                && !shared
+            {
+                for coord in region {
+                    owner[coord.y * size + coord.x] = region_owner;
+                }
+            }
+        }
+    }
+    return owner;
+}
+
+/// 数子法: 每种颜色的活棋加上它围住的空点 (包括死子所在的点)
+///
+/// 返回值以 `Stone::as_usize` 为下标, 长度为 `max_stone + 1`
+pub fn area_score<B: GoBoard>(board: &B, max_stone: Stone, dead: &[Coord]) -> Vec<usize> {
+    let mut score = vec![0; max_stone.as_usize() + 1];
+    for stone in ownership(board, dead) {
+        if stone != Stone::VOID && stone.as_usize() < score.len() {
+            score[stone.as_usize()] += 1;
+        }
+    }
+    return score;
+}
+
+/// 数目法: 每种颜色围住的空点 (包括死子所在的点), 加上提走的子与围住的对方死子
+///
+/// 返回值以 `Stone::as_usize` 为下标, 长度为 `max_stone + 1`
+pub fn territory_score<B: GoBoard>(board: &B, max_stone: Stone, dead: &[Coord]) -> Vec<usize> {
+    let size = board.size();
+    let mut score: Vec<usize> = (0..=max_stone.as_usize())
+        .map(|n| match n {
+            0 => 0,
+            n => board.captures(Stone::new(n as u8)),
+        })
+        .collect();
+    for (idx, owner) in ownership(board, dead).into_iter().enumerate() {
+        if owner == Stone::VOID || owner.as_usize() >= score.len() {
+            continue;
+        }
+        let coord = Coord::new(idx % size, idx / size);
+        let stone = board.stone(coord);
+        if stone == owner {
+            // 自己的活棋
+            continue;
+        }
+        score[owner.as_usize()] += 1;
+        if stone != Stone::VOID {
+            // 死子算作俘虏
+            score[owner.as_usize()] += 1;
+        }
+    }
+    return score;
+}
+
+fn neighbors(coord: Coord, size: usize) -> impl Iterator<Item = Coord> {
+    let (x, y) = (coord.x, coord.y);
+    [
+        (x > 0).then(|| Coord::new(x - 1, y)),
+        (x + 1 < size).then(|| Coord::new(x + 1, y)),
+        (y > 0).then(|| Coord::new(x, y - 1)),
+        (y + 1 < size).then(|| Coord::new(x, y + 1)),
+    ]
+    .into_iter()
+    .flatten()
+}
@@ -0,0 +1,633 @@
+//! SGF (FF[4]) 棋谱的读写
+//!
+//! 解析结果是通用的 `SgfNode` 树, 保留全部属性 (包括不认识的属性), 所以写回去不会丢失信息;
+//! 常用的属性 (SZ, KM, RU, HA, PB/PW, AB/AW/AE, B/W, C) 有对应的读写方法。
+//!
+//! # 多色扩展
+//!
+//! 标准 SGF 只有黑白两色, 这里为 `Stone::new(3)` 到 `Stone::new(6)` 定义了私有属性。
+//! 每种颜色有一个颜色标识, 黑白沿用 `B`/`W`, 第 n 色 (n >= 3) 为 `X` 加上第 n 个大写字母:
+//!
+//! | 颜色 | 标识 | 落子 | 摆子 | 棋手 | 队伍 |
+//! |------|------|------|------|------|------|
+//! | 1    | B    | B    | AB   | PB   | BT   |
+//! | 2    | W    | W    | AW   | PW   | WT   |
+//! | 3    | XC   | XC   | AXC  | PXC  | XCT  |
+//! | 4    | XD   | XD   | AXD  | PXD  | XDT  |
+//! | 5    | XE   | XE   | AXE  | PXE  | XET  |
+//! | 6    | XF   | XF   | AXF  | PXF  | XFT  |
+//!
+//! 根节点的 `NC[n]` 记录颜色数, 只在 n > 2 时写出, 所以两色的棋谱就是普通的 B/W 棋谱。
+//! 读取时没有 `NC` 则按两色处理。
+
+use std::fmt::Display;
+
+use crate::{Coord, Stone, board::Board};
+
+/// 多色扩展支持的最多颜色数
+pub const MAX_COLORS: usize = 6;
+
+/// 颜色标识, 见模块文档
+pub fn color_ident(stone: Stone) -> Option<String> {
+    match stone.as_usize() {
+        1 => Some("B".to_string()),
+        2 => Some("W".to_string()),
+        n @ 3..=MAX_COLORS => Some(format!("X{}", (b'A' + n as u8 - 1) as char)),
+        _ => None,
+    }
+}
+
+/// 由颜色标识得到颜色, 即 `color_ident` 的逆
+pub fn ident_color(ident: &str) -> Option<Stone> {
+    match ident.as_bytes() {
+        b"B" => Some(Stone::BLACK),
+        b"W" => Some(Stone::WHITE),
+        [b'X', c] => {
+            let n = (*c as usize).checked_sub(b'A' as usize)? + 1;
+            if (3..=MAX_COLORS).contains(&n) {
+                Some(Stone::new(n as u8))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+fn all_colors() -> impl Iterator<Item = (Stone, String)> {
+    (1..=MAX_COLORS as u8).map(|n| {
+        let stone = Stone::new(n);
+        (stone, color_ident(stone).unwrap())
+    })
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SgfError {
+    /// 出错位置 (字节下标)
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl Display for SgfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "sgf error at {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for SgfError {}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SgfProperty {
+    pub ident: String,
+    /// 已经去掉转义的属性值
+    pub values: Vec<String>,
+}
+
+/// 棋谱树的一个节点, `children[0]` 为主变化
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SgfNode {
+    pub properties: Vec<SgfProperty>,
+    pub children: Vec<SgfNode>,
+}
+
+/// 根节点上的对局信息
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GameInfo {
+    pub size: usize,
+    /// 颜色数 (NC), 普通棋谱为 2
+    pub num_colors: usize,
+    pub komi: Option<f64>,
+    pub rules: Option<String>,
+    pub handicap: Option<usize>,
+    /// players[i] 为第 i+1 色的棋手 (PB/PW/PXC...)
+    pub players: Vec<Option<String>>,
+    /// teams[i] 为第 i+1 色的队伍 (BT/WT/XCT...)
+    pub teams: Vec<Option<String>>,
+    pub result: Option<String>,
+}
+
+impl GameInfo {
+    pub fn new(size: usize, num_colors: usize) -> Self {
+        assert!((2..=MAX_COLORS).contains(&num_colors));
+        GameInfo {
+            size,
+            num_colors,
+            komi: None,
+            rules: None,
+            handicap: None,
+            players: vec![None; num_colors],
+            teams: vec![None; num_colors],
+            result: None,
+        }
+    }
+
+    pub fn player(&self, stone: Stone) -> Option<&str> {
+        self.players
+            .get(stone.as_usize().checked_sub(1)?)?
+            .as_deref()
+    }
+
+    pub fn team(&self, stone: Stone) -> Option<&str> {
+        self.teams.get(stone.as_usize().checked_sub(1)?)?.as_deref()
+    }
+}
+
+/// 一步棋: 颜色与坐标, 坐标为 `None` 表示 pass
+pub type SgfMove = (Stone, Option<Coord>);
+
+impl SgfNode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 第一个属性值
+    pub fn get(&self, ident: &str) -> Option<&str> {
+        self.get_all(ident)?.first().map(|s| s.as_str())
+    }
+
+    pub fn get_all(&self, ident: &str) -> Option<&[String]> {
+        self.properties
+            .iter()
+            .find(|p| p.ident == ident)
+            .map(|p| p.values.as_slice())
+    }
+
+    /// 设置属性 (覆盖已有的同名属性)
+    pub fn set(&mut self, ident: &str, values: Vec<String>) {
+        if let Some(p) = self.properties.iter_mut().find(|p| p.ident == ident) {
+            p.values = values;
+        } else {
+            self.properties.push(SgfProperty {
+                ident: ident.to_string(),
+                values,
+            });
+        }
+    }
+
+    pub fn remove(&mut self, ident: &str) {
+        self.properties.retain(|p| p.ident != ident);
+    }
+
+    /// 节点上的落子 (B, W 或多色扩展的 XC...XF)
+    pub fn sgf_move(&self, size: usize) -> Option<SgfMove> {
+        for property in &self.properties {
+            if let Some(stone) = ident_color(&property.ident) {
+                let value = property.values.first().map_or("", |v| v.as_str());
+                return Some((stone, parse_point(value, size)));
+            }
+        }
+        return None;
+    }
+
+    pub fn set_move(&mut self, stone: Stone, coord: Option<Coord>) {
+        let ident = color_ident(stone).expect("unsupported stone color");
+        self.properties.retain(|p| ident_color(&p.ident).is_none());
+        let value = match coord {
+            Some(coord) => point_string(coord),
+            None => String::new(),
+        };
+        self.set(&ident, vec![value]);
+    }
+
+    /// 节点上的摆子 (AB/AW/AXC.../AE), 其中 AE 的颜色为 `Stone::VOID`
+    pub fn setup(&self, size: usize) -> Vec<(Stone, Coord)> {
+        let mut setup = vec![];
+        let idents = all_colors()
+            .map(|(stone, ident)| (stone, format!("A{ident}")))
+            .chain([(Stone::VOID, "AE".to_string())]);
+        for (stone, ident) in idents {
+            for value in self.get_all(&ident).unwrap_or_default() {
+                for coord in parse_point_list(value, size) {
+                    setup.push((stone, coord));
+                }
+            }
+        }
+        return setup;
+    }
+
+    pub fn add_setup(&mut self, stone: Stone, coord: Coord) {
+        let ident = match color_ident(stone) {
+            Some(ident) => format!("A{ident}"),
+            None => "AE".to_string(),
+        };
+        let value = point_string(coord);
+        if let Some(p) = self.properties.iter_mut().find(|p| p.ident == ident) {
+            p.values.push(value);
+        } else {
+            self.set(&ident, vec![value]);
+        }
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.get("C")
+    }
+
+    pub fn set_comment(&mut self, comment: &str) {
+        self.set("C", vec![comment.to_string()]);
+    }
+
+    /// 读取根节点上的对局信息, 没有 SZ 时按 19 路处理, 没有 NC 时按两色处理
+    pub fn game_info(&self) -> GameInfo {
+        let mut info = GameInfo::new(self.size(), self.num_colors());
+        info.komi = self.get("KM").and_then(|s| s.trim().parse().ok());
+        info.rules = self.get("RU").map(|s| s.to_string());
+        info.handicap = self.get("HA").and_then(|s| s.trim().parse().ok());
+        info.result = self.get("RE").map(|s| s.to_string());
+        for (stone, ident) in all_colors().take(info.num_colors) {
+            let i = stone.as_usize() - 1;
+            info.players[i] = self.get(&format!("P{ident}")).map(|s| s.to_string());
+            info.teams[i] = self.get(&format!("{ident}T")).map(|s| s.to_string());
+        }
+        return info;
+    }
+
+    /// 把对局信息写入根节点, 两色时不写 NC 及多色扩展的属性
+    pub fn set_game_info(&mut self, info: &GameInfo) {
+        self.set("GM", vec!["1".to_string()]);
+        self.set("FF", vec!["4".to_string()]);
+        self.set("SZ", vec![info.size.to_string()]);
+        let mut optional = vec![
+            (
+                "NC".to_string(),
+                (info.num_colors > 2).then(|| info.num_colors.to_string()),
+            ),
+            ("KM".to_string(), info.komi.map(|k| k.to_string())),
+            ("RU".to_string(), info.rules.clone()),
+            ("HA".to_string(), info.handicap.map(|h| h.to_string())),
+        ];
+        for (stone, ident) in all_colors() {
+            let i = stone.as_usize() - 1;
+            let player = info.players.get(i).cloned().flatten();
+            let team = info.teams.get(i).cloned().flatten();
+            optional.push((format!("P{ident}"), player));
+            optional.push((format!("{ident}T"), team));
+        }
+        optional.push(("RE".to_string(), info.result.clone()));
+        for (ident, value) in optional {
+            match value {
+                Some(value) => self.set(&ident, vec![value]),
+                None => self.remove(&ident),
+            }
+        }
+    }
+
+    /// 颜色数 (NC), 没有时为 2
+    pub fn num_colors(&self) -> usize {
+        self.get("NC")
+            .and_then(|s| s.trim().parse().ok())
+            .filter(|n| (2..=MAX_COLORS).contains(n))
+            .unwrap_or(2)
+    }
+
+    /// 棋盘大小 (只支持正方形棋盘), 没有 SZ 时为 19
+    pub fn size(&self) -> usize {
+        self.get("SZ")
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(19)
+    }
+
+    /// 从该节点开始, 每次取第一个子节点得到的主变化 (包括该节点)
+    pub fn main_line(&self) -> Vec<&SgfNode> {
+        let mut line = vec![self];
+        let mut node = self;
+        while let Some(child) = node.children.first() {
+            line.push(child);
+            node = child;
+        }
+        return line;
+    }
+
+    /// 主变化上依次的落子
+    pub fn main_line_moves(&self) -> Vec<SgfMove> {
+        let size = self.size();
+        self.main_line()
+            .into_iter()
+            .filter_map(|node| node.sgf_move(size))
+            .collect()
+    }
+}
+
+/// 解析 SGF 文件中的所有棋谱 (collection)
+pub fn parse(text: &str) -> Result<Vec<SgfNode>, SgfError> {
+    let mut parser = Parser {
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    let mut games = vec![];
+    parser.skip_whitespace();
+    while parser.pos < parser.bytes.len() {
+        // 允许 "(;" 之前有其他内容, 比如邮件头
+        if parser.peek() != Some(b'(') {
+            parser.pos += 1;
+            continue;
+        }
+        games.push(parser.game_tree()?);
+        parser.skip_whitespace();
+    }
+    if games.is_empty() {
+        return Err(parser.error("no game tree"));
+    }
+    return Ok(games);
+}
+
+/// 解析第一局棋谱, 返回根节点
+pub fn parse_game(text: &str) -> Result<SgfNode, SgfError> {
+    Ok(parse(text)?.swap_remove(0))
+}
+
+/// 写出棋谱, 主变化上每个节点一行
+pub fn to_sgf(root: &SgfNode) -> String {
+    let mut s = String::new();
+    write_tree(&mut s, root);
+    s.push('\n');
+    return s;
+}
+
+fn write_tree(s: &mut String, node: &SgfNode) {
+    s.push('(');
+    let mut node = node;
+    loop {
+        write_node(s, node);
+        match node.children.len() {
+            0 => break,
+            1 => {
+                s.push('\n');
+                node = &node.children[0];
+            }
+            _ => {
+                for child in &node.children {
+                    s.push('\n');
+                    write_tree(s, child);
+                }
+                break;
+            }
+        }
+    }
+    s.push(')');
+}
+
+fn write_node(s: &mut String, node: &SgfNode) {
+    s.push(';');
+    for property in &node.properties {
+        s.push_str(&property.ident);
+        for value in &property.values {
+            s.push('[');
+            for c in value.chars() {
+                if c == ']' || c == '\\' {
+                    s.push('\\');
+                }
+                s.push(c);
+            }
+            s.push(']');
+        }
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> SgfError {
+        SgfError {
+            position: self.pos,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8, message: &'static str) -> Result<(), SgfError> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.error(message));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// GameTree = "(" Sequence { GameTree } ")"
+    fn game_tree(&mut self) -> Result<SgfNode, SgfError> {
+        self.expect(b'(', "expect '('")?;
+
+        // 先读出 sequence, 再把它串成一条链
+        let mut sequence = vec![];
+        self.skip_whitespace();
+        while self.peek() == Some(b';') {
+            sequence.push(self.node()?);
+            self.skip_whitespace();
+        }
+        if sequence.is_empty() {
+            return Err(self.error("empty sequence"));
+        }
+
+        let mut variations = vec![];
+        while self.peek() == Some(b'(') {
+            variations.push(self.game_tree()?);
+            self.skip_whitespace();
+        }
+        self.expect(b')', "expect ')'")?;
+
+        let mut node = sequence.pop().unwrap();
+        node.children = variations;
+        while let Some(mut parent) = sequence.pop() {
+            parent.children = vec![node];
+            node = parent;
+        }
+        return Ok(node);
+    }
+
+    /// Node = ";" { Property }
+    fn node(&mut self) -> Result<SgfNode, SgfError> {
+        self.expect(b';', "expect ';'")?;
+        let mut node = SgfNode::new();
+        loop {
+            self.skip_whitespace();
+            let start = self.pos;
+            // FF[3] 允许属性名中有小写字母, 只保留大写部分
+            let mut ident = String::new();
+            while let Some(c) = self.peek() {
+                if c.is_ascii_uppercase() {
+                    ident.push(c as char);
+                } else if !c.is_ascii_lowercase() {
+                    break;
+                }
+                self.pos += 1;
+            }
+            if self.pos == start {
+                break;
+            }
+            if ident.is_empty() {
+                return Err(self.error("bad property identifier"));
+            }
+
+            let mut values = vec![];
+            self.skip_whitespace();
+            while self.peek() == Some(b'[') {
+                values.push(self.value()?);
+                self.skip_whitespace();
+            }
+            if values.is_empty() {
+                return Err(self.error("property without value"));
+            }
+
+            match node.properties.iter_mut().find(|p| p.ident == ident) {
+                // 重复的属性合并
+                Some(p) => p.values.append(&mut values),
+                None => node.properties.push(SgfProperty { ident, values }),
+            }
+        }
+        return Ok(node);
+    }
+
+    /// "[" ... "]", 处理转义与 soft line break
+    fn value(&mut self) -> Result<String, SgfError> {
+        self.pos += 1; // '['
+        let mut value: Vec<u8> = vec![];
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated value")),
+                Some(b']') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        None => return Err(self.error("unterminated value")),
+                        // soft line break
+                        Some(b'\n') => {
+                            self.pos += 1;
+                            if self.peek() == Some(b'\r') {
+                                self.pos += 1;
+                            }
+                        }
+                        Some(b'\r') => {
+                            self.pos += 1;
+                            if self.peek() == Some(b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        Some(c) => {
+                            value.push(c);
+                            self.pos += 1;
+                        }
+                    }
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        String::from_utf8(value).map_err(|_| self.error("value is not utf-8"))
+    }
+}
+
+/// SGF 坐标 "aa" 为左上角, 与 `Coord` 一致; 空值以及 19 路以内的 "tt" 为 pass
+pub fn parse_point(value: &str, size: usize) -> Option<Coord> {
+    let v = value.trim().as_bytes();
+    if v.len() != 2 || (size <= 19 && v == b"tt") {
+        return None;
+    }
+    let x = point_index(v[0])?;
+    let y = point_index(v[1])?;
+    if x >= size || y >= size {
+        return None;
+    }
+    return Some(Coord::new(x, y));
+}
+
+/// a-z 为 0-25, A-Z 为 26-51
+fn point_index(c: u8) -> Option<usize> {
+    match c {
+        b'a'..=b'z' => Some((c - b'a') as usize),
+        b'A'..=b'Z' => Some((c - b'A') as usize + 26),
+        _ => None,
+    }
+}
+
+fn point_char(i: usize) -> char {
+    if i < 26 {
+        (b'a' + i as u8) as char
+    } else {
+        (b'A' + (i - 26) as u8) as char
+    }
+}
+
+pub fn point_string(coord: Coord) -> String {
+    let mut s = String::with_capacity(2);
+    s.push(point_char(coord.x));
+    s.push(point_char(coord.y));
+    s
+}
+
+/// 点或者压缩的矩形 "aa:cc"
+pub fn parse_point_list(value: &str, size: usize) -> Vec<Coord> {
+    match value.split_once(':') {
+        Some((a, b)) => {
+            let (Some(a), Some(b)) = (parse_point(a, size), parse_point(b, size)) else {
+                return vec![];
+            };
+            let mut coords = vec![];
+            for y in a.y.min(b.y)..=a.y.max(b.y) {
+                for x in a.x.min(b.x)..=a.x.max(b.x) {
+                    coords.push(Coord::new(x, y));
+                }
+            }
+            coords
+        }
+        None => parse_point(value, size).into_iter().collect(),
+    }
+}
+
+/// 沿主变化复盘, 返回最终局面
+///
+/// 遇到摆子 (AB/AW/AE) 时会用摆子后的棋盘重新创建 `Board`, 所以之前的全局同形历史不再保留
+pub fn replay_main_line(root: &SgfNode) -> Result<Board, &'static str> {
+    let size = root.size();
+    let mut board = Board::new(size);
+    for node in root.main_line() {
+        apply_node(&mut board, node)?;
+    }
+    return Ok(board);
+}
+
+/// 把一个节点的摆子和落子应用到棋盘上
+pub fn apply_node(board: &mut Board, node: &SgfNode) -> Result<(), &'static str> {
+    let size = board.size();
+    apply_setup(board, &node.setup(size));
+    if let Some((stone, Some(coord))) = node.sgf_move(size) {
+        board.place_stone(coord, stone)?;
+    }
+    Ok(())
+}
+
+/// 摆子, 没有摆子时什么都不做
+///
+/// `Board` 不支持直接修改棋子, 所以用摆子后的棋盘重新创建, 之前的历史与悔棋记录都会丢失
+pub fn apply_setup(board: &mut Board, setup: &[(Stone, Coord)]) {
+    if setup.is_empty() {
+        return;
+    }
+    let mut b_array = board.board_array().to_vec();
+    for &(stone, coord) in setup {
+        b_array[board.idx(coord)] = stone;
+    }
+    *board = Board::new_with_board(board.size(), b_array.into_boxed_slice());
+}
+
+impl Display for SgfNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&to_sgf(self))
+    }
+}
@@ -0,0 +1,594 @@
+//! 死活题求解器 (df-pn, depth-first proof-number search)
+//!
+//! 只在题目标记的区域 `region` 内落子 (以及 pass), 判定规则:
+//! - 进攻方提走任意一颗目标棋子 (`targets`), 则进攻方胜
+//! - 目标棋子按 Benson 算法无条件活, 或双方连续 pass, 则防守方胜 (双活也算活)
+//!
+//! 置换表以 `Board::hash` 为键, 搜索时用 `Board::undo` 回退, 不复制棋盘。
+//! 置换表不区分到达局面的路径, 所以和劫争相关的结论 (GHI) 不保证严格正确。
+
+use std::collections::HashMap;
+
+use crate::{Coord, Stone, board::Board};
+
+const INF: u32 = u32::MAX / 2;
+
+const DEFAULT_MAX_NODES: usize = 1_000_000;
+
+/// 一步棋, `None` 表示 pass
+pub type TsumegoMove = Option<Coord>;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Goal {
+    /// 先手方杀死目标棋子
+    Kill,
+    /// 先手方做活目标棋子
+    Live,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TsumegoStatus {
+    /// 先手方能达成目标
+    Solved,
+    /// 先手方无法达成目标
+    Failed,
+    /// 超出节点上限, 未能得出结论
+    Unknown,
+}
+
+#[derive(Clone, Debug)]
+pub struct Refutation {
+    /// 先手方的失败尝试
+    pub try_move: TsumegoMove,
+    /// 后手方成功应对的变化, 从后手方对 `try_move` 的应手开始 (不含 `try_move`)
+    pub line: Vec<TsumegoMove>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TsumegoSolution {
+    pub status: TsumegoStatus,
+    /// 正解的主变化 (先手方第一手开始); 先手方失败时为空
+    pub main_line: Vec<TsumegoMove>,
+    /// 先手方其他第一手的失败变化
+    pub refutations: Vec<Refutation>,
+    /// 搜索过的节点数
+    pub nodes: usize,
+}
+
+pub struct Tsumego {
+    board: Board,
+    region: Vec<Coord>,
+    targets: Vec<Coord>,
+    to_play: Stone,
+    goal: Goal,
+    max_nodes: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    pn: u32,
+    dn: u32,
+}
+
+/// 搜索时的局面, 除了棋盘以外还需要轮到谁、连续 pass 了几次
+#[derive(Clone, Copy)]
+struct Node {
+    /// 是否轮到先手方 (OR 节点)
+    or_node: bool,
+    passes: u8,
+}
+
+struct Search<'a> {
+    problem: &'a Tsumego,
+    board: Board,
+    table: HashMap<u64, Entry>,
+    /// 当前搜索路径上的局面, 用于检测循环
+    path: Vec<u64>,
+    nodes: usize,
+}
+
+impl Tsumego {
+    /// `targets` 为防守方的棋子 (被杀/要做活的棋), 必须都在棋盘上
+    ///
+    /// `Goal::Kill` 时 `to_play` 是进攻方, `Goal::Live` 时 `to_play` 是防守方
+    pub fn new(
+        board: Board,
+        region: Vec<Coord>,
+        targets: Vec<Coord>,
+        to_play: Stone,
+        goal: Goal,
+    ) -> Result<Self, &'static str> {
+        if to_play != Stone::BLACK && to_play != Stone::WHITE {
+            return Err("死活题只支持黑白两色");
+        }
+        if region.is_empty() {
+            return Err("求解区域为空");
+        }
+        if targets.is_empty() {
+            return Err("没有目标棋子");
+        }
+        for coord in region.iter().chain(targets.iter()) {
+            if coord.x >= board.size() || coord.y >= board.size() {
+                return Err("非法坐标");
+            }
+        }
+
+        let problem = Tsumego {
+            board,
+            region,
+            targets,
+            to_play,
+            goal,
+            max_nodes: DEFAULT_MAX_NODES,
+        };
+        let defender = problem.defender();
+        if problem
+            .targets
+            .iter()
+            .any(|&coord| problem.board.stone(coord) != defender)
+        {
+            return Err("目标棋子必须都是防守方的棋子");
+        }
+        return Ok(problem);
+    }
+
+    /// 设置搜索的节点上限, 超出则返回 `TsumegoStatus::Unknown`
+    pub fn with_max_nodes(mut self, max_nodes: usize) -> Self {
+        self.max_nodes = max_nodes;
+        self
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn to_play(&self) -> Stone {
+        self.to_play
+    }
+
+    pub fn goal(&self) -> Goal {
+        self.goal
+    }
+
+    fn attacker(&self) -> Stone {
+        match self.goal {
+            Goal::Kill => self.to_play,
+            Goal::Live => self.to_play.next_stone(2),
+        }
+    }
+
+    fn defender(&self) -> Stone {
+        self.attacker().next_stone(2)
+    }
+
+    pub fn solve(&self) -> TsumegoSolution {
+        let mut search = Search {
+            problem: self,
+            board: self.board.clone(),
+            table: HashMap::new(),
+            path: vec![],
+            nodes: 0,
+        };
+        let root = Node {
+            or_node: true,
+            passes: 0,
+        };
+
+        let entry = search.mid(root, INF, INF);
+        let status = if entry.pn == 0 {
+            TsumegoStatus::Solved
+        } else if entry.dn == 0 {
+            TsumegoStatus::Failed
+        } else {
+            TsumegoStatus::Unknown
+        };
+
+        let mut solution = TsumegoSolution {
+            status,
+            main_line: vec![],
+            refutations: vec![],
+            nodes: 0,
+        };
+        if status == TsumegoStatus::Solved {
+            solution.main_line = search.extract_line(root);
+        }
+
+        // 对先手方的每个其他第一手, 给出后手方的应对
+        if status != TsumegoStatus::Unknown {
+            let main_move = solution.main_line.first().copied();
+            for mv in search.legal_moves(root) {
+                if Some(mv) == main_move {
+                    continue;
+                }
+                search.play(root, mv);
+                let child = root.after(mv);
+                let entry = search.mid(child, INF, INF);
+                if entry.dn == 0 {
+                    let line = search.extract_line(child);
+                    solution.refutations.push(Refutation { try_move: mv, line });
+                }
+                search.unplay(mv);
+            }
+        }
+
+        solution.nodes = search.nodes;
+        return solution;
+    }
+}
+
+impl Node {
+    fn after(self, mv: TsumegoMove) -> Node {
+        Node {
+            or_node: !self.or_node,
+            passes: if mv.is_none() { self.passes + 1 } else { 0 },
+        }
+    }
+}
+
+impl Search<'_> {
+    fn stone_to_move(&self, node: Node) -> Stone {
+        if node.or_node {
+            self.problem.to_play
+        } else {
+            self.problem.to_play.next_stone(2)
+        }
+    }
+
+    fn key(&self, node: Node) -> u64 {
+        let mut key = self.board.hash();
+        if node.or_node {
+            key ^= 0x2545_F491_4F6C_DD1D;
+        }
+        if node.passes > 0 {
+            key ^= 0x9E37_79B9_7F4A_7C15;
+        }
+        return key;
+    }
+
+    fn aborted(&self) -> bool {
+        self.nodes >= self.problem.max_nodes
+    }
+
+    /// 终局判定, 返回先手方是否获胜
+    fn terminal(&self, node: Node) -> Option<bool> {
+        let problem = self.problem;
+        let defender = problem.defender();
+
+        let attacker_win = if problem
+            .targets
+            .iter()
+            .any(|&coord| self.board.stone(coord) != defender)
+        {
+            Some(true)
+        } else if node.passes >= 2 {
+            Some(false)
+        } else {
+            let alive = unconditionally_alive(&self.board, defender);
+            if problem
+                .targets
+                .iter()
+                .all(|&coord| alive[self.board.idx(coord)])
+            {
+                Some(false)
+            } else {
+                None
+            }
+        };
+
+        attacker_win.map(|attacker_win| attacker_win == (problem.goal == Goal::Kill))
+    }
+
+    fn terminal_entry(&self, node: Node) -> Option<Entry> {
+        match self.terminal(node)? {
+            true => Some(Entry { pn: 0, dn: INF }),
+            false => Some(Entry { pn: INF, dn: 0 }),
+        }
+    }
+
+    fn lookup(&self, node: Node) -> Entry {
+        let key = self.key(node);
+        if self.path.contains(&key) {
+            // 循环局面按双方都无法取得进展处理, 即防守方获胜
+            return match self.problem.goal {
+                Goal::Kill => Entry { pn: INF, dn: 0 },
+                Goal::Live => Entry { pn: 0, dn: INF },
+            };
+        }
+        if let Some(entry) = self.table.get(&key) {
+            return *entry;
+        }
+        return self.terminal_entry(node).unwrap_or(Entry { pn: 1, dn: 1 });
+    }
+
+    fn play(&mut self, node: Node, mv: TsumegoMove) -> bool {
+        match mv {
+            Some(coord) => {
+                let stone = self.stone_to_move(node);
+                self.board.place_stone(coord, stone).is_ok()
+            }
+            None => true,
+        }
+    }
+
+    fn unplay(&mut self, mv: TsumegoMove) {
+        if mv.is_some() {
+            self.board.undo();
+        }
+    }
+
+    /// 区域内合法的落子, 以及 pass
+    fn legal_moves(&mut self, node: Node) -> Vec<TsumegoMove> {
+        let mut moves = vec![];
+        for i in 0..self.problem.region.len() {
+            let mv = Some(self.problem.region[i]);
+            if self.play(node, mv) {
+                self.unplay(mv);
+                moves.push(mv);
+            }
+        }
+        moves.push(None);
+        return moves;
+    }
+
+    fn children(&mut self, node: Node, moves: &[TsumegoMove]) -> Vec<Entry> {
+        let mut entries = Vec::with_capacity(moves.len());
+        for &mv in moves {
+            self.play(node, mv);
+            entries.push(self.lookup(node.after(mv)));
+            self.unplay(mv);
+        }
+        return entries;
+    }
+
+    /// df-pn 的 multiple iterative deepening, 直到 pn >= pn_th 或 dn >= dn_th
+    fn mid(&mut self, node: Node, pn_th: u32, dn_th: u32) -> Entry {
+        self.nodes += 1;
+        let key = self.key(node);
+        if let Some(entry) = self.terminal_entry(node) {
+            self.table.insert(key, entry);
+            return entry;
+        }
+
+        let moves = self.legal_moves(node);
+        self.path.push(key);
+        let entry = loop {
+            let entries = self.children(node, &moves);
+            let (entry, best, second) = if node.or_node {
+                summarize(&entries, |e| e.pn, |e| e.dn)
+            } else {
+                let (e, best, second) = summarize(&entries, |e| e.dn, |e| e.pn);
+                (Entry { pn: e.dn, dn: e.pn }, best, second)
+            };
+
+            if entry.pn >= pn_th || entry.dn >= dn_th || self.aborted() {
+                break entry;
+            }
+
+            let child = entries[best];
+            let (child_pn_th, child_dn_th) = if node.or_node {
+                (
+                    pn_th.min(second.saturating_add(1)),
+                    dn_th - (entry.dn - child.dn),
+                )
+            } else {
+                (
+                    pn_th - (entry.pn - child.pn),
+                    dn_th.min(second.saturating_add(1)),
+                )
+            };
+
+            let mv = moves[best];
+            self.play(node, mv);
+            self.mid(node.after(mv), child_pn_th, child_dn_th);
+            self.unplay(mv);
+        };
+        self.path.pop();
+
+        self.table.insert(key, entry);
+        return entry;
+    }
+
+    /// 沿置换表取出胜方的变化, 直到终局
+    fn extract_line(&mut self, node: Node) -> Vec<TsumegoMove> {
+        let mut line = vec![];
+        let mut node = node;
+        let max_len = self.problem.region.len() * 2 + 2;
+
+        while line.len() < max_len && self.terminal(node).is_none() {
+            let moves = self.legal_moves(node);
+            let entries = self.children(node, &moves);
+
+            // 胜方走保持胜利的一手, 负方尽量不走 pass, 也不走立刻终局的一手
+            let prover_wins = self.lookup(node).pn == 0;
+            let mut chosen: Option<usize> = None;
+            for (i, entry) in entries.iter().enumerate() {
+                let keeps = if prover_wins {
+                    entry.pn == 0
+                } else {
+                    entry.dn == 0
+                };
+                if !keeps {
+                    continue;
+                }
+                let mv = moves[i];
+                let better = match chosen {
+                    None => true,
+                    Some(j) => moves[j].is_none() && mv.is_some(),
+                };
+                if better {
+                    chosen = Some(i);
+                }
+                if node.or_node != prover_wins && mv.is_some() {
+                    self.play(node, mv);
+                    let is_terminal = self.terminal(node.after(mv)).is_some();
+                    self.unplay(mv);
+                    if !is_terminal {
+                        chosen = Some(i);
+                        break;
+                    }
+                }
+            }
+            let Some(i) = chosen else {
+                break;
+            };
+
+            let mv = moves[i];
+            self.play(node, mv);
+            line.push(mv);
+            node = node.after(mv);
+        }
+
+        for &mv in line.iter().rev() {
+            self.unplay(mv);
+        }
+        while line.last() == Some(&None) {
+            line.pop();
+        }
+        return line;
+    }
+}
+
+/// 以 `proof` 为选择依据汇总子节点: 返回 (汇总值, 最佳子节点下标, 第二小的 `proof`)
+///
+/// 返回的 Entry 中 pn 为 `proof` 的最小值, dn 为 `disproof` 的和
+fn summarize(
+    entries: &[Entry],
+    proof: impl Fn(&Entry) -> u32,
+    disproof: impl Fn(&Entry) -> u32,
+) -> (Entry, usize, u32) {
+    let mut best = 0;
+    let mut min = INF;
+    let mut second = INF;
+    let mut sum: u32 = 0;
+    for (i, entry) in entries.iter().enumerate() {
+        let p = proof(entry);
+        if p < min {
+            second = min;
+            min = p;
+            best = i;
+        } else if p < second {
+            second = p;
+        }
+        sum = sum.saturating_add(disproof(entry)).min(INF);
+    }
+    return (Entry { pn: min, dn: sum }, best, second);
+}
+
+/// Benson 算法: 计算 `stone` 一方无条件活的棋子
+///
+/// 返回的数组下标与 `Board::board_array` 一致
+pub fn unconditionally_alive(board: &Board, stone: Stone) -> Vec<bool> {
+    let size = board.size();
+    let b_array = board.board_array();
+    let n = b_array.len();
+
+    let neighbors = |idx: usize| {
+        let (x, y) = (idx % size, idx / size);
+        let mut v = Vec::with_capacity(4);
+        if x > 0 {
+            v.push(idx - 1);
+        }
+        if x < size - 1 {
+            v.push(idx + 1);
+        }
+        if y > 0 {
+            v.push(idx - size);
+        }
+        if y < size - 1 {
+            v.push(idx + size);
+        }
+        v
+    };
+
+    // 把 `stone` 的棋子分为 chain, 其余的点分为 region, 都以连通分量编号
+    let mut chain_of = vec![usize::MAX; n];
+    let mut region_of = vec![usize::MAX; n];
+    let mut chain_count = 0;
+    let mut region_count = 0;
+    for start in 0..n {
+        let is_chain = b_array[start] == stone;
+        let (ids, count) = if is_chain {
+            (&mut chain_of, &mut chain_count)
+        } else {
+            (&mut region_of, &mut region_count)
+        };
+        if ids[start] != usize::MAX {
+            continue;
+        }
+        ids[start] = *count;
+        let mut stack = vec![start];
+        while let Some(idx) = stack.pop() {
+            for neighbor_idx in neighbors(idx) {
+                if (b_array[neighbor_idx] == stone) == is_chain && ids[neighbor_idx] == usize::MAX {
+                    ids[neighbor_idx] = *count;
+                    stack.push(neighbor_idx);
+                }
+            }
+        }
+        *count += 1;
+    }
+
+    // region 周围的 chain, 以及 region 对哪些 chain 是 "healthy" 的 (region 内所有空点都是该 chain 的气)
+    let mut region_chains: Vec<Vec<usize>> = vec![vec![]; region_count];
+    let mut region_healthy: Vec<Vec<usize>> = vec![vec![]; region_count];
+    for region in 0..region_count {
+        let mut chains = vec![];
+        let mut empties = vec![];
+        for idx in 0..n {
+            if region_of[idx] != region {
+                continue;
+            }
+            let adjacent: Vec<usize> = neighbors(idx)
+                .into_iter()
+                .filter(|&i| chain_of[i] != usize::MAX)
+                .map(|i| chain_of[i])
+                .collect();
+            for &chain in &adjacent {
+                if !chains.contains(&chain) {
+                    chains.push(chain);
+                }
+            }
+            if b_array[idx] == Stone::VOID {
+                empties.push(adjacent);
+            }
+        }
+        region_healthy[region] = chains
+            .iter()
+            .copied()
+            .filter(|chain| empties.iter().all(|adjacent| adjacent.contains(chain)))
+            .collect();
+        region_chains[region] = chains;
+    }
+
+    // 反复删去 healthy region 少于2个的 chain, 以及与被删 chain 相邻的 region
+    let mut chain_alive = vec![true; chain_count];
+    let mut region_alive = vec![true; region_count];
+    loop {
+        let mut changed = false;
+        for region in 0..region_count {
+            if region_alive[region] && region_chains[region].iter().any(|&c| !chain_alive[c]) {
+                region_alive[region] = false;
+                changed = true;
+            }
+        }
+        for (chain, alive) in chain_alive.iter_mut().enumerate() {
+            if !*alive {
+                continue;
+            }
+            let vital = (0..region_count)
+                .filter(|&r| region_alive[r] && region_healthy[r].contains(&chain))
+                .count();
+            if vital < 2 {
+                *alive = false;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    return (0..n)
+        .map(|idx| chain_of[idx] != usize::MAX && chain_alive[chain_of[idx]])
+        .collect();
+}
@@ -0,0 +1,140 @@
+use rand::{RngExt, SeedableRng, rngs::StdRng};
+use rustgo::{
+    Coord, Stone,
+    board::{Board, BoardArray, GoBoard, bitboard::BitBoard},
+};
+
+const VOID: Stone = Stone::VOID;
+const BLACK: Stone = Stone::BLACK;
+const WHITE: Stone = Stone::WHITE;
+
+/// 两种实现随机对局, 每一步的结果都必须一致
+fn random_game(size: usize, n_stone: u8, seed: u64, moves: usize) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut a = Board::new(size);
+    let mut b = BitBoard::new(size);
+    let mut stone = BLACK;
+
+    for _ in 0..moves {
+        let coord = Coord::new(rng.random_range(0..size), rng.random_range(0..size));
+        let res_a = a.place_stone(coord, stone);
+        let res_b = b.place_stone(coord, stone);
+        match (res_a, res_b) {
+            (Ok(mut eaten_a), Ok(mut eaten_b)) => {
+                eaten_a.sort_by_key(|c| (c.y, c.x));
+                eaten_b.sort_by_key(|c| (c.y, c.x));
+                assert_eq!(eaten_a, eaten_b);
+                stone = stone.next_stone(n_stone);
+            }
+            (Err(err_a), Err(err_b)) => assert_eq!(err_a, err_b),
+            (res_a, res_b) => panic!("{:?} != {:?}", res_a, res_b),
+        }
+        assert_eq!(a.board_string(), b.board_string());
+        assert_eq!(a.hash(), b.hash());
+        for s in 1..=n_stone {
+            assert_eq!(a.captures(Stone::new(s)), b.captures(Stone::new(s)));
+        }
+
+        // 偶尔悔棋
+        if rng.random_range(0..10) == 0 {
+            assert_eq!(GoBoard::undo(&mut a), GoBoard::undo(&mut b));
+            assert_eq!(a.board_string(), b.board_string());
+            assert_eq!(a.hash(), b.hash());
+        }
+    }
+}
+
+#[test]
+fn test_random_games() {
+    random_game(9, 2, 1, 400);
+    random_game(19, 2, 2, 500);
+    random_game(13, 4, 3, 600);
+    random_game(5, 3, 4, 300);
+}
+
+#[test]
+fn test_edge_captures() {
+    #[rustfmt::skip]
+    let b_array: BoardArray = Box::new([
+        WHITE, WHITE, BLACK,
+        BLACK, VOID, VOID,
+        VOID, VOID, VOID,
+    ]);
+    let mut a = Board::new_with_board(3, b_array.clone());
+    let mut b = BitBoard::new_with_board(3, &b_array);
+    assert_eq!(a.hash(), b.hash());
+    assert_eq!(a.board_string(), b.board_string());
+
+    // 角上的两颗白子只剩 [1,1] 一口气, 黑棋落在 [1,1] 提走它们
+    assert_eq!(b.liberties(0), 1);
+    let mut b2 = BitBoard::new(3);
+    assert_eq!(b2.place_stone(Coord::new(3, 0), BLACK), Err("非法坐标"));
+    assert_eq!(b2.place_stone(Coord::new(2, 2), BLACK), Ok(vec![]));
+    assert_eq!(b2.liberties(8), 2);
+
+    let captured = vec![Coord::new(0, 0), Coord::new(1, 0)];
+    let mut eaten_a = a.place_stone(Coord::new(1, 1), BLACK).unwrap();
+    let mut eaten_b = b.place_stone(Coord::new(1, 1), BLACK).unwrap();
+    eaten_a.sort_by_key(|c| (c.y, c.x));
+    eaten_b.sort_by_key(|c| (c.y, c.x));
+    assert_eq!(eaten_a, captured);
+    assert_eq!(eaten_b, captured);
+    assert_eq!(a.board_string(), b.board_string());
+    assert_eq!(b.captures(BLACK), 2);
+}
+
+#[test]
+fn test_ko() {
+    #[rustfmt::skip]
+    let b_array: BoardArray = Box::new([
+        VOID, BLACK, WHITE, VOID,
+        BLACK, WHITE, VOID, WHITE,
+        VOID, BLACK, WHITE, VOID,
+        VOID, VOID, VOID, VOID,
+    ]);
+    let mut a = Board::new_with_board(4, b_array.clone());
+    let mut b = BitBoard::new_with_board(4, &b_array);
+
+    // 黑提白, 白提回 (初始局面不在历史记录中), 黑不能立刻再提
+    let eaten = b.place_stone(Coord::new(2, 1), BLACK).unwrap();
+    assert_eq!(eaten, vec![Coord::new(1, 1)]);
+    assert_eq!(a.place_stone(Coord::new(2, 1), BLACK).unwrap(), eaten);
+
+    let eaten = b.place_stone(Coord::new(1, 1), WHITE).unwrap();
+    assert_eq!(eaten, vec![Coord::new(2, 1)]);
+    assert_eq!(a.place_stone(Coord::new(1, 1), WHITE).unwrap(), eaten);
+
+    assert_eq!(b.place_stone(Coord::new(2, 1), BLACK), Err("禁止全局同形"));
+    assert_eq!(a.place_stone(Coord::new(2, 1), BLACK), Err("禁止全局同形"));
+}
+
+fn check_is_legal_and_captures<B: GoBoard>() {
+    #[rustfmt::skip]
+    let moves = [
+        (1, 0, BLACK), (0, 0, WHITE),
+        (0, 1, BLACK),
+    ];
+    let mut board = B::new(3);
+    for (x, y, stone) in moves {
+        assert!(board.is_legal(Coord::new(x, y), stone));
+        board.place_stone(Coord::new(x, y), stone).unwrap();
+    }
+    assert_eq!(board.captures(BLACK), 1);
+    assert_eq!(board.captures(WHITE), 0);
+
+    // is_legal 不改变棋盘
+    let before = board.board_string();
+    assert!(!board.is_legal(Coord::new(1, 0), WHITE));
+    assert!(!board.is_legal(Coord::new(0, 0), WHITE));
+    assert!(board.is_legal(Coord::new(2, 2), WHITE));
+    assert_eq!(board.board_string(), before);
+
+    assert!(board.undo());
+    assert_eq!(board.captures(BLACK), 0);
+}
+
+#[test]
+fn test_is_legal_and_captures() {
+    check_is_legal_and_captures::<Board>();
+    check_is_legal_and_captures::<BitBoard>();
+}
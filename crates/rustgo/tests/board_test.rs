@@ -53,3 +53,65 @@ fn test_3() {
     let result = board.place_stone(Coord::new(1, 1), WHITE);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_undo() {
+    #[rustfmt::skip]
+    let board: BoardArray = Box::new([
+        VOID, BLACK, VOID,
+        BLACK, WHITE, BLACK,
+        VOID, VOID, VOID,
+    ]);
+    let mut board = Board::new_with_board(3, board);
+    let before = board.board_string();
+    let hash = board.hash();
+
+    let eaten = board.place_stone(Coord::new(1, 2), BLACK).unwrap();
+    assert_eq!(eaten.len(), 1);
+    assert_ne!(board.hash(), hash);
+    assert_eq!(board.move_count(), 1);
+
+    assert!(board.undo());
+    assert_eq!(board.board_string(), before);
+    assert_eq!(board.hash(), hash);
+    assert!(!board.undo());
+
+    // 撤销后棋子组和气也要恢复
+    let eaten = board.place_stone(Coord::new(1, 2), BLACK).unwrap();
+    assert_eq!(eaten.len(), 1);
+}
+
+#[test]
+fn test_undo_merge() {
+    let mut board = Board::new(5);
+    board.place_stone(Coord::new(0, 0), BLACK).unwrap();
+    board.place_stone(Coord::new(2, 0), BLACK).unwrap();
+    // 连接两个组之后悔棋, 两个组要重新分开
+    board.place_stone(Coord::new(1, 0), BLACK).unwrap();
+    assert!(board.undo());
+
+    board.place_stone(Coord::new(1, 0), WHITE).unwrap();
+    let eaten = board.place_stone(Coord::new(0, 1), WHITE).unwrap();
+    assert_eq!(eaten, vec![Coord::new(0, 0)]);
+    assert_eq!(board.stone(Coord::new(2, 0)), BLACK);
+
+    // 全部悔棋之后回到空棋盘
+    while board.undo() {}
+    assert_eq!(board.board_array(), [VOID; 5 * 5]);
+    assert_eq!(board.hash(), 0);
+    board.place_stone(Coord::new(0, 0), WHITE).unwrap();
+}
+
+#[test]
+fn test_hash_order_independent() {
+    let mut a = Board::new(5);
+    a.place_stone(Coord::new(1, 1), BLACK).unwrap();
+    a.place_stone(Coord::new(2, 2), WHITE).unwrap();
+
+    let mut b = Board::new(5);
+    b.place_stone(Coord::new(2, 2), WHITE).unwrap();
+    b.place_stone(Coord::new(1, 1), BLACK).unwrap();
+
+    assert_eq!(a.hash(), b.hash());
+    assert_eq!(Board::new(5).hash(), 0);
+}
@@ -0,0 +1,144 @@
+use rustgo::{
+    Coord, Stone,
+    board::Board,
+    book::{BookGame, OpeningBook},
+};
+
+const GAME_1: &str = "(;GM[1]FF[4]SZ[19]RE[B+R];B[dd];W[pp];B[dp];W[pd])";
+// GAME_1 左右翻转
+const GAME_2: &str = "(;GM[1]FF[4]SZ[19]RE[W+3.5];B[pd];W[dp];B[pp];W[dd])";
+// 主变化之外的分支不应被收录
+const GAME_3: &str = "(;GM[1]FF[4]SZ[19]RE[B+1.5];B[dd](;W[pp];B[dp])(;W[qq];B[cc]))";
+
+#[test]
+fn test_symmetric_games_merge() {
+    let mut book = OpeningBook::new(19, 10);
+    assert!(book.add_sgf(GAME_1));
+    assert!(book.add_sgf(GAME_2));
+
+    let board = Board::new(19);
+    let moves = book.lookup(&board, Stone::BLACK);
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].count, 2);
+    assert_eq!(moves[0].wins, 1);
+    assert_eq!(moves[0].losses, 1);
+    assert_eq!(moves[0].win_rate(), Some(0.5));
+
+    // 着法要变换回查询局面的方向
+    let mut board = Board::new(19);
+    board.place_stone(Coord::new(15, 3), Stone::BLACK).unwrap();
+    let moves = book.lookup(&board, Stone::WHITE);
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].coord, Some(Coord::new(3, 15)));
+}
+
+#[test]
+fn test_main_line_and_max_moves() {
+    let mut book = OpeningBook::new(19, 2);
+    assert!(book.add_sgf(GAME_3));
+    // 只收录前两手
+    assert_eq!(book.len(), 2);
+
+    let mut board = Board::new(19);
+    board.place_stone(Coord::new(3, 3), Stone::BLACK).unwrap();
+    let moves = book.lookup(&board, Stone::WHITE);
+    assert_eq!(moves.len(), 1);
+    assert_eq!(moves[0].coord, Some(Coord::new(15, 15)));
+
+    assert!(book.suggest(&board, Stone::WHITE, 1).is_some());
+    assert!(book.suggest(&board, Stone::WHITE, 2).is_none());
+    assert!(book.lookup(&board, Stone::BLACK).is_empty());
+}
+
+#[test]
+fn test_illegal_move_not_recorded() {
+    let mut book = OpeningBook::new(19, 10);
+    // 白棋下在黑棋上, 之后的着法都不收录
+    assert!(book.add_game(&BookGame {
+        size: 19,
+        moves: vec![
+            (Stone::BLACK, Some(Coord::new(3, 3))),
+            (Stone::WHITE, Some(Coord::new(3, 3))),
+            (Stone::BLACK, Some(Coord::new(15, 15))),
+        ],
+        winner: None,
+    }));
+    assert_eq!(book.len(), 1);
+
+    let mut board = Board::new(19);
+    board.place_stone(Coord::new(3, 3), Stone::BLACK).unwrap();
+    assert!(book.lookup(&board, Stone::WHITE).is_empty());
+}
+
+#[test]
+fn test_wrong_size() {
+    let mut book = OpeningBook::new(9, 10);
+    assert!(!book.add_sgf(GAME_1));
+    assert!(!book.add_game(&BookGame {
+        size: 19,
+        moves: vec![],
+        winner: None,
+    }));
+    assert!(book.is_empty());
+}
+
+#[test]
+fn test_save_load() {
+    let mut book = OpeningBook::new(19, 10);
+    book.add_sgf(GAME_1);
+    book.add_sgf(GAME_3);
+
+    let mut buf = vec![];
+    book.save(&mut buf).unwrap();
+    let loaded = OpeningBook::load(buf.as_slice()).unwrap();
+    assert_eq!(loaded.size(), 19);
+    assert_eq!(loaded.max_moves(), 10);
+    assert_eq!(loaded.len(), book.len());
+
+    let mut board = Board::new(19);
+    board.place_stone(Coord::new(3, 3), Stone::BLACK).unwrap();
+    assert_eq!(
+        loaded.lookup(&board, Stone::WHITE),
+        book.lookup(&board, Stone::WHITE)
+    );
+
+    assert!(OpeningBook::load(&b"nope"[..]).is_err());
+
+    // 损坏的文件: 棋盘大小不合法, 或着法在棋盘之外
+    let mut bad_size = buf.clone();
+    bad_size[5] = 1;
+    let err = OpeningBook::load(bad_size.as_slice()).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    let mut bad_idx = buf.clone();
+    // magic, version, size, max_moves, entries, 第一个局面的 hash 与着法数之后是第一个着法
+    let at = 4 + 2 + 2 + 4 + 8 + 2;
+    bad_idx[at..at + 2].copy_from_slice(&(19u16 * 19).to_le_bytes());
+    let err = OpeningBook::load(bad_idx.as_slice()).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+
+    let book = OpeningBook::new(19, u16::MAX as usize + 1);
+    let err = book.save(&mut vec![]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_add_dir() {
+    let dir = std::env::temp_dir().join(format!("rustgo_book_test_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("1.sgf"), GAME_1).unwrap();
+    std::fs::write(dir.join("2.SGF"), GAME_2).unwrap();
+    std::fs::write(dir.join("readme.txt"), "not a game").unwrap();
+    // GBK 编码的棋手名
+    let mut bytes = b"(;GM[1]FF[4]SZ[19]PB[".to_vec();
+    bytes.extend([0xc0, 0xee]);
+    bytes.extend(b"]RE[B+R];B[dd];W[pp])");
+    std::fs::write(dir.join("3.sgf"), bytes).unwrap();
+
+    let mut book = OpeningBook::new(19, 10);
+    let n = book.add_dir(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(n, 3);
+    let moves = book.lookup(&Board::new(19), Stone::BLACK);
+    assert_eq!(moves[0].count, 3);
+}
@@ -0,0 +1,34 @@
+use rustgo::{
+    Stone,
+    board::{Board, bitboard::BitBoard},
+    bot::{Bot, RandomBot},
+};
+
+/// 同样的种子在两种棋盘上下出同样的棋, 直到双方都 pass
+#[test]
+fn test_random_bot_on_any_board() {
+    let mut a = Board::new(7);
+    let mut b = BitBoard::new(7);
+    let mut bot_a = RandomBot::with_seed(7);
+    let mut bot_b = RandomBot::with_seed(7);
+    let mut stone = Stone::BLACK;
+    let mut passes = 0;
+
+    for move_number in 0..200 {
+        let coord = bot_a.genmove(&mut a, stone, move_number);
+        assert_eq!(bot_b.genmove(&mut b, stone, move_number), coord);
+        match coord {
+            Some(coord) => {
+                passes = 0;
+                assert_eq!(a.place_stone(coord, stone), b.place_stone(coord, stone));
+                assert_eq!(a.hash(), b.hash());
+            }
+            None => passes += 1,
+        }
+        if passes == 2 {
+            return;
+        }
+        stone = stone.next_stone(2);
+    }
+    panic!("game did not end");
+}
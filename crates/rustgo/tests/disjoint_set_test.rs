@@ -111,3 +111,30 @@ fn test_delete() {
 
     assert!(ds.group_roots().is_empty());
 }
+
+#[test]
+fn test_union_by_size() {
+    let mut ds = DisjointSet::<TestType>::new(TEST_SIZE);
+    for i in 1..10 {
+        ds.connect(0, i);
+    }
+    let root = ds.find_root(0).unwrap();
+
+    // 大的 group 挂到孤立元素上时, root 不变
+    ds.connect(20, 5);
+    assert!(ds.find_root(20) == Some(root));
+    assert!(ds.group_size(20) == 11);
+    assert!(ds.len() == 1);
+}
+
+#[test]
+fn test_long_chain() {
+    const SIZE: usize = 1 << 20;
+    let mut ds = DisjointSet::<u32>::new(SIZE);
+    for i in 1..SIZE {
+        ds.connect(i, i - 1);
+    }
+    assert!(ds.len() == 1);
+    assert!(ds.group_size(SIZE - 1) == SIZE);
+    assert!(ds.is_connected(0, SIZE - 1));
+}
@@ -0,0 +1,246 @@
+use rustgo::{
+    Coord, Stone,
+    board::Board,
+    game_tree::{GameTree, Markup, MarkupKind},
+    sgf::{self, GameInfo},
+};
+
+const BLACK: Stone = Stone::BLACK;
+const WHITE: Stone = Stone::WHITE;
+
+const GAME_9X9: &str = include_str!("data/synthetic_9x9.sgf");
+const GAME_19X19: &str = include_str!("data/synthetic_19x19_handicap.sgf");
+const THREE_COLORS: &str = include_str!("data/three_colors.sgf");
+
+fn load(text: &str) -> GameTree {
+    GameTree::from_sgf(&sgf::parse_game(text).unwrap()).unwrap()
+}
+
+/// 主变化前 `n` 手之后的局面
+fn replay(text: &str, n: usize) -> String {
+    let root = sgf::parse_game(text).unwrap();
+    let mut board = Board::new(root.size());
+    let mut moves = 0;
+    for node in root.main_line() {
+        if node.sgf_move(root.size()).is_some() {
+            if moves == n {
+                break;
+            }
+            moves += 1;
+        }
+        sgf::apply_node(&mut board, node).unwrap();
+    }
+    board.board_string()
+}
+
+#[test]
+fn test_next_prev() {
+    let mut tree = load(GAME_9X9);
+    assert_eq!(tree.current(), GameTree::ROOT);
+    assert!(!tree.go_prev());
+
+    let mut boards = vec![tree.board().board_string()];
+    while tree.go_next() {
+        boards.push(tree.board().board_string());
+        assert_eq!(
+            tree.board().board_string(),
+            replay(GAME_9X9, tree.move_number())
+        );
+    }
+    assert_eq!(tree.move_number(), 25);
+    assert_eq!(tree.board().captures(BLACK), 1);
+
+    while tree.go_prev() {
+        boards.pop();
+        assert_eq!(&tree.board().board_string(), boards.last().unwrap());
+    }
+    assert_eq!(boards.len(), 1);
+    assert_eq!(tree.current(), GameTree::ROOT);
+}
+
+#[test]
+fn test_go_to_move() {
+    let mut tree = load(GAME_19X19);
+    assert!(tree.go_to_move(10));
+    assert_eq!(tree.move_number(), 10);
+    assert_eq!(tree.board().board_string(), replay(GAME_19X19, 10));
+
+    assert!(tree.go_to_move(3));
+    assert_eq!(tree.board().board_string(), replay(GAME_19X19, 3));
+
+    assert!(!tree.go_to_move(1000));
+    assert!(tree.variations().is_empty());
+
+    assert!(tree.go_to_move(0));
+    assert_eq!(tree.current(), GameTree::ROOT);
+    // 让子在根节点上
+    assert_eq!(tree.board().stone(Coord::new(3, 3)), BLACK);
+}
+
+#[test]
+fn test_variations() {
+    let mut tree = load(GAME_9X9);
+    tree.go_to_move(5);
+    assert_eq!(tree.variations().len(), 2);
+
+    assert!(tree.variation(1));
+    assert_eq!(
+        tree.node(tree.current()).comment.as_deref(),
+        Some("Too slow.")
+    );
+    assert_eq!(tree.board().stone(Coord::new(6, 5)), WHITE);
+    assert!(!tree.variation(5));
+
+    // 记住了选择的变化
+    tree.go_to_move(8);
+    assert_eq!(tree.variations().len(), 2);
+    assert!(tree.variation(1));
+    tree.go_to_root();
+    assert!(tree.go_to_move(10));
+    assert_eq!(
+        tree.node(tree.current()).mv,
+        Some((WHITE, Some(Coord::new(7, 3))))
+    );
+
+    tree.go_to_move(5);
+    assert!(tree.variation(0));
+    assert_eq!(tree.board().stone(Coord::new(6, 5)), Stone::VOID);
+    assert_eq!(tree.board().stone(Coord::new(5, 3)), WHITE);
+}
+
+#[test]
+fn test_play_branch() {
+    let mut tree = load(GAME_9X9);
+    let len = tree.len();
+    tree.go_to_move(5);
+
+    // 已有的变化
+    let existing = tree.variations()[0];
+    assert_eq!(tree.play(WHITE, Some(Coord::new(5, 3))), Ok(existing));
+    assert_eq!(tree.len(), len);
+
+    // 非法落子不改变棋谱
+    assert!(tree.play(BLACK, Coord::from_a1("E5", 9)).is_err());
+    assert_eq!(tree.len(), len);
+    assert_eq!(tree.current(), existing);
+
+    // 新的分支
+    tree.go_prev();
+    let id = tree.play(WHITE, Some(Coord::new(2, 3))).unwrap();
+    assert_eq!(tree.len(), len + 1);
+    assert_eq!(tree.variations().len(), 0);
+    assert_eq!(
+        tree.node(id).parent.map(|p| tree.node(p).children.len()),
+        Some(3)
+    );
+    assert_eq!(tree.board().stone(Coord::new(2, 3)), WHITE);
+    assert_eq!(tree.to_play(), BLACK);
+
+    tree.play(BLACK, None).unwrap();
+    assert_eq!(tree.move_number(), 7);
+
+    tree.go_to(id);
+    tree.promote_variation();
+    tree.go_to_root();
+    tree.go_to_move(6);
+    assert_eq!(tree.current(), id);
+
+    let written = tree.to_sgf();
+    let reloaded = GameTree::from_sgf(&written).unwrap();
+    assert_eq!(reloaded.to_sgf(), written);
+    assert_eq!(reloaded.len(), tree.len());
+}
+
+#[test]
+fn test_setup_node() {
+    let mut tree = GameTree::new(GameInfo::new(5, 2));
+    tree.play(BLACK, Some(Coord::new(2, 2))).unwrap();
+    tree.add_setup(vec![
+        (Stone::VOID, Coord::new(2, 2)),
+        (WHITE, Coord::new(0, 0)),
+    ]);
+    assert_eq!(tree.board().stone(Coord::new(2, 2)), Stone::VOID);
+    tree.play(BLACK, Some(Coord::new(1, 0))).unwrap();
+    tree.play(WHITE, Some(Coord::new(3, 3))).unwrap();
+    tree.play(BLACK, Some(Coord::new(0, 1))).unwrap();
+    assert_eq!(tree.board().stone(Coord::new(0, 0)), Stone::VOID);
+
+    tree.go_prev();
+    tree.go_prev();
+    tree.go_prev();
+    assert_eq!(tree.board().stone(Coord::new(0, 0)), WHITE);
+    // 退出摆子节点
+    tree.go_prev();
+    assert_eq!(tree.board().stone(Coord::new(2, 2)), BLACK);
+    assert_eq!(tree.board().stone(Coord::new(0, 0)), Stone::VOID);
+}
+
+#[test]
+fn test_markup_and_comment() {
+    let text = "(;SZ[5];B[cc]C[look]CR[aa][bb]TR[ee]LB[dd:A][de:ko])";
+    let mut tree = load(text);
+    tree.go_next();
+    let node = tree.node(tree.current());
+    assert_eq!(node.comment.as_deref(), Some("look"));
+    assert_eq!(node.markup.len(), 5);
+    assert_eq!(
+        node.markup[0],
+        Markup {
+            coord: Coord::new(0, 0),
+            kind: MarkupKind::Circle
+        }
+    );
+    assert_eq!(node.markup[4].kind, MarkupKind::Label("ko".to_string()));
+
+    tree.clear_markup();
+    tree.add_markup(Markup {
+        coord: Coord::new(1, 1),
+        kind: MarkupKind::Square,
+    });
+    tree.set_comment(None);
+    let root = tree.to_sgf();
+    let node = &root.children[0];
+    assert_eq!(node.get("SQ"), Some("bb"));
+    assert_eq!(node.comment(), None);
+}
+
+#[test]
+fn test_to_play() {
+    let mut tree = load(GAME_19X19);
+    assert_eq!(tree.to_play(), WHITE);
+    tree.go_next();
+    assert_eq!(tree.to_play(), BLACK);
+
+    let mut tree = load(THREE_COLORS);
+    assert_eq!(tree.to_play(), BLACK);
+    tree.go_to_move(2);
+    assert_eq!(tree.to_play(), Stone::new(3));
+    tree.go_next();
+    assert_eq!(tree.to_play(), BLACK);
+}
+
+#[test]
+fn test_sgf_round_trip() {
+    for text in [GAME_9X9, GAME_19X19, THREE_COLORS] {
+        let root = sgf::parse_game(text).unwrap();
+        let tree = GameTree::from_sgf(&root).unwrap();
+        // 对局信息与 pass 会被规范化, 比如 KM[6.50] 写成 KM[6.5], B[tt] 写成 B[]
+        let written = tree.to_sgf();
+        assert_eq!(written.game_info(), root.game_info());
+        assert_eq!(written.main_line_moves(), root.main_line_moves());
+        assert_eq!(written.setup(root.size()), root.setup(root.size()));
+        assert_eq!(GameTree::from_sgf(&written).unwrap().to_sgf(), written);
+        if !text.contains("[tt]") {
+            let mut expected = root.clone();
+            expected.set_game_info(&root.game_info());
+            assert_eq!(written, expected);
+        }
+    }
+}
+
+#[test]
+fn test_illegal_variation() {
+    // 变化中有非法落子
+    let text = "(;SZ[5];B[cc](;W[dd])(;W[cc]))";
+    assert!(GameTree::from_sgf(&sgf::parse_game(text).unwrap()).is_err());
+}
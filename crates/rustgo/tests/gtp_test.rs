@@ -0,0 +1,318 @@
+use std::io::Cursor;
+
+use rustgo::{
+    Coord, Stone,
+    bot::RandomBot,
+    gtp::{self, GtpCommand, GtpEngine},
+};
+
+fn engine() -> GtpEngine {
+    GtpEngine::new(Box::new(RandomBot::with_seed(42)))
+}
+
+/// 运行一段 GTP 会话, 返回按空行切分的响应
+fn session(engine: &mut GtpEngine, input: &str) -> Vec<String> {
+    let mut output = vec![];
+    engine.run(Cursor::new(input), &mut output).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    return output
+        .split_terminator("\n\n")
+        .map(str::to_string)
+        .collect();
+}
+
+#[test]
+fn test_parse_command() {
+    assert_eq!(
+        gtp::parse_command("12 play\tb D4 # comment\r"),
+        Some(GtpCommand {
+            id: Some(12),
+            name: "play".to_string(),
+            args: vec!["b".to_string(), "D4".to_string()],
+        })
+    );
+    assert_eq!(gtp::parse_command("   "), None);
+    assert_eq!(gtp::parse_command("# only a comment"), None);
+    assert_eq!(gtp::parse_command("name").unwrap().id, None);
+}
+
+#[test]
+fn test_vertex() {
+    assert_eq!(gtp::parse_vertex("A1", 19), Ok(Some(Coord::new(0, 18))));
+    assert_eq!(gtp::parse_vertex("t19", 19), Ok(Some(Coord::new(18, 0))));
+    assert_eq!(gtp::parse_vertex("J10", 19), Ok(Some(Coord::new(8, 9))));
+    assert_eq!(gtp::parse_vertex("PASS", 19), Ok(None));
+    assert!(gtp::parse_vertex("I5", 19).is_err());
+    assert!(gtp::parse_vertex("K1", 9).is_err());
+    assert!(gtp::parse_vertex("A0", 9).is_err());
+    assert!(gtp::parse_vertex("A10", 9).is_err());
+    assert!(gtp::parse_vertex("", 9).is_err());
+    assert_eq!(gtp::vertex_string(Some(Coord::new(24, 0)), 25), "Z25");
+    assert_eq!(gtp::vertex_string(None, 19), "pass");
+}
+
+#[test]
+fn test_handicap_points() {
+    let vertices = |size, n| {
+        gtp::handicap_points(size, n)
+            .unwrap()
+            .into_iter()
+            .map(|c| gtp::vertex_string(Some(c), size))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    assert_eq!(vertices(19, 2), "D4 Q16");
+    assert_eq!(vertices(19, 3), "D4 Q16 D16");
+    assert_eq!(vertices(19, 5), "D4 Q16 D16 Q4 K10");
+    assert_eq!(vertices(19, 6), "D4 Q16 D16 Q4 D10 Q10");
+    assert_eq!(vertices(19, 9), "D4 Q16 D16 Q4 D10 Q10 K4 K16 K10");
+    assert_eq!(vertices(9, 4), "C3 G7 C7 G3");
+    assert_eq!(gtp::handicap_points(19, 1), None);
+    assert_eq!(gtp::handicap_points(19, 10), None);
+    assert_eq!(gtp::handicap_points(10, 5), None);
+    assert_eq!(gtp::handicap_points(5, 2), None);
+}
+
+#[test]
+fn test_session() {
+    let mut engine = engine();
+    let responses = session(
+        &mut engine,
+        "1 protocol_version\n\
+         2 boardsize 9\n\
+         komi 6.5\n\
+         3 play black E5\n\
+         4 play white E5\n\
+         5 play w pass\n\
+         6 known_command genmove\n\
+         7 known_command foo\n\
+         8 foo\n\
+         9 boardsize 30\n\
+         10 play b\n\
+         quit\n\
+         11 name\n",
+    );
+    assert_eq!(
+        responses,
+        vec![
+            "=1 2",
+            "=2",
+            "=",
+            "=3",
+            "?4 illegal move",
+            "=5",
+            "=6 true",
+            "=7 false",
+            "?8 unknown command",
+            "?9 unacceptable size",
+            "?10 syntax error",
+            "=",
+        ]
+    );
+    assert_eq!(engine.board().size(), 9);
+    assert_eq!(engine.komi(), 6.5);
+    assert_eq!(engine.moves().len(), 2);
+}
+
+#[test]
+fn test_genmove_and_undo() {
+    let mut engine = engine();
+    let responses = session(
+        &mut engine,
+        "boardsize 5\nclear_board\ngenmove b\ngenmove w\n",
+    );
+    let coords: Vec<Coord> = responses[2..]
+        .iter()
+        .map(|r| {
+            gtp::parse_vertex(r.strip_prefix("= ").unwrap(), 5)
+                .unwrap()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(engine.board().stone(coords[0]), Stone::BLACK);
+    assert_eq!(engine.board().stone(coords[1]), Stone::WHITE);
+
+    let responses = session(&mut engine, "undo\nundo\nundo\n");
+    assert_eq!(responses, vec!["=", "=", "? cannot undo"]);
+    assert!(
+        engine
+            .board()
+            .board_array()
+            .iter()
+            .all(|&s| s == Stone::VOID)
+    );
+}
+
+#[test]
+fn test_random_bot_fills_board_without_own_eyes() {
+    let mut engine = engine();
+    session(&mut engine, "boardsize 7\n");
+    let mut passes = 0;
+    let mut stone = "b";
+    for _ in 0..500 {
+        let res = session(&mut engine, &format!("genmove {stone}\n"));
+        if res[0] == "= pass" {
+            passes += 1;
+            if passes == 2 {
+                break;
+            }
+        } else {
+            passes = 0;
+        }
+        stone = if stone == "b" { "w" } else { "b" };
+    }
+    assert_eq!(passes, 2);
+    // 两个随机棋手下完之后, 双方各自的眼都还空着
+    assert!(engine.board().board_array().contains(&Stone::VOID));
+}
+
+#[test]
+fn test_fixed_handicap_and_score() {
+    let mut engine = engine();
+    let responses = session(
+        &mut engine,
+        "boardsize 9\nkomi 0.5\nfixed_handicap 4\nfixed_handicap 2\nundo\nfinal_score\n",
+    );
+    assert_eq!(
+        responses,
+        vec![
+            "=",
+            "=",
+            "= C3 G7 C7 G3",
+            "? board not empty",
+            "? cannot undo",
+            "= B+80.5",
+        ]
+    );
+
+    let responses = session(
+        &mut engine,
+        "clear_board\nplay b D1\nplay b D2\nplay b D3\nplay b D4\nplay b D5\n\
+         play b D6\nplay b D7\nplay b D8\nplay b D9\n\
+         play w E1\nplay w E2\nplay w E3\nplay w E4\nplay w E5\n\
+         play w E6\nplay w E7\nplay w E8\nplay w E9\n\
+         final_score\nkomi 9\nfinal_score\nfinal_status_list dead\n",
+    );
+    // 黑: 4 列, 白: 5 列
+    assert_eq!(responses[19], "= W+9.5");
+    assert_eq!(responses[21], "= W+18");
+    assert_eq!(responses[22], "=");
+
+    let responses = session(&mut engine, "clear_board\nkomi 0\nfinal_score\n");
+    assert_eq!(responses[2], "= 0");
+}
+
+#[test]
+fn test_showboard_and_list_commands() {
+    let mut engine = engine();
+    let responses = session(
+        &mut engine,
+        "boardsize 3\nplay b A1\nplay w C3\nshowboard\nlist_commands\n",
+    );
+    assert_eq!(
+        responses[3],
+        "= \n   A B C\n 3 . . O 3\n 2 . . . 2\n 1 X . . 1\n   A B C"
+    );
+    let commands: Vec<&str> = responses[4].strip_prefix("= ").unwrap().lines().collect();
+    assert_eq!(commands, gtp::COMMANDS);
+}
+
+#[test]
+fn test_time_commands() {
+    let mut engine = engine();
+    let responses = session(
+        &mut engine,
+        "time_settings 300 30 5
+time_left b 120 0
+time_left c3 10 1
+time_settings 300 x 5
+",
+    );
+    assert_eq!(responses, ["=", "=", "? invalid color", "? syntax error"]);
+}
+
+#[test]
+fn test_parse_response() {
+    assert_eq!(gtp::parse_response("= D4"), Some(Ok("D4".to_string())));
+    assert_eq!(gtp::parse_response("=12"), Some(Ok(String::new())));
+    assert_eq!(
+        gtp::parse_response("?3 illegal move\n"),
+        Some(Err("illegal move".to_string()))
+    );
+    assert_eq!(
+        gtp::parse_response("= a\nb\n"),
+        Some(Ok("a\nb".to_string()))
+    );
+    assert_eq!(gtp::parse_response("D4"), None);
+    assert_eq!(gtp::parse_response(""), None);
+}
+
+#[test]
+fn test_colors() {
+    assert_eq!(gtp::parse_color("Black"), Some(Stone::BLACK));
+    assert_eq!(gtp::parse_color("w"), Some(Stone::WHITE));
+    assert_eq!(gtp::parse_color("c3"), Some(Stone::new(3)));
+    assert_eq!(gtp::parse_color("C6"), Some(Stone::new(6)));
+    assert_eq!(gtp::parse_color("c7"), None);
+    assert_eq!(gtp::parse_color("c2"), None);
+    for n in 1..=6 {
+        let stone = Stone::new(n);
+        assert_eq!(
+            gtp::parse_color(gtp::color_name(stone).unwrap()),
+            Some(stone)
+        );
+    }
+    assert_eq!(gtp::color_name(Stone::VOID), None);
+    assert_eq!(gtp::color_name(Stone::new(7)), None);
+}
+
+#[test]
+fn test_multi_color_session() {
+    let mut engine = engine();
+    let responses = session(
+        &mut engine,
+        "boardsize 5\n\
+         play c3 A1\n\
+         num_colors 7\n\
+         num_colors 3\n\
+         play c3 A1\n\
+         play c4 A2\n\
+         genmove C3\n\
+         undo\n\
+         showboard\n",
+    );
+    assert_eq!(
+        responses[..6],
+        [
+            "=",
+            "? invalid color",
+            "? unacceptable number of colors",
+            "=",
+            "=",
+            "? invalid color",
+        ]
+    );
+    assert_eq!(engine.num_colors(), 3);
+    assert!(responses[6].starts_with("= "));
+    assert_eq!(responses[7], "=");
+    assert!(responses[8].contains(" 1 $ . . . . 1"));
+}
+
+#[test]
+fn test_multi_color_score() {
+    let mut engine = engine();
+    // 三列分别属于黑、白、第三色, 第三色的一列在中间与两边都相邻
+    let responses = session(
+        &mut engine,
+        "boardsize 5\nnum_colors 3\nkomi 0.5\n\
+         play b A1\nplay b A2\nplay b A3\nplay b A4\nplay b A5\n\
+         play c3 C1\nplay c3 C2\nplay c3 C3\nplay c3 C4\nplay c3 C5\n\
+         play w E1\nplay w E2\nplay w E3\nplay w E4\nplay w E5\n\
+         final_score\n",
+    );
+    // 黑 5, 白 5.5, 第三色 5.5, 中间的空点两边都不算
+    assert_eq!(responses[18], "= 0");
+
+    let responses = session(&mut engine, "play c3 B1\nfinal_score\n");
+    assert_eq!(responses[1], "= C3+1");
+}
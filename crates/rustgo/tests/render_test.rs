@@ -0,0 +1,158 @@
+use rustgo::{
+    Coord, Stone,
+    board::Board,
+    game_tree::{GameTree, Markup, MarkupKind},
+    render::{self, Annotations, RenderStyle},
+    sgf,
+};
+
+const BLACK: Stone = Stone::BLACK;
+const WHITE: Stone = Stone::WHITE;
+
+fn count(svg: &str, pattern: &str) -> usize {
+    svg.matches(pattern).count()
+}
+
+#[test]
+fn test_star_points() {
+    assert_eq!(render::star_points(19).len(), 9);
+    assert!(render::star_points(19).contains(&Coord::new(3, 9)));
+    assert_eq!(render::star_points(13).len(), 5);
+    assert!(render::star_points(13).contains(&Coord::new(6, 6)));
+    assert_eq!(render::star_points(9).len(), 5);
+    assert!(render::star_points(9).contains(&Coord::new(2, 6)));
+    assert_eq!(render::star_points(14).len(), 4);
+    assert_eq!(render::star_points(5), vec![Coord::new(2, 2)]);
+    assert!(render::star_points(3).is_empty());
+    assert!(render::star_points(4).is_empty());
+    assert_eq!(render::star_points(25).len(), 9);
+}
+
+#[test]
+fn test_column_label() {
+    assert_eq!(render::column_label(0), "A");
+    assert_eq!(render::column_label(7), "H");
+    assert_eq!(render::column_label(8), "J");
+    assert_eq!(render::column_label(18), "T");
+    assert_eq!(render::column_label(24), "Z");
+    assert_eq!(render::column_label(25), "26");
+}
+
+#[test]
+fn test_stones_and_palette() {
+    let mut board = Board::new(9);
+    board.place_stone(Coord::new(2, 2), BLACK).unwrap();
+    board.place_stone(Coord::new(6, 6), WHITE).unwrap();
+    board.place_stone(Coord::new(4, 4), Stone::new(3)).unwrap();
+
+    let style = RenderStyle::default();
+    let svg = render::board_svg(&board, &Annotations::default(), &style);
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>\n"));
+    assert_eq!(count(&svg, r##"fill="#d62728""##), 1);
+    assert_eq!(count(&svg, r##"fill="#ffffff" stroke"##), 1);
+    // 9 路有 9 条横线和 9 条竖线, 5 个星位
+    assert_eq!(count(&svg, "<line "), 18);
+    assert_eq!(count(&svg, r#"r="3.2""#), 5);
+    // 四周的坐标
+    assert_eq!(count(&svg, ">J</text>"), 2);
+    assert_eq!(count(&svg, ">9</text>"), 2);
+
+    let style = RenderStyle {
+        coordinates: false,
+        palette: vec![render::StoneColor::new("red", "red", "white")],
+        ..Default::default()
+    };
+    let svg = render::board_svg(&board, &Annotations::default(), &style);
+    assert_eq!(count(&svg, "</text>"), 0);
+    // 颜色比 palette 多时循环使用
+    assert_eq!(count(&svg, r#"fill="red""#), 3);
+}
+
+#[test]
+fn test_annotations() {
+    let mut board = Board::new(5);
+    board.place_stone(Coord::new(0, 1), BLACK).unwrap();
+    board.place_stone(Coord::new(0, 0), WHITE).unwrap();
+    board.place_stone(Coord::new(1, 0), BLACK).unwrap();
+
+    let style = RenderStyle {
+        coordinates: false,
+        ..Default::default()
+    };
+    let mut annotations = Annotations {
+        last_move: Some(Coord::new(1, 0)),
+        move_numbers: vec![(Coord::new(0, 1), 1), (Coord::new(0, 0), 2)],
+        ..Default::default()
+    };
+    let svg = render::board_svg(&board, &annotations, &style);
+    // 被提走的 2 不画
+    assert_eq!(count(&svg, ">1</text>"), 1);
+    assert_eq!(count(&svg, ">2</text>"), 0);
+    assert_eq!(count(&svg, r##"fill="none" stroke="#ffffff""##), 1);
+
+    // 最后一手有手数时不画圆圈
+    annotations.move_numbers.push((Coord::new(1, 0), 3));
+    let svg = render::board_svg(&board, &annotations, &style);
+    assert_eq!(count(&svg, ">3</text>"), 1);
+    assert_eq!(count(&svg, r#"fill="none""#), 0);
+
+    let mut ownership = vec![(Stone::VOID, 0.0); 25];
+    ownership[0] = (BLACK, 0.9);
+    ownership[24] = (WHITE, 0.5);
+    let annotations = Annotations {
+        ownership: Some(ownership),
+        markup: vec![
+            Markup {
+                coord: Coord::new(2, 2),
+                kind: MarkupKind::Triangle,
+            },
+            Markup {
+                coord: Coord::new(3, 3),
+                kind: MarkupKind::Label("<a&b>".to_string()),
+            },
+        ],
+        ..Default::default()
+    };
+    let svg = render::board_svg(&board, &annotations, &style);
+    assert_eq!(count(&svg, "fill-opacity"), 2);
+    assert_eq!(count(&svg, "<polygon "), 1);
+    assert!(svg.contains(">&lt;a&amp;b&gt;</text>"));
+}
+
+#[test]
+fn test_tree_svg() {
+    let text = include_str!("data/synthetic_9x9.sgf");
+    let mut tree = GameTree::from_sgf(&sgf::parse_game(text).unwrap()).unwrap();
+    tree.go_to_move(22);
+    let stones = tree
+        .board()
+        .board_array()
+        .iter()
+        .filter(|&&s| s != Stone::VOID)
+        .count();
+
+    let style = RenderStyle::default();
+    let svg = render::tree_svg(&tree, &style, true);
+    // 每颗棋子上都有手数, 再加上 4 * 9 个坐标
+    assert_eq!(count(&svg, "</text>"), stones + 4 * 9);
+    assert!(svg.contains(">22</text>"));
+
+    let svg = render::tree_svg(&tree, &style, false);
+    assert_eq!(count(&svg, "</text>"), 4 * 9);
+    assert_eq!(count(&svg, r#"fill="none""#), 1);
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn test_png() {
+    let board = Board::new(9);
+    let style = RenderStyle::default();
+    let svg = render::board_svg(&board, &Annotations::default(), &style);
+    let png = render::svg_to_png(&svg).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    // IHDR 中的宽度
+    let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+    assert_eq!(width, 38 * 2 + 32 * 8);
+    assert!(svg.contains(r#"width="332""#));
+}
@@ -0,0 +1,81 @@
+use rustgo::{
+    Coord, Stone,
+    board::Board,
+    render::replay::{Replay, ReplayOptions},
+    sgf,
+};
+
+const BLACK: Stone = Stone::BLACK;
+const WHITE: Stone = Stone::WHITE;
+
+/// 棋子半径为格子的 0.47
+const STONE_R: &str = r#"r="15.04""#;
+
+fn count(svg: &str, pattern: &str) -> usize {
+    svg.matches(pattern).count()
+}
+
+#[test]
+fn test_svg_frames() {
+    let moves = vec![
+        (BLACK, Some(Coord::new(0, 1))),
+        (WHITE, Some(Coord::new(0, 0))),
+        (BLACK, None),
+        (WHITE, Some(Coord::new(4, 4))),
+        (BLACK, Some(Coord::new(1, 0))),
+    ];
+    let replay = Replay::new(Board::new(5), moves);
+    let options = ReplayOptions {
+        frame_ms: 1000,
+        ..Default::default()
+    };
+    let svg = replay.to_svg(&options).unwrap();
+
+    // 4 颗棋子, 再加上第 5 手提子时的半透明棋子
+    assert_eq!(count(&svg, STONE_R), 5);
+    assert_eq!(count(&svg, r#"opacity="0.35""#), 1);
+    // 第 5 手时白子被提走, 第 4 手的标记也同时消失
+    assert_eq!(count(&svg, r#"to="hidden" begin="5s""#), 2);
+    // 提子标记在第 6 秒消失, 之后再没有动画
+    assert_eq!(count(&svg, r#"to="hidden" begin="6s""#), 1);
+    // pass 没有最后一手的标记: 4 个标记, 除了最后一个都会消失
+    assert_eq!(count(&svg, r#"fill="none""#), 4);
+    assert_eq!(count(&svg, "to=\"visible\""), 4 + 1 + 4);
+}
+
+#[test]
+fn test_sgf_replay() {
+    let text = include_str!("data/synthetic_19x19_handicap.sgf");
+    let replay = Replay::from_sgf(&sgf::parse_game(text).unwrap());
+    let svg = replay.to_svg(&ReplayOptions::default()).unwrap();
+    let placed = replay.moves().iter().filter(|(_, c)| c.is_some()).count();
+    // 4 颗让子一开始就显示
+    assert_eq!(count(&svg, STONE_R), placed + 4);
+    assert_eq!(count(&svg, r#"<g visibility="hidden">"#), placed * 2);
+
+    let text = include_str!("data/three_colors.sgf");
+    let replay = Replay::from_sgf(&sgf::parse_game(text).unwrap());
+    let svg = replay.to_svg(&ReplayOptions::default()).unwrap();
+    assert_eq!(count(&svg, r##"fill="#d62728""##), 6);
+}
+
+#[test]
+fn test_illegal_move() {
+    let moves = vec![
+        (BLACK, Some(Coord::new(0, 0))),
+        (WHITE, Some(Coord::new(0, 0))),
+    ];
+    let replay = Replay::new(Board::new(5), moves);
+    assert!(replay.to_svg(&ReplayOptions::default()).is_err());
+}
+
+#[cfg(feature = "gif")]
+#[test]
+fn test_gif() {
+    let text = include_str!("data/synthetic_9x9.sgf");
+    let replay = Replay::from_sgf(&sgf::parse_game(text).unwrap());
+    let gif = replay.to_gif(&ReplayOptions::default()).unwrap();
+    assert_eq!(&gif[..6], b"GIF89a");
+    assert!(gif.windows(11).any(|w| w == b"NETSCAPE2.0"));
+    assert_eq!(gif.last(), Some(&0x3b));
+}
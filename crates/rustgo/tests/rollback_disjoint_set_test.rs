@@ -0,0 +1,124 @@
+use rand::{RngExt, SeedableRng, rngs::StdRng};
+use rustgo::{DisjointSet, RollbackDisjointSet};
+
+type TestType = u8;
+const TEST_SIZE: usize = (TestType::MAX / 2 - 1) as usize;
+
+#[test]
+fn test_empty() {
+    let ds = RollbackDisjointSet::<TestType>::new(TEST_SIZE);
+    for i in 0..ds.capacity() {
+        assert!(!ds.contains(i));
+        assert!(ds.find_root(i).is_none());
+        assert!(ds.group_members(i).is_none());
+        assert!(ds.group_size(i) == 0);
+        assert!(!ds.is_connected(i, i));
+    }
+
+    assert!(ds.capacity() == TEST_SIZE);
+    assert!(ds.is_empty());
+    assert!(ds.group_roots().is_empty());
+}
+
+#[test]
+fn test_connect_delete() {
+    let mut ds = RollbackDisjointSet::<TestType>::new(TEST_SIZE);
+    let half = ds.capacity() / 2;
+    for i in 0..half {
+        ds.connect(i, i + half);
+    }
+    for i in 0..half {
+        assert!(ds.find_root(i) == ds.find_root(i + half));
+        assert!(ds.group_members(i) == Some(vec![i, i + half]));
+        assert!(ds.group_size(i + half) == 2);
+        assert!(ds.is_connected(i + half, i));
+    }
+    assert!(ds.len() == half);
+    assert!(ds.group_roots().len() == half);
+
+    for i in half..half * 2 {
+        assert!(ds.delete_group(i).unwrap() == vec![i - half, i]);
+    }
+    for i in 0..ds.capacity() {
+        assert!(!ds.contains(i));
+        assert!(ds.find_root(i).is_none());
+    }
+    assert!(ds.is_empty());
+}
+
+#[test]
+fn test_rollback() {
+    let mut ds = RollbackDisjointSet::<TestType>::new(TEST_SIZE);
+    ds.connect(0, 1);
+    ds.connect(1, 2);
+    let checkpoint = ds.checkpoint();
+
+    ds.connect(2, 3);
+    ds.connect(10, 11);
+    ds.connect(3, 10);
+    assert!(ds.group_members(0) == Some(vec![0, 1, 2, 3, 10, 11]));
+    assert!(ds.delete_group(11) == Some(vec![0, 1, 2, 3, 10, 11]));
+    ds.insert(0);
+    assert!(ds.len() == 1);
+    ds.insert(20);
+    assert!(ds.len() == 2);
+
+    ds.rollback(checkpoint);
+    assert!(ds.len() == 1);
+    assert!(ds.group_members(2) == Some(vec![0, 1, 2]));
+    assert!(!ds.contains(3));
+    assert!(!ds.contains(10));
+    assert!(!ds.is_connected(2, 3));
+
+    ds.rollback(ds.checkpoint());
+    ds.clear_history();
+    ds.rollback(ds.checkpoint());
+    assert!(ds.group_size(0) == 3);
+}
+
+/// 随机操作的结果与 DisjointSet 一致, 回滚之后与回滚点的状态一致
+#[test]
+fn test_random_against_disjoint_set() {
+    let mut rng = StdRng::seed_from_u64(114514);
+    let mut ds = RollbackDisjointSet::<TestType>::new(TEST_SIZE);
+    let mut reference = DisjointSet::<TestType>::new(TEST_SIZE);
+
+    for _ in 0..20 {
+        let checkpoint = ds.checkpoint();
+        let saved = reference.clone();
+
+        for _ in 0..50 {
+            let a = rng.random_range(0..TEST_SIZE);
+            let b = rng.random_range(0..TEST_SIZE);
+            match rng.random_range(0..3) {
+                0 => {
+                    ds.insert(a);
+                    reference.insert(a);
+                }
+                1 => {
+                    ds.connect(a, b);
+                    reference.connect(a, b);
+                }
+                _ => {
+                    assert!(ds.delete_group(a) == reference.delete_group(a));
+                }
+            }
+        }
+        assert_same(&ds, &mut reference.clone());
+
+        if rng.random_range(0..2) == 0 {
+            ds.rollback(checkpoint);
+            reference = saved;
+            assert_same(&ds, &mut reference.clone());
+        }
+    }
+}
+
+fn assert_same(ds: &RollbackDisjointSet<TestType>, reference: &mut DisjointSet<TestType>) {
+    assert!(ds.len() == reference.len());
+    for i in 0..TEST_SIZE {
+        assert!(ds.contains(i) == reference.contains(i));
+        assert!(ds.group_size(i) == reference.group_size(i));
+        assert!(ds.group_members(i).as_ref() == reference.group_members(i));
+    }
+}
@@ -0,0 +1,64 @@
+use rustgo::{
+    Coord, Stone,
+    board::{Board, BoardArray},
+    score,
+};
+
+const VOID: Stone = Stone::VOID;
+const BLACK: Stone = Stone::BLACK;
+const WHITE: Stone = Stone::WHITE;
+
+/// 右边一列里有一颗黑子
+fn board() -> Board {
+    #[rustfmt::skip]
+    let board: BoardArray = Box::new([
+        VOID, BLACK, WHITE, VOID,
+        VOID, BLACK, WHITE, VOID,
+        VOID, BLACK, WHITE, BLACK,
+        VOID, BLACK, WHITE, VOID,
+    ]);
+    return Board::new_with_board(4, board);
+}
+
+#[test]
+fn test_ownership() {
+    let board = board();
+    let owner = score::ownership(&board, &[]);
+    assert_eq!(&owner[0..4], [BLACK, BLACK, WHITE, VOID]);
+    assert_eq!(&owner[8..12], [BLACK, BLACK, WHITE, BLACK]);
+
+    let owner = score::ownership(&board, &[Coord::new(3, 2)]);
+    assert!((0..4).all(|y| owner[y * 4 + 3] == WHITE));
+}
+
+#[test]
+fn test_area_score() {
+    let board = board();
+    assert_eq!(score::area_score(&board, WHITE, &[]), vec![0, 9, 4]);
+    assert_eq!(
+        score::area_score(&board, WHITE, &[Coord::new(3, 2)]),
+        vec![0, 8, 8]
+    );
+    // 多色时其他颜色为 0
+    assert_eq!(
+        score::area_score(&board, Stone::new(3), &[]),
+        vec![0, 9, 4, 0]
+    );
+}
+
+#[test]
+fn test_territory_score() {
+    let board = board();
+    assert_eq!(score::territory_score(&board, WHITE, &[]), vec![0, 4, 0]);
+    // 死子所在的点算地, 死子本身算俘虏
+    assert_eq!(
+        score::territory_score(&board, WHITE, &[Coord::new(3, 2)]),
+        vec![0, 4, 5]
+    );
+
+    let mut board = board;
+    board.place_stone(Coord::new(3, 3), WHITE).unwrap();
+    board.place_stone(Coord::new(3, 1), WHITE).unwrap();
+    assert_eq!(board.captures(WHITE), 1);
+    assert_eq!(score::territory_score(&board, WHITE, &[]), vec![0, 4, 3]);
+}
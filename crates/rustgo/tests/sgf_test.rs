@@ -0,0 +1,350 @@
+use rustgo::{
+    Coord, Stone,
+    board::Board,
+    sgf::{self, GameInfo, SgfNode},
+};
+
+const BLACK: Stone = Stone::BLACK;
+const WHITE: Stone = Stone::WHITE;
+
+const GAME_9X9: &str = include_str!("data/synthetic_9x9.sgf");
+const GAME_19X19: &str = include_str!("data/synthetic_19x19_handicap.sgf");
+const COLLECTION: &str = include_str!("data/collection.sgf");
+/// SGF FF[4] 规范中的示例棋谱: 第21期名人战 武宫正树 - 赵治勋, 带有多个参考图
+const FF4_EXAMPLE: &str = include_str!("data/ff4_example.sgf");
+
+fn count_nodes(node: &SgfNode) -> usize {
+    1 + node.children.iter().map(count_nodes).sum::<usize>()
+}
+
+#[test]
+fn test_root_properties() {
+    let root = sgf::parse_game(GAME_9X9).unwrap();
+    let info = root.game_info();
+    assert_eq!(info.size, 9);
+    assert_eq!(info.komi, Some(6.5));
+    assert_eq!(info.rules.as_deref(), Some("Japanese"));
+    assert_eq!(info.handicap, None);
+    assert_eq!(info.num_colors, 2);
+    assert_eq!(info.player(BLACK), Some("Alice"));
+    assert_eq!(info.player(WHITE), Some("Bob"));
+    assert_eq!(info.result.as_deref(), Some("B+R"));
+
+    let root = sgf::parse_game(GAME_19X19).unwrap();
+    let info = root.game_info();
+    assert_eq!(info.size, 19);
+    assert_eq!(info.handicap, Some(4));
+    assert_eq!(info.komi, Some(0.5));
+    assert_eq!(info.player(BLACK), Some("Alice Black"));
+}
+
+#[test]
+fn test_comment_escape() {
+    let root = sgf::parse_game(GAME_9X9).unwrap();
+    assert_eq!(
+        root.comment(),
+        Some("Teaching game, 9x9.\nComments may contain ] and \\ and a soft line break.")
+    );
+}
+
+#[test]
+fn test_variations() {
+    let root = sgf::parse_game(GAME_9X9).unwrap();
+    let line = root.main_line();
+    // 第5手之后分成两个变化
+    let branch = line[5];
+    assert_eq!(branch.children.len(), 2);
+    assert_eq!(branch.children[0].comment(), Some("Standard shape."));
+    assert_eq!(branch.children[1].comment(), Some("Too slow."));
+    assert_eq!(
+        branch.children[1].sgf_move(9),
+        Some((WHITE, Some(Coord::new(6, 5))))
+    );
+    // 第二个变化里面又有两个变化
+    let sub = branch.children[1].main_line();
+    assert_eq!(sub.len(), 4);
+    assert_eq!(sub[2].children.len(), 2);
+    assert_eq!(count_nodes(&root), 1 + 5 + 20 + 3 + 1 + 3);
+}
+
+#[test]
+fn test_passes() {
+    let root = sgf::parse_game(GAME_9X9).unwrap();
+    let moves = root.main_line_moves();
+    assert_eq!(moves.len(), 25);
+    assert_eq!(moves[0], (BLACK, Some(Coord::new(4, 4))));
+    assert_eq!(moves[23], (WHITE, None));
+    assert_eq!(moves[24], (BLACK, None));
+
+    // 19 路以内 "tt" 也是 pass
+    let root = sgf::parse_game(GAME_19X19).unwrap();
+    let moves = root.main_line_moves();
+    assert_eq!(moves[moves.len() - 2], (WHITE, None));
+    assert_eq!(moves[moves.len() - 1], (BLACK, None));
+}
+
+#[test]
+fn test_setup() {
+    let root = sgf::parse_game(GAME_19X19).unwrap();
+    let setup = root.setup(19);
+    assert_eq!(
+        setup,
+        vec![
+            (BLACK, Coord::new(3, 3)),
+            (BLACK, Coord::new(15, 3)),
+            (BLACK, Coord::new(3, 15)),
+            (BLACK, Coord::new(15, 15)),
+        ]
+    );
+
+    // 压缩的矩形
+    let root = sgf::parse_game("(;SZ[5]AB[aa:bc]AW[ee]AE[cc])").unwrap();
+    let setup = root.setup(5);
+    assert_eq!(setup.len(), 6 + 1 + 1);
+    assert_eq!(setup[5], (BLACK, Coord::new(1, 2)));
+    assert_eq!(setup[7], (Stone::VOID, Coord::new(2, 2)));
+}
+
+#[test]
+fn test_replay() {
+    let root = sgf::parse_game(GAME_9X9).unwrap();
+    let board = sgf::replay_main_line(&root).unwrap();
+    // B[ib] 提走了角上的白子
+    assert_eq!(board.captures(BLACK), 1);
+    assert_eq!(board.stone(Coord::new(8, 0)), Stone::VOID);
+    assert_eq!(board.stone(Coord::new(8, 1)), BLACK);
+
+    let root = sgf::parse_game(GAME_19X19).unwrap();
+    let board = sgf::replay_main_line(&root).unwrap();
+    assert_eq!(board.stone(Coord::new(3, 3)), BLACK);
+    assert_eq!(board.stone(Coord::new(13, 2)), WHITE);
+
+    for root in sgf::parse(COLLECTION).unwrap() {
+        sgf::replay_main_line(&root).unwrap();
+    }
+}
+
+#[test]
+fn test_replay_setup_in_middle() {
+    // 中途用 AE 清除棋子后再落子
+    let root = sgf::parse_game("(;SZ[3];B[bb];AE[bb]W[bb];B[aa])").unwrap();
+    let board = sgf::replay_main_line(&root).unwrap();
+    assert_eq!(board.stone(Coord::new(1, 1)), WHITE);
+    assert_eq!(board.stone(Coord::new(0, 0)), BLACK);
+
+    let root = sgf::parse_game("(;SZ[3];B[bb];W[bb])").unwrap();
+    assert!(sgf::replay_main_line(&root).is_err());
+}
+
+#[test]
+fn test_round_trip() {
+    for text in [GAME_9X9, GAME_19X19, COLLECTION, FF4_EXAMPLE] {
+        for root in sgf::parse(text).unwrap() {
+            let written = sgf::to_sgf(&root);
+            let reparsed = sgf::parse_game(&written).unwrap();
+            assert_eq!(reparsed, root);
+            assert_eq!(sgf::to_sgf(&reparsed), written);
+        }
+    }
+}
+
+#[test]
+fn test_real_game() {
+    let root = sgf::parse_game(FF4_EXAMPLE).unwrap();
+    let info = root.game_info();
+    assert_eq!(info.size, 19);
+    assert_eq!(info.komi, Some(5.5));
+    assert_eq!(info.player(BLACK), Some("Takemiya Masaki"));
+    assert_eq!(info.player(WHITE), Some("Cho Chikun"));
+    assert_eq!(info.result.as_deref(), Some("W+Resign"));
+    // 客户端专用的属性原样保留
+    assert_eq!(root.get("FG"), Some("257:Figure 1"));
+    assert_eq!(root.get("US"), Some("Arno Hollosi"));
+
+    assert_eq!(root.main_line_moves().len(), 101);
+    let board = sgf::replay_main_line(&root).unwrap();
+    assert_eq!(board.stone(Coord::new(10, 12)), BLACK);
+
+    // CRLF 换行的文件读出同样的棋谱
+    let crlf = FF4_EXAMPLE.replace('\n', "\r\n");
+    assert_eq!(sgf::parse_game(&crlf).unwrap(), root);
+
+    let reparsed = sgf::parse_game(&sgf::to_sgf(&root)).unwrap();
+    assert_eq!(count_nodes(&reparsed), count_nodes(&root));
+    let board_again = sgf::replay_main_line(&reparsed).unwrap();
+    assert_eq!(board_again.board_array(), board.board_array());
+}
+
+#[test]
+fn test_collection() {
+    let games = sgf::parse(COLLECTION).unwrap();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].game_info().player(BLACK), Some("Alice"));
+    assert_eq!(games[1].game_info().player(BLACK), Some("Bob"));
+    assert_eq!(games[1].main_line_moves().len(), 7);
+}
+
+#[test]
+fn test_write_new_game() {
+    let mut root = SgfNode::new();
+    let mut info = GameInfo::new(9, 2);
+    info.komi = Some(7.0);
+    info.rules = Some("Chinese".to_string());
+    info.players = vec![Some("a]b".to_string()), Some("c\\d".to_string())];
+    root.set_game_info(&info);
+    root.add_setup(BLACK, Coord::new(2, 2));
+    root.add_setup(BLACK, Coord::new(6, 6));
+
+    let moves = [
+        (WHITE, Some(Coord::new(4, 4))),
+        (BLACK, Some(Coord::new(4, 3))),
+        (WHITE, None),
+    ];
+    let mut nodes: Vec<SgfNode> = moves
+        .iter()
+        .map(|&(stone, coord)| {
+            let mut node = SgfNode::new();
+            node.set_move(stone, coord);
+            node
+        })
+        .collect();
+    nodes[1].set_comment("attach");
+    while let Some(node) = nodes.pop() {
+        match nodes.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => root.children.push(node),
+        }
+    }
+
+    let text = sgf::to_sgf(&root);
+    assert!(text.contains("PB[a\\]b]PW[c\\\\d]"));
+    assert!(text.contains("W[]"));
+
+    let parsed = sgf::parse_game(&text).unwrap();
+    assert_eq!(parsed, root);
+    assert_eq!(parsed.main_line_moves(), moves);
+
+    let board = sgf::replay_main_line(&parsed).unwrap();
+    let mut expected = Board::new(9);
+    for coord in [Coord::new(2, 2), Coord::new(6, 6)] {
+        let mut b_array = expected.board_array().to_vec();
+        b_array[expected.idx(coord)] = BLACK;
+        expected = Board::new_with_board(9, b_array.into_boxed_slice());
+    }
+    expected.place_stone(Coord::new(4, 4), WHITE).unwrap();
+    expected.place_stone(Coord::new(4, 3), BLACK).unwrap();
+    assert_eq!(board.board_string(), expected.board_string());
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(sgf::parse("").is_err());
+    assert!(sgf::parse("(;B[aa]").is_err());
+    assert!(sgf::parse("(;B[aa").is_err());
+    assert!(sgf::parse("()").is_err());
+    assert!(sgf::parse("(;B)").is_err());
+
+    let err = sgf::parse("(;SZ[9];B[aa]").unwrap_err();
+    assert_eq!(err.position, 13);
+}
+
+#[test]
+fn test_ff3_lowercase_ident() {
+    let root = sgf::parse_game("(;GaMe[1]SiZe[9]\n;Black[ee] ; White[cc])").unwrap();
+    assert_eq!(root.size(), 9);
+    assert_eq!(root.get("GM"), Some("1"));
+    assert_eq!(
+        root.main_line_moves(),
+        vec![
+            (BLACK, Some(Coord::new(4, 4))),
+            (WHITE, Some(Coord::new(2, 2)))
+        ]
+    );
+}
+
+const THREE_COLORS: &str = include_str!("data/three_colors.sgf");
+
+#[test]
+fn test_color_ident() {
+    assert_eq!(sgf::color_ident(BLACK).as_deref(), Some("B"));
+    assert_eq!(sgf::color_ident(WHITE).as_deref(), Some("W"));
+    assert_eq!(sgf::color_ident(Stone::new(3)).as_deref(), Some("XC"));
+    assert_eq!(sgf::color_ident(Stone::new(6)).as_deref(), Some("XF"));
+    assert_eq!(sgf::color_ident(Stone::VOID), None);
+    for n in 1..=sgf::MAX_COLORS as u8 {
+        let stone = Stone::new(n);
+        let ident = sgf::color_ident(stone).unwrap();
+        assert_eq!(sgf::ident_color(&ident), Some(stone));
+    }
+    assert_eq!(sgf::ident_color("XA"), None);
+    assert_eq!(sgf::ident_color("XG"), None);
+    assert_eq!(sgf::ident_color("C"), None);
+}
+
+#[test]
+fn test_multi_color_read() {
+    let root = sgf::parse_game(THREE_COLORS).unwrap();
+    let info = root.game_info();
+    assert_eq!(info.num_colors, 3);
+    assert_eq!(info.player(Stone::new(3)), Some("Carol"));
+    assert_eq!(info.team(BLACK), Some("Red"));
+    assert_eq!(info.team(Stone::new(3)), Some("Green"));
+    assert_eq!(root.setup(9), vec![(Stone::new(3), Coord::new(4, 4))]);
+
+    let moves = root.main_line_moves();
+    assert_eq!(moves.len(), 15);
+    assert_eq!(moves[2], (Stone::new(3), Some(Coord::new(2, 6))));
+    assert_eq!(moves[10], (WHITE, None));
+
+    let board = sgf::replay_main_line(&root).unwrap();
+    assert_eq!(board.stone(Coord::new(4, 4)), Stone::new(3));
+    assert_eq!(board.stone(Coord::new(5, 3)), Stone::new(3));
+}
+
+#[test]
+fn test_multi_color_write() {
+    let mut root = SgfNode::new();
+    let mut info = GameInfo::new(7, 4);
+    info.players = (1..=4).map(|i| Some(format!("p{i}"))).collect();
+    info.teams = vec![Some("t1".to_string()), None, None, Some("t4".to_string())];
+    root.set_game_info(&info);
+    root.add_setup(Stone::new(4), Coord::new(3, 3));
+
+    let mut node = SgfNode::new();
+    node.set_move(Stone::new(3), Some(Coord::new(1, 1)));
+    // 覆盖掉之前的落子
+    node.set_move(Stone::new(4), Some(Coord::new(2, 1)));
+    root.children.push(node);
+
+    let text = sgf::to_sgf(&root);
+    assert!(text.contains("NC[4]"));
+    assert!(text.contains("PXD[p4]XDT[t4]"));
+    assert!(text.contains("AXD[dd]"));
+    assert!(text.contains(";XD[cb]"));
+    assert!(!text.contains("XC[bb]"));
+
+    let parsed = sgf::parse_game(&text).unwrap();
+    assert_eq!(parsed, root);
+    assert_eq!(parsed.game_info(), info);
+
+    for text in [THREE_COLORS, &text] {
+        let root = sgf::parse_game(text).unwrap();
+        assert_eq!(sgf::parse_game(&sgf::to_sgf(&root)).unwrap(), root);
+    }
+}
+
+#[test]
+fn test_two_colors_degrade() {
+    let mut root = SgfNode::new();
+    let mut info = GameInfo::new(9, 2);
+    info.players = vec![Some("a".to_string()), Some("b".to_string())];
+    info.teams = vec![Some("x".to_string()), Some("y".to_string())];
+    root.set_game_info(&info);
+    let mut node = SgfNode::new();
+    node.set_move(WHITE, Some(Coord::new(0, 0)));
+    root.children.push(node);
+
+    let text = sgf::to_sgf(&root);
+    assert_eq!(text, "(;GM[1]FF[4]SZ[9]PB[a]BT[x]PW[b]WT[y]\n;W[aa])\n");
+    assert_eq!(sgf::parse_game(&text).unwrap().game_info(), info);
+}
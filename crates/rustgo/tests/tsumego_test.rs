@@ -0,0 +1,154 @@
+use rustgo::{
+    Coord, Stone,
+    board::{Board, BoardArray},
+    tsumego::{Goal, Tsumego, TsumegoStatus, unconditionally_alive},
+};
+
+const V: Stone = Stone::VOID;
+const B: Stone = Stone::BLACK;
+const W: Stone = Stone::WHITE;
+
+/// 白棋在上边有直三的眼位, 外围被黑棋包住
+fn straight_three() -> Board {
+    #[rustfmt::skip]
+    let board: BoardArray = Box::new([
+        V, V, V, W, B,
+        W, W, W, W, B,
+        B, B, B, B, B,
+        V, V, V, V, V,
+        V, V, V, V, V,
+    ]);
+    Board::new_with_board(5, board)
+}
+
+/// 白棋在上边有直四的眼位, 外围被黑棋包住
+fn straight_four() -> Board {
+    #[rustfmt::skip]
+    let board: BoardArray = Box::new([
+        V, V, V, V, W, B,
+        W, W, W, W, W, B,
+        B, B, B, B, B, B,
+        V, V, V, V, V, V,
+        V, V, V, V, V, V,
+        V, V, V, V, V, V,
+    ]);
+    Board::new_with_board(6, board)
+}
+
+fn top_row(n: usize) -> Vec<Coord> {
+    (0..n).map(|x| Coord::new(x, 0)).collect()
+}
+
+#[test]
+fn test_invalid_problem() {
+    let res = Tsumego::new(
+        straight_three(),
+        top_row(3),
+        vec![Coord::new(4, 0)],
+        Stone::BLACK,
+        Goal::Kill,
+    );
+    assert!(res.is_err());
+
+    let res = Tsumego::new(
+        straight_three(),
+        vec![],
+        vec![Coord::new(3, 0)],
+        Stone::BLACK,
+        Goal::Kill,
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_black_to_kill_straight_three() {
+    let problem = Tsumego::new(
+        straight_three(),
+        top_row(3),
+        vec![Coord::new(3, 0)],
+        Stone::BLACK,
+        Goal::Kill,
+    )
+    .unwrap();
+    let solution = problem.solve();
+    assert_eq!(solution.status, TsumegoStatus::Solved);
+    let first = solution.main_line[0].unwrap();
+    assert_eq!((first.x, first.y), (1, 0));
+
+    // 点边上的两个点都会被白棋在中间做活
+    for refutation in &solution.refutations {
+        if let Some(coord) = refutation.try_move {
+            assert_ne!((coord.x, coord.y), (1, 0));
+            let answer = refutation.line[0].unwrap();
+            assert_eq!((answer.x, answer.y), (1, 0));
+        }
+    }
+    assert!(!solution.refutations.is_empty());
+}
+
+#[test]
+fn test_white_to_live_straight_three() {
+    let problem = Tsumego::new(
+        straight_three(),
+        top_row(3),
+        vec![Coord::new(3, 0)],
+        Stone::WHITE,
+        Goal::Live,
+    )
+    .unwrap();
+    let solution = problem.solve();
+    assert_eq!(solution.status, TsumegoStatus::Solved);
+    let first = solution.main_line[0].unwrap();
+    assert_eq!((first.x, first.y), (1, 0));
+}
+
+#[test]
+fn test_black_cannot_kill_straight_four() {
+    let problem = Tsumego::new(
+        straight_four(),
+        top_row(4),
+        vec![Coord::new(4, 0)],
+        Stone::BLACK,
+        Goal::Kill,
+    )
+    .unwrap();
+    let solution = problem.solve();
+    assert_eq!(solution.status, TsumegoStatus::Failed);
+    assert!(solution.main_line.is_empty());
+    // 每一个尝试都有白棋的应对
+    assert_eq!(solution.refutations.len(), 5);
+}
+
+#[test]
+fn test_node_limit() {
+    let problem = Tsumego::new(
+        straight_four(),
+        top_row(4),
+        vec![Coord::new(4, 0)],
+        Stone::BLACK,
+        Goal::Kill,
+    )
+    .unwrap()
+    .with_max_nodes(3);
+    let solution = problem.solve();
+    assert_eq!(solution.status, TsumegoStatus::Unknown);
+}
+
+#[test]
+fn test_unconditionally_alive() {
+    #[rustfmt::skip]
+    let board: BoardArray = Box::new([
+        V, W, V, W, B,
+        W, W, W, W, B,
+        B, B, B, B, B,
+        V, V, V, V, V,
+        V, V, V, V, V,
+    ]);
+    let board = Board::new_with_board(5, board);
+    let alive = unconditionally_alive(&board, W);
+    assert!(alive[board.idx(Coord::new(3, 0))]);
+    assert!(!alive[board.idx(Coord::new(4, 0))]);
+
+    let alive = unconditionally_alive(&straight_three(), W);
+    assert!(alive.iter().all(|&a| !a));
+}